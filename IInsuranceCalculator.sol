@@ -0,0 +1,1379 @@
+// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+// Generated by scripts/gen_interface.py from src/lib.rs; regenerate rather than editing by hand.
+
+/// Canonical interface of the insurance calculator: every external function, error and event.
+/// Its ERC-165 id, 0x5fa5751e, is the XOR of the pricing function selectors only:
+/// `calculateInsuranceFee`, `calculateInsuranceFeeNow`, `calculatePortfolioFee`,
+/// `calculateFlashLoanFee`, `calculatePoolFlashLoanFee`, `quoteCoveragePremium`.
+interface IInsuranceCalculator {
+    /// Global parameters governance applies as one set: (protocol_fee_bps, cancellation_penalty_bps,
+    /// target_reserve_ratio, skim_incentive_bps, stale_threshold, unrated_default_il,
+    /// max_il_deviation_bps, quote_validity, max_effective_il, max_default_history, grace_duration,
+    /// grace_factor), each in the units of its individual setter with zero meaning the same default
+    struct ParameterBundle {
+        uint256 protocol_fee_bps;
+        uint256 cancellation_penalty_bps;
+        uint256 target_reserve_ratio;
+        uint256 skim_incentive_bps;
+        uint256 stale_threshold;
+        uint256 unrated_default_il;
+        uint256 max_il_deviation_bps;
+        uint256 quote_validity;
+        uint256 max_effective_il;
+        uint256 max_default_history;
+        uint256 grace_duration;
+        uint256 grace_factor;
+    }
+
+    /// Math calculation error
+    error CalculationError();
+    /// Invalid input parameters
+    error InvalidInput();
+    /// Caller lacks the required role
+    error Unauthorized(address caller);
+    /// Caller lacks the required role for this pool
+    error UnauthorizedForPool(address caller, bytes32 pool_id);
+    /// Owner has already been set
+    error AlreadyInitialized();
+    /// Requested amount exceeds the tracked balance
+    error InsufficientBalance(uint256 available, uint256 requested);
+    /// Native ETH transfer was rejected
+    error TransferFailed();
+    /// Withdrawal would leave the pool below its minimum reserve
+    error MinimumReserveBreached(uint256 reserves, uint256 minimum, uint256 requested);
+    /// Attached value does not cover the required amount
+    error InsufficientPayment(uint256 required, uint256 provided);
+    /// Policy is not in a state that allows this action
+    error PolicyNotActive(uint256 policy_id);
+    /// Policy is still inside its claim waiting period
+    error ClaimTooEarly(uint256 policy_id, uint256 claimable_at);
+    /// Pool reserves do not exceed what its coverage requires
+    error NoExcessReserves(uint256 reserves, uint256 required);
+    /// Pool id has already been registered
+    error PoolAlreadyRegistered(bytes32 pool_id);
+    /// Pool id must be registered before quoting
+    error PoolNotRegistered(bytes32 pool_id);
+    /// No quote was issued under this id
+    error QuoteNotFound(bytes32 quote_id);
+    /// Quote validity window has passed
+    error QuoteExpired(bytes32 quote_id, uint256 expiry);
+    /// Quote has already been settled
+    error QuoteAlreadySettled(bytes32 quote_id);
+    /// Revealed parameters do not match any commitment
+    error CommitmentMismatch(bytes32 commitment);
+    /// Reveal landed in the same block as its commitment
+    error RevealTooEarly(uint256 commit_block, uint256 current_block);
+    /// Reveal window for the commitment has passed
+    error CommitmentExpired(uint256 commit_block, uint256 current_block);
+    /// Pool data is still within the staleness threshold
+    error PoolNotStale(uint256 last_update, uint256 stale_threshold);
+    /// Coverage purchase would push the pool past its exposure cap
+    error ExposureCapExceeded(uint256 current, uint256 cap, uint256 requested);
+    /// IL update moves the stored value further than the deviation band allows
+    error DeviationTooLarge(uint256 old_value, uint256 proposed);
+    /// IL update carries a sequence number at or below the pool's latest
+    error StaleSequence(uint256 stored, uint256 provided);
+    /// Neither the pool nor the contract has a fee recipient configured
+    error NoFeeRecipient(bytes32 pool_id);
+    /// Pool has no price feed configured
+    error NoOracle(bytes32 pool_id);
+    /// Data is older than its maximum age
+    error StaleData(uint256 age, uint256 max_age);
+    /// Price feed reverted or answered with a non-positive price
+    error InvalidOracleResponse(address feed);
+    /// Pool has no data provider registered
+    error NoDataProvider(bytes32 pool_id);
+    /// Pool has been deactivated and no longer quotes
+    error PoolInactive(bytes32 pool_id);
+    /// Premium stream does not exist or has been closed
+    error StreamNotActive(uint256 stream_id);
+    /// Prepaid coverage budget cannot cover a swap's insurance fee
+    error InsufficientBudget(uint256 shortfall);
+    /// Updater's bond is below the minimum required to write IL
+    error InsufficientBond(uint256 bond, uint256 required);
+    /// Updater's bond is still within its cooldown after the last IL write
+    error BondLocked(uint256 unlocks_at);
+    /// Pool has no IL update waiting out its dispute window
+    error NoPendingIl(bytes32 pool_id);
+    /// Flash loan is larger than the pool's per-loan cap
+    error FlashLoanCapExceeded(uint256 cap, uint256 requested);
+    /// A self-check on a computed fee failed (builds with `invariant-checks` only)
+    error InvariantViolated(uint8 code);
+    /// Data provider reverted when queried
+    error DataProviderFailed(address provider);
+    /// Quoted price strays too far from the pool's reference price
+    error PriceCircuitBreaker(uint256 current_price, uint256 reference_price);
+    /// Emergency change would raise a fee or loosen a cap
+    error NotMoreConservative(uint256 current, uint256 proposed);
+    /// Fee at execution exceeds the caller's expected fee plus tolerance
+    error FeeSlippage(uint256 expected, uint256 actual);
+    /// IL update arrived before the pool's minimum update interval elapsed
+    error TooFrequent(uint256 remaining);
+
+    event ProtocolFeeUpdated(uint256 old_bps, uint256 new_bps);
+    event TreasuryUpdated(address indexed old_treasury, address indexed new_treasury);
+    event PremiumCollected(bytes32 indexed pool_id, uint256 to_reserves, uint256 to_treasury);
+    event TreasuryWithdrawn(address indexed to, uint256 amount);
+    event PoolRegistered(bytes32 indexed pool_id, address token0, address token1, uint256 fee_tier);
+    event RequireRegistrationSet(bool required);
+    event PoolOperatorSet(bytes32 indexed pool_id, address indexed operator);
+    event FlashFeeMultiplierSet(bytes32 indexed pool_id, uint256 multiplier);
+    event BaseFeeSet(bytes32 indexed pool_id, uint256 base_fee);
+    event UpdaterSet(address indexed updater, bool authorized);
+    event MinIlUpdateIntervalSet(uint256 interval);
+    event HistoricalIlUpdated(bytes32 indexed pool_id, uint256 old_il, uint256 new_il, address indexed updater);
+    event PoolMarkedUnrated(bytes32 indexed pool_id, address indexed caller, uint256 last_update);
+    event QuoteIssued(bytes32 indexed quote_id, bytes32 indexed pool_id, address indexed issuer, uint256 fee, uint256 expiry, uint256 epoch);
+    event QuoteSettled(bytes32 indexed quote_id, address indexed issuer, uint256 fee);
+    event QuoteCommitted(bytes32 indexed commitment, address indexed committer, uint256 commit_block);
+    event QuoteRevealed(bytes32 indexed commitment, bytes32 indexed pool_id, uint256 amount, uint256 fee, uint256 epoch);
+    event RevealWindowSet(uint256 blocks);
+    event PremiumPaid(bytes32 indexed pool_id, address indexed payer, uint256 fee, uint256 epoch);
+    event FundDeposited(bytes32 indexed pool_id, address indexed depositor, uint256 amount);
+    event FundWithdrawn(bytes32 indexed pool_id, address indexed to, uint256 amount);
+    event MinReserveSet(bytes32 indexed pool_id, uint256 minimum);
+    event CoveragePurchased(uint256 indexed policy_id, bytes32 indexed pool_id, address indexed holder, uint256 notional, uint256 premium, uint256 expiry, uint256 epoch);
+    event DeductibleAllowed(uint256 deductible, bool allowed);
+    event PolicyClaimed(uint256 indexed policy_id, address indexed holder, uint256 payout);
+    event PolicyExpired(uint256 indexed policy_id);
+    event ExpiryIncentivePaid(uint256 indexed policy_id, address indexed keeper, uint256 incentive);
+    event ExpiryIncentiveSet(uint256 bps);
+    event PolicyRenewed(uint256 indexed policy_id, uint256 premium, uint256 expiry, uint256 epoch);
+    event PolicyTransferred(uint256 indexed policy_id, address indexed old_holder, address indexed new_holder);
+    event PolicyCancelled(uint256 indexed policy_id, address indexed holder, uint256 refund);
+    event RefundWithdrawn(address indexed holder, uint256 amount);
+    event ReservesSkimmed(bytes32 indexed pool_id, address indexed caller, uint256 excess, uint256 incentive);
+    event HedgingFeeModeSet(bytes32 indexed pool_id, bool enabled, uint256 volatility);
+    event LoyaltyTiersUpdated(uint256[] thresholds, uint256[] discounts_bps);
+    event FlashFeeExemptSet(address indexed account, bool exempt);
+    event FeeHolidaySet(bytes32 indexed pool_id, uint256 until);
+    event RebateEpochClosed(bytes32 indexed pool_id, uint256 indexed epoch, uint256 projected_il, uint256 realized_il);
+    event MaxRebateBpsSet(uint256 bps);
+    event DustThresholdSet(bytes32 indexed pool_id, uint256 threshold);
+    event SwapRecorded(bytes32 indexed pool_id, address indexed trader, uint256 amount, uint256 fee, uint256 epoch);
+    event GracePeriodSet(uint256 duration, uint256 factor);
+    event SurgeParamsSet(bytes32 indexed pool_id, uint256 threshold, uint256 multiplier);
+    event LpFeesRecorded(bytes32 indexed pool_id, uint256 fees, uint256 fee_growth_global);
+    event MaxExposureSet(bytes32 indexed pool_id, uint256 cap);
+    event PoolHookSet(bytes32 indexed pool_id, address indexed hook);
+    event SwapPushed(bytes32 indexed pool_id, uint256 volume, uint256 price_after, uint256 il);
+    event ProductConfigured(bytes32 indexed pool_id, uint8 indexed product_id, bool enabled, uint256 base_fee, uint256 il_weight, uint256 size_weight);
+    event LenderSet(address indexed lender, bool authorized);
+    event DefaultScoreParamsSet(uint256 max_score, uint256 decay_bps);
+    event DefaultRecorded(bytes32 indexed pool_id, address indexed lender, uint256 severity, uint256 score);
+    event RepaymentRecorded(bytes32 indexed pool_id, address indexed lender, uint256 score);
+    event UtilizationCheckpointed(bytes32 indexed pool_id, address indexed lender, uint256 utilization, uint256 cumulative);
+    event TwuParamsSet(uint256 window, uint256 weight_bps);
+    event InsuranceShareSet(bytes32 indexed pool_id, uint256 share_bps);
+    event FlashLoanRecorded(bytes32 indexed pool_id, address indexed lender, uint256 amount, uint256 lp_portion, uint256 insurance_portion, uint256 epoch);
+    event FlashLpFeesWithdrawn(bytes32 indexed pool_id, address indexed to, uint256 amount);
+    event FeeRecipientSet(bytes32 indexed pool_id, address indexed recipient);
+    event DefaultFeeRecipientSet(address indexed old_recipient, address indexed new_recipient);
+    event PriceFeedSet(bytes32 indexed pool_id, address indexed feed);
+    event OracleMaxAgeSet(uint256 max_age);
+    event MaxEffectiveIlSet(uint256 cap);
+    event MaxDefaultHistorySet(uint256 cap);
+    event CancellationPenaltySet(uint256 bps);
+    event TargetReserveRatioSet(uint256 ratio);
+    event SkimIncentiveSet(uint256 bps);
+    event MaxIlDeviationSet(uint256 bps);
+    event StaleThresholdSet(uint256 threshold);
+    event UnratedDefaultIlSet(uint256 il);
+    event QuoteValiditySet(uint256 validity);
+    event ParametersUpdated(uint256 indexed epoch, uint256 protocol_fee_bps, uint256 cancellation_penalty_bps, uint256 target_reserve_ratio, uint256 skim_incentive_bps, uint256 stale_threshold, uint256 unrated_default_il, uint256 max_il_deviation_bps, uint256 quote_validity, uint256 max_effective_il, uint256 max_default_history, uint256 grace_duration, uint256 grace_factor );
+    event EmergencyAdminSet(address indexed old_admin, address indexed new_admin);
+    event IlWeightSet(bytes32 indexed pool_id, uint256 il_weight);
+    event RiskPresetSet(uint8 indexed tier, bytes32 name);
+    event RiskPresetApplied(bytes32 indexed pool_id, uint8 indexed tier, bytes32 name);
+    event CircuitBreakerSet(bytes32 indexed pool_id, uint256 max_deviation_bps);
+    event CircuitBreakerBypassSet(bytes32 indexed pool_id, bool bypassed);
+    event DefaultHistoryClamped(bytes32 indexed pool_id, address indexed lender, uint256 provided, uint256 cap);
+    event DataProviderSet(bytes32 indexed pool_id, address indexed provider);
+    event PoolTypeSet(bytes32 indexed pool_id, uint8 pool_type);
+    event FeeCurveSet(bytes32 indexed pool_id, uint256[] size_fractions, uint256[] multipliers);
+    event FeeCurveCleared(bytes32 indexed pool_id);
+    event UncertaintyPremiumSet(uint256 premium);
+    event PoolObservationsReset(bytes32 indexed pool_id, uint256 observations);
+    event MaxDataAgeSet(bytes32 indexed pool_id, uint256 max_age);
+    event DefaultMaxDataAgeSet(uint256 max_age);
+    event PoolActiveSet(bytes32 indexed pool_id, bool active);
+    event OpenInterestWeightSet(uint256 weight);
+    event SystemicRiskParamsSet(uint256 soft_threshold, uint256 hard_threshold, uint256 max_multiplier);
+    event AccrualEpochLengthSet(uint256 length);
+    event PremiumEpochsClosed(bytes32 indexed pool_id, uint256 next_epoch, uint256 earned);
+    event StreamOpened(uint256 indexed stream_id, bytes32 indexed pool_id, address indexed payer, uint256 notional, uint256 rate);
+    event StreamCheckpointed(uint256 indexed stream_id, uint256 owed, uint256 rate);
+    event StreamClosed(uint256 indexed stream_id, uint256 paid);
+    event CoverageBudgetDeposited(bytes32 indexed pool_id, address indexed payer, uint256 amount);
+    event CoverageBudgetWithdrawn(bytes32 indexed pool_id, address indexed payer, uint256 amount);
+    event AutoRenewSet(uint256 indexed policy_id, bool enabled);
+    event RenewalFunded(uint256 indexed policy_id, uint256 amount, uint256 balance);
+    event RenewalBalanceWithdrawn(uint256 indexed policy_id, address indexed holder, uint256 amount);
+    event PolicyLapsed(uint256 indexed policy_id, uint256 premium, uint256 balance);
+    event IlUpdateGuarded(bytes32 indexed pool_id, address indexed updater, uint256 old_value, uint256 proposed);
+    event UpdaterBondParamsSet(uint256 min_bond, uint256 cooldown);
+    event UpdaterBondPosted(address indexed updater, uint256 amount, uint256 bond);
+    event UpdaterBondWithdrawn(address indexed updater, uint256 amount);
+    event UpdaterSlashed(address indexed updater, address indexed recipient, uint256 amount);
+    event IlDisputeWindowSet(uint256 window);
+    event IlUpdatePending(bytes32 indexed pool_id, uint256 il, uint256 effective_at, address indexed updater);
+    event PendingIlRejected(bytes32 indexed pool_id, uint256 il, address indexed guardian);
+    event IlSourceParamsSet(uint256 max_sources, uint256 quorum, uint256 heartbeat);
+    event IlSourceAdded(bytes32 indexed pool_id, address indexed source);
+    event IlSourceRemoved(bytes32 indexed pool_id, address indexed source);
+    event IlSourceSubmitted(bytes32 indexed pool_id, address indexed source, uint256 il);
+    event MaxFlashAmountSet(bytes32 indexed pool_id, uint256 cap);
+    event IdempotentReplay(address indexed caller, bytes32 indexed key);
+    event AprReferenceSizeSet(bytes32 indexed pool_id, uint256 size);
+    event StressScenarioSet(bytes32 indexed name, uint256 il_shock, uint256 utilization, uint256 volume_haircut_bps);
+    event FeeSmoothingSet(bytes32 indexed pool_id, uint256 max_change_bps);
+    event TokenDecimalsSet(bytes32 indexed pool_id, uint8 decimals0, uint8 decimals1);
+    event FeeFormulaSet(bytes32 indexed pool_id, address formula);
+    event FeeFormulaBoundsSet(bytes32 indexed pool_id, uint256 min_bps, uint256 max_bps);
+    event FeeFormulaFallback(bytes32 indexed pool_id, address indexed formula);
+
+    /// Claims ownership of a freshly deployed calculator
+    function initialize() external;
+
+    /// Returns the contract owner
+    function owner() external view returns (address);
+
+    /// Sets the emergency admin (zero removes the role)
+    function setEmergencyAdmin(address admin) external;
+
+    /// Returns the emergency admin
+    function emergencyAdmin() external view returns (address);
+
+    /// Lowers a pool's insurance base fee below its current effective value
+    /// Zero is rejected since it restores the default, which may be higher.
+    function emergencyLowerBaseFee(bytes32 pool_id, uint256 new_value) external;
+
+    /// Lowers a pool's flash fee multiplier below its current effective value (zero means 1.0)
+    function emergencyLowerFlashFeeMultiplier(bytes32 pool_id, uint256 new_value) external;
+
+    /// Lowers a pool's surge multiplier below its current value; zero disables surge entirely
+    function emergencyLowerSurgeMultiplier(bytes32 pool_id, uint256 new_value) external;
+
+    /// Tightens a pool's exposure cap below its current value; any non-zero cap tightens an
+    /// uncapped pool
+    function emergencyTightenExposureCap(bytes32 pool_id, uint256 new_value) external;
+
+    /// Returns whether the contract implements an ERC-165 interface
+    function supportsInterface(bytes4 interface_id) external view returns (bool);
+
+    /// Validates and writes every bundle parameter at once, returning the new parameter epoch
+    /// Every field is checked against its individual setter's bounds before any is written, so
+    /// one invalid field reverts the whole bundle.
+    function applyParameterBundle(ParameterBundle memory bundle) external returns (uint256);
+
+    /// Returns the bundle parameters as stored, in `ParameterBundle` order
+    function getParameters() external view returns (uint256, uint256, uint256, uint256, uint256, uint256, uint256, uint256, uint256, uint256, uint256, uint256);
+
+    /// Returns the current parameter epoch
+    function parameterEpoch() external view returns (uint256);
+
+    /// Returns the bundle parameters in force during `epoch`, in `ParameterBundle` order, and
+    /// the timestamp the epoch began (zero for the initial epoch)
+    function getParametersAtEpoch(uint256 epoch) external view returns (ParameterBundle memory, uint256);
+
+    /// Sets the protocol share of collected premiums, in basis points
+    function setProtocolFeeBps(uint256 bps) external;
+
+    /// Sets the address allowed to withdraw the treasury balance
+    function setTreasury(address treasury) external;
+
+    /// Returns the protocol share of collected premiums, in basis points
+    function protocolFeeBps() external view returns (uint256);
+
+    /// Returns the treasury address
+    function treasury() external view returns (address);
+
+    /// Returns the premiums accrued to the treasury and not yet withdrawn
+    function treasuryBalance() external view returns (uint256);
+
+    /// Returns the premiums held in reserve for a pool
+    function getPoolReserves(bytes32 pool_id) external view returns (uint256);
+
+    /// Sets the length of premium accrual epochs in seconds (zero restores the default)
+    /// Reverts while any policy premium is still unearned, since epochs are numbered by it.
+    function setAccrualEpochLength(uint256 length) external;
+
+    /// Returns the length of premium accrual epochs in seconds
+    function accrualEpochLength() external view returns (uint256);
+
+    /// Moves the premium earned in a pool's finished epochs from unearned into reserves,
+    /// returning the amount recognized
+    /// Closes at most 64 epochs per call; a longer backlog needs further calls.
+    function closeEpoch(bytes32 pool_id) external returns (uint256);
+
+    /// Returns the premium per second (WAD-scaled, so 1e18 is one wei a second) streaming
+    /// coverage of `notional` costs at the pool's current risk parameters
+    /// This is the IL product's insurance fee for one 30-day term spread evenly over it, priced
+    /// against liquidity and volume read from the pool's data provider.
+    function getStreamingRate(bytes32 pool_id, uint256 notional) external view returns (uint256);
+
+    /// Opens a premium stream covering `notional` on a pool for the caller, returning its id
+    function openStream(bytes32 pool_id, uint256 notional) external returns (uint256);
+
+    /// Accrues a stream's premium at its current rate up to now, then re-prices the rate from
+    /// here on; callable by anyone. Returns the total owed
+    function checkpointStream(uint256 stream_id) external returns (uint256);
+
+    /// Closes the caller's stream, paying everything it accrued in ETH and refunding any excess
+    function closeStream(uint256 stream_id) external payable returns (uint256);
+
+    /// Returns a stream's (payer, pool_id, notional, rate, last_checkpoint, owed, open) record,
+    /// with `owed` as of its last checkpoint
+    function getStream(uint256 stream_id) external view returns (address, bytes32, uint256, uint256, uint256, uint256, bool);
+
+    /// Returns a pool's (earned, unearned, next_epoch) premium accrual: the policy premium
+    /// recognized into reserves so far, what is still waiting on its epochs, and the next
+    /// epoch `close_epoch` will recognize
+    function getPremiumAccrual(bytes32 pool_id) external view returns (uint256, uint256, uint256);
+
+    /// Collects an ETH premium for a pool, splitting it between reserves and treasury
+    function collectPremium(bytes32 pool_id) external payable;
+
+    /// Withdraws accrued treasury premiums, callable only by the treasury
+    function withdrawTreasury(address to, uint256 amount) external;
+
+    /// Registers a pool's token pair and fee tier
+    function registerPool(bytes32 pool_id, address token0, address token1, uint256 fee_tier) external;
+
+    /// Registers a pool as volatile (0) or stable (1); the type selects its insurance formula
+    /// `pool_id` stays opaque: it need not be `compute_pool_id` of the tokens, so ids derived
+    /// some other way, such as Uniswap v4 `PoolKey` hashes, register as well.
+    function registerPoolWithType(bytes32 pool_id, address token0, address token1, uint256 fee_tier, uint8 pool_type) external;
+
+    /// Returns a pool's type: volatile (0) or stable (1)
+    function getPoolType(bytes32 pool_id) external view returns (uint8);
+
+    /// Sets the decimals of a pool's token0 and token1 used by denominated quotes
+    function setTokenDecimals(bytes32 pool_id, uint8 decimals0, uint8 decimals1) external;
+
+    /// Returns a pool's (token0, token1) decimals, 18 each until configured
+    function getTokenDecimals(bytes32 pool_id) external view returns (uint8, uint8);
+
+    /// Delegates a pool's core insurance fee to an external `IFeeFormula` (zero restores the
+    /// internal formula); owner-only, since the formula prices coverage paid from reserves
+    function setFeeFormula(bytes32 pool_id, address formula) external;
+
+    /// Returns a pool's external fee formula, zero when it prices internally
+    function getFeeFormula(bytes32 pool_id) external view returns (address);
+
+    /// Sets the band a pool's external formula fee is clamped to, in bps of the internal
+    /// formula's fee; the floor must be non-zero and the cap at most `MAX_FORMULA_BPS`
+    function setFeeFormulaBounds(bytes32 pool_id, uint256 min_bps, uint256 max_bps) external;
+
+    /// Returns the (min_bps, max_bps) band of the internal fee a pool's external formula fee is
+    /// clamped to
+    function getFeeFormulaBounds(bytes32 pool_id) external view returns (uint256, uint256);
+
+    /// Returns every configured setting of a pool in one call, in `PoolConfig` order
+    function getPoolConfig(bytes32 pool_id) external view returns (uint256, uint256, uint256, uint8, address, address, uint256, bool, uint256, uint256, uint256, uint256, uint256);
+
+    /// Stops a registered pool from quoting and selling coverage; existing policies can still
+    /// be claimed
+    function deactivatePool(bytes32 pool_id) external;
+
+    /// Restores quoting and coverage sales on a deactivated pool
+    function reactivatePool(bytes32 pool_id) external;
+
+    /// Returns whether a pool quotes; pools are active unless deactivated
+    function isPoolActive(bytes32 pool_id) external view returns (bool);
+
+    /// Returns a pool's (token0, token1, fee_tier, registered, registered_at) metadata
+    function getPoolMetadata(bytes32 pool_id) external view returns (address, address, uint256, bool, uint256);
+
+    /// Returns the number of pools in the index
+    function poolCount() external view returns (uint256);
+
+    /// Returns the pool id at `index` in the index
+    function poolAt(uint256 index) external view returns (bytes32);
+
+    /// Toggles whether quotes require the pool to be registered
+    function setRequireRegistration(bool required) external;
+
+    /// Returns whether quotes require the pool to be registered
+    function requireRegistration() external view returns (bool);
+
+    /// Assigns the operator allowed to manage a pool's parameters
+    /// Global updaters lose IL write access to the pool once it has an operator; clearing the
+    /// operator back to zero returns the pool to the global updater set.
+    function setPoolOperator(bytes32 pool_id, address operator) external;
+
+    /// Returns a pool's operator
+    function getPoolOperator(bytes32 pool_id) external view returns (address);
+
+    /// Sets a pool's flash fee multiplier (WAD, zero means 1.0)
+    function setDefaultFlashFeeMultiplier(bytes32 pool_id, uint256 multiplier) external;
+
+    /// Returns a pool's flash fee multiplier
+    function getDefaultFlashFeeMultiplier(bytes32 pool_id) external view returns (uint256);
+
+    /// Sets the largest single flash loan a pool prices (zero means unlimited)
+    function setMaxFlashAmount(bytes32 pool_id, uint256 cap) external;
+
+    /// Returns the largest single flash loan a pool prices (zero means unlimited)
+    function getMaxFlashAmount(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns the most a single flash loan can draw from a pool: the liquidity left after
+    /// `utilization_rate` and this block's earlier loans, bounded by the pool's cap
+    /// The per-pool counterpart of ERC-3156 `maxFlashLoan`.
+    function maxFlashLoan(bytes32 pool_id, uint256 total_liquidity, uint256 utilization_rate) external view returns (uint256);
+
+    /// Sets a pool's insurance base fee (WAD, zero restores the default)
+    function setBaseFee(bytes32 pool_id, uint256 base_fee) external;
+
+    /// Returns the insurance base fee applied to a pool
+    function getBaseFee(bytes32 pool_id) external view returns (uint256);
+
+    /// Sets the IL weight a pool's IL product is priced with (WAD, zero restores the default)
+    function setIlWeight(bytes32 pool_id, uint256 il_weight) external;
+
+    /// Returns the IL weight a pool's IL product is priced with
+    function getIlWeight(bytes32 pool_id) external view returns (uint256);
+
+    /// Defines risk tier `tier` as a named bundle of pool parameters
+    /// Each field is validated as its individual setter would; a zero surge multiplier leaves
+    /// surge disabled and a zero exposure cap leaves pools uncapped.
+    function setRiskPreset(uint8 tier, bytes32 name, uint256 base_fee, uint256 il_weight, uint256 surge_threshold, uint256 surge_multiplier, uint256 flash_multiplier, uint256 max_exposure) external;
+
+    /// Returns risk tier `tier` as (name, base_fee, il_weight, surge_threshold, surge_multiplier,
+    /// flash_multiplier, max_exposure), reverting if it is undefined
+    function getRiskPreset(uint8 tier) external view returns (bytes32, uint256, uint256, uint256, uint256, uint256, uint256);
+
+    /// Copies a risk tier's parameters into a pool's configuration
+    /// The pool must be registered and active. Fields can still be overridden one at a time through
+    /// their own setters afterwards.
+    function applyPreset(bytes32 pool_id, uint8 tier) external;
+
+    /// Configures a non-IL insurance product on a pool (base fee and weights in WAD)
+    /// The IL product (id 0) is priced by the pool's base fee and cannot be reconfigured here.
+    function setProductConfig(bytes32 pool_id, uint8 product_id, bool enabled, uint256 base_fee, uint256 il_weight, uint256 size_weight) external;
+
+    /// Returns the (base_fee, il_weight, size_weight) a product is priced with on a pool
+    function getProductConfig(bytes32 pool_id, uint8 product_id) external view returns (uint256, uint256, uint256);
+
+    /// Pays a trade's insurance premium in ETH, returning the fee charged
+    /// Any value above the fee is refunded to the caller in the same call, so wallets can
+    /// safely attach a small buffer. Underpayment reverts with the required fee.
+    function payPremium(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external payable returns (uint256);
+
+    /// Pays a trade's insurance premium like `pay_premium`, reverting with `FeeSlippage` if the
+    /// fee at execution exceeds `expected_fee * (10_000 + tolerance_bps) / 10_000`
+    /// A fee at or below the expected fee always goes through.
+    function payPremiumWithTolerance(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 expected_fee, uint256 tolerance_bps) external payable returns (uint256);
+
+    /// Deposits ETH into a pool's insurance fund
+    function depositToFund(bytes32 pool_id) external payable;
+
+    /// Withdraws from a pool's insurance fund without breaching its minimum reserve
+    function withdrawFromFund(bytes32 pool_id, address to, uint256 amount) external;
+
+    /// Sets the reserve floor owner withdrawals must leave in a pool's fund
+    function setMinReserve(bytes32 pool_id, uint256 minimum) external;
+
+    /// Returns a pool's minimum reserve
+    function getMinReserve(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns the sum of all pool reserves
+    function getTotalReserves() external view returns (uint256);
+
+    /// Quotes the premium for IL coverage of `coverage_fraction` of `notional` for `duration` seconds
+    function quoteCoveragePremium(bytes32 pool_id, uint256 notional, uint256 coverage_fraction, uint256 duration, uint256 deductible_wad, uint256 coinsurance_bps, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Quotes the premium for covering `coverage_fraction` (WAD) of a `notional` position under a
+    /// product for `duration` seconds
+    /// The insurance fee on the full notional buys one 30-day term; other durations are pro-rated.
+    /// A deductible (WAD fraction of loss) discounts the premium to
+    /// `premium / (1 + 4 * deductible)`, so a 5% deductible prices at 1/1.2 of full cover.
+    /// Co-insurance then scales it by the covered share, `(10_000 - coinsurance_bps) / 10_000`,
+    /// and partial cover by `coverage_fraction`, each step rounding down. Risk multipliers are
+    /// always priced off the full position.
+    function quoteProductCoveragePremium(bytes32 pool_id, uint8 product_id, uint256 notional, uint256 coverage_fraction, uint256 duration, uint256 deductible_wad, uint256 coinsurance_bps, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Sets the position size a pool's required premium APR is quoted for (zero restores the
+    /// default of one WAD unit)
+    function setAprReferenceSize(bytes32 pool_id, uint256 size) external;
+
+    /// Returns the position size a pool's required premium APR is quoted for
+    function getAprReferenceSize(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns the annualized premium (WAD rate) a pool demands for full IL cover of its
+    /// reference position size
+    /// Simple annualization with no compounding: the one-year premium divided by the size, so
+    /// it matches a full-cover, no-deductible `quote_coverage_premium` for 365 days up to
+    /// rounding. Priced without any caller's loyalty discount, so every caller sees the same rate.
+    function getRequiredPremiumApr(bytes32 pool_id, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Closes a pool's open rebate epoch at its realized IL (WAD) and opens the next one,
+    /// returning the closed epoch
+    function closeRebateEpoch(bytes32 pool_id, uint256 realized_il) external returns (uint256);
+
+    /// Returns a pool's open rebate epoch
+    function currentRebateEpoch(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns a rebate epoch's (premium_total, projected_il, realized_il, closed)
+    function getRebateEpoch(bytes32 pool_id, uint256 epoch) external view returns (uint256, uint256, uint256, bool);
+
+    /// Computes the rebate owed on `premium_paid` from a closed epoch whose realized IL came in
+    /// under the premium-weighted IL it was priced at
+    /// The rebate is `premium_paid * (projected - realized) / projected`, capped at the
+    /// configured share of the premium; epochs realizing at or above projection rebate zero.
+    function calculateRebate(bytes32 pool_id, uint256 epoch, uint256 premium_paid) external view returns (uint256);
+
+    /// Sets the largest share of a premium a rebate may return, in basis points (zero restores
+    /// the default)
+    function setMaxRebateBps(uint256 bps) external;
+
+    /// Returns the largest share of a premium a rebate may return, in basis points
+    function maxRebateBps() external view returns (uint256);
+
+    /// Allows or disallows a deductible (WAD fraction of loss) for new policies
+    function setAllowedDeductible(uint256 deductible_wad, bool allowed) external;
+
+    /// Returns whether a deductible may be chosen at purchase; zero is always allowed
+    function isDeductibleAllowed(uint256 deductible_wad) external view returns (bool);
+
+    /// Buys IL coverage on a pool, paying the premium in ETH and refunding any excess
+    function buyCoverage(bytes32 pool_id, uint256 notional, uint256 coverage_fraction, uint256 duration, uint256 deductible_wad, uint256 coinsurance_bps, uint256 total_liquidity, uint256 total_volume) external payable returns (uint256);
+
+    /// Buys coverage under a product on a pool, paying the premium in ETH and refunding any excess
+    /// The policy covers `notional * coverage_fraction` (rounded down), which is also what
+    /// counts towards the pool's exposure.
+    function buyProductCoverage(bytes32 pool_id, uint8 product_id, uint256 notional, uint256 coverage_fraction, uint256 duration, uint256 deductible_wad, uint256 coinsurance_bps, uint256 total_liquidity, uint256 total_volume) external payable returns (uint256);
+
+    /// Buys IL coverage like `buy_coverage`, reverting with `FeeSlippage` if the premium at
+    /// execution exceeds `expected_fee * (10_000 + tolerance_bps) / 10_000`
+    function buyCoverageWithTolerance(bytes32 pool_id, uint256 notional, uint256 coverage_fraction, uint256 duration, uint256 deductible_wad, uint256 coinsurance_bps, uint256 total_liquidity, uint256 total_volume, uint256 expected_fee, uint256 tolerance_bps) external payable returns (uint256);
+
+    /// Computes what a policy pays out for a realized loss (WAD fraction of notional)
+    /// The policy's deductible is taken off the loss first, then co-insurance keeps the
+    /// holder's share: `max(loss - deductible, 0) * notional * (10_000 - coinsurance_bps) / 10_000`.
+    function calculateClaimPayout(uint256 policy_id, uint256 realized_loss) external view returns (uint256);
+
+    /// Claims an active policy against the IL its pool has realized since the policy started,
+    /// paying the holder from reserves
+    /// A policy can only be claimed once it has run for `CLAIM_WAITING_PERIOD` (1 day).
+    function claimPolicy(uint256 policy_id) external returns (uint256);
+
+    /// Marks a lapsed policy expired and releases its notional, callable by anyone
+    /// Expiry is lazy: a lapsed policy keeps counting toward open interest and the systemic
+    /// multiplier until this is called, so the caller receives `expiry_incentive_bps` of the
+    /// policy's premium from the pool's reserves (capped at what the reserves hold).
+    /// Auto-renewing policies are left to `process_renewal` for `RENEWAL_GRACE_PERIOD` past
+    /// expiry; after that they lapse here with their renewal balance kept withdrawable, so a
+    /// renewal that keeps failing to price cannot hold their notional open.
+    function expirePolicy(uint256 policy_id) external returns (uint256);
+
+    /// Extends a live policy by `duration` at current pricing, paid in ETH
+    function renewPolicy(uint256 policy_id, uint256 duration, uint256 total_liquidity, uint256 total_volume) external payable returns (uint256);
+
+    /// Renews a policy like `renew_policy`, reverting with `FeeSlippage` if the premium at
+    /// execution exceeds `expected_fee * (10_000 + tolerance_bps) / 10_000`
+    function renewPolicyWithTolerance(uint256 policy_id, uint256 duration, uint256 total_liquidity, uint256 total_volume, uint256 expected_fee, uint256 tolerance_bps) external payable returns (uint256);
+
+    /// Turns automatic renewal of the caller's policy on or off
+    function setAutoRenew(uint256 policy_id, bool enabled) external;
+
+    /// Adds the attached ETH to a policy's prepaid renewal balance, returning the new balance
+    function fundRenewal(uint256 policy_id) external payable returns (uint256);
+
+    /// Withdraws a policy's whole renewal balance to its holder
+    function withdrawRenewalBalance(uint256 policy_id) external returns (uint256);
+
+    /// Returns a policy's (auto_renew, renewal_balance, term)
+    function getRenewalStatus(uint256 policy_id) external view returns (bool, uint256, uint256);
+
+    /// Renews an expired auto-renewing policy for another term from now at current pricing,
+    /// paid from its renewal balance; callable by anyone. Returns the premium charged
+    /// Pricing reads liquidity and volume from the pool's data provider and applies the
+    /// holder's loyalty discount, never the caller's. When the pool has been deactivated or the
+    /// balance falls short, the policy lapses instead and zero is returned.
+    function processRenewal(uint256 policy_id) external returns (uint256);
+
+    /// Moves a live policy to a new holder
+    function transferPolicy(uint256 policy_id, address new_holder) external;
+
+    /// Returns the ids of every policy an address holds
+    function getHolderPolicies(address holder) external view returns (uint256[] memory);
+
+    /// Cancels an active policy, crediting the unused premium (less the penalty) for withdrawal
+    /// The refund is `premium * remaining / duration` minus `cancellation_penalty_bps` of that
+    /// amount, and is zero in the final 10% of the policy's life. It is drawn from the policy's
+    /// unearned premium only, and whatever of that it leaves is recognized into reserves.
+    function cancelPolicy(uint256 policy_id) external returns (uint256);
+
+    /// Computes what cancelling a policy now would refund, never more than its unearned premium
+    function calculateCancellationRefund(uint256 policy_id) external view returns (uint256);
+
+    /// Sets the share of each cancellation refund withheld as a penalty, in basis points
+    function setCancellationPenaltyBps(uint256 bps) external;
+
+    /// Returns the cancellation penalty in basis points
+    function cancellationPenaltyBps() external view returns (uint256);
+
+    /// Returns the cancellation refunds an address can withdraw
+    function getRefundBalance(address holder) external view returns (uint256);
+
+    /// Withdraws the caller's accumulated cancellation refunds
+    function withdrawRefund() external returns (uint256);
+
+    /// Returns a policy's (holder, pool_id, notional, premium, start, expiry, status, deductible,
+    /// coinsurance_bps) record
+    function getPolicy(uint256 policy_id) external view returns (address, bytes32, uint256, uint256, uint256, uint256, uint8, uint256, uint256);
+
+    /// Returns the insurance product a policy was bought under
+    function getPolicyProduct(uint256 policy_id) external view returns (uint8);
+
+    /// Returns the share of its position a policy covers (WAD)
+    function getPolicyCoverageFraction(uint256 policy_id) external view returns (uint256);
+
+    /// Returns the notional of a pool's active policies
+    function getOutstandingCoverage(bytes32 pool_id) external view returns (uint256);
+
+    /// Sets the WAD weight `w` of the open-interest multiplier
+    /// `1 + w * min(outstanding_coverage / total_liquidity, 1)` (zero disables it)
+    function setOpenInterestWeight(uint256 weight) external;
+
+    /// Returns the WAD weight of the open-interest multiplier
+    function openInterestWeight() external view returns (uint256);
+
+    /// Returns the notional of active policies across every pool
+    function getTotalOutstandingCoverage() external view returns (uint256);
+
+    /// Configures the systemic multiplier on every insurance quote: 1.0 while total open
+    /// coverage is at or below `soft_threshold`, rising linearly to `max_multiplier` (WAD) at
+    /// `hard_threshold` and flat beyond; a zero `hard_threshold` disables it
+    function setSystemicRiskParams(uint256 soft_threshold, uint256 hard_threshold, uint256 max_multiplier) external;
+
+    /// Returns the (soft_threshold, hard_threshold, max_multiplier) systemic risk parameters
+    function getSystemicRiskParams() external view returns (uint256, uint256, uint256);
+
+    /// Returns the systemic multiplier (WAD) total open coverage currently puts on every quote;
+    /// lapsed policies count until `expire_policy` releases them
+    function getSystemicMultiplier() external view returns (uint256);
+
+    /// Returns a pool's open coverage as a share of `total_liquidity` (WAD, capped at 1.0)
+    /// Lapsed policies count until `expire_policy` releases them.
+    function getOpenInterestRatio(bytes32 pool_id, uint256 total_liquidity) external view returns (uint256);
+
+    /// Caps the outstanding coverage a pool may carry (zero removes the cap)
+    function setMaxExposure(bytes32 pool_id, uint256 cap) external;
+
+    /// Returns a pool's exposure cap (zero if uncapped)
+    function getMaxExposure(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns the notional still available for new coverage on a pool
+    /// Uncapped pools report `U256::MAX`; a cap lowered below current exposure reports zero.
+    function getRemainingCapacity(bytes32 pool_id) external view returns (uint256);
+
+    /// Sets the reserves required per unit of outstanding coverage (WAD, zero restores 1.0)
+    function setTargetReserveRatio(uint256 ratio) external;
+
+    /// Returns the reserves required per unit of outstanding coverage
+    function targetReserveRatio() external view returns (uint256);
+
+    /// Sets the share of each skim paid to the caller, in basis points
+    function setSkimIncentiveBps(uint256 bps) external;
+
+    /// Returns the share of each skim paid to the caller, in basis points
+    function skimIncentiveBps() external view returns (uint256);
+
+    /// Sets the share of a lapsed policy's premium paid to whoever expires it, in basis points
+    function setExpiryIncentiveBps(uint256 bps) external;
+
+    /// Returns the share of a lapsed policy's premium paid to whoever expires it, in basis points
+    function expiryIncentiveBps() external view returns (uint256);
+
+    /// Sweeps reserves above the pool's coverage requirement to the treasury, callable by anyone
+    /// The requirement is `outstanding_coverage * target_reserve_ratio`, never below the pool's
+    /// minimum reserve. The caller receives `skim_incentive_bps` of the excess (rounded down).
+    function skimExcess(bytes32 pool_id) external returns (uint256);
+
+    /// Estimates the cost of hedging a position's IL with an at-the-money straddle
+    /// Uses the `0.4 * sigma * sqrt(T) * notional` approximation with sigma annualized (WAD)
+    /// and T in years. Zero volatility or duration costs nothing.
+    function estimateHedgingCost(uint256 notional, uint256 volatility_wad, uint256 duration_seconds) external view returns (uint256);
+
+    /// Computes the IL (WAD) of a concentrated-liquidity position in `[lower_price, upper_price]`
+    /// opened at `entry_price`, as the price moves to `current_price`
+    /// Token amounts follow the range formula: `x = L * (1/sqrt(P) - 1/sqrt(upper))` and
+    /// `y = L * (sqrt(P) - sqrt(lower))` with P clamped into the range, so the position is all
+    /// token0 below it and all token1 above it. IL is `1 - V(current) / H(current)`, where H
+    /// values the entry amounts at the current price; L cancels out. Prices are WAD.
+    function computeRangeIl(uint256 entry_price, uint256 current_price, uint256 lower_price, uint256 upper_price) external view returns (uint256);
+
+    /// Enables or disables the hedging-cost premium floor for a pool
+    /// `volatility` is an annualized WAD fraction, at most 500%.
+    function setHedgingFeeMode(bytes32 pool_id, bool enabled, uint256 volatility) external;
+
+    /// Returns a pool's (enabled, volatility) hedging fee mode
+    function getHedgingFeeMode(bytes32 pool_id) external view returns (bool, uint256);
+
+    /// Grants or revokes IL updater rights on pools that have no operator assigned
+    function setUpdater(address updater, bool authorized) external;
+
+    /// Returns whether an address may push IL data
+    function isUpdater(address updater) external view returns (bool);
+
+    /// Stores a pool's historical IL (WAD fraction) and clears any unrated flag
+    /// `sequence` must exceed the pool's last accepted sequence number.
+    function updateHistoricalIl(bytes32 pool_id, uint256 il, uint256 sequence) external;
+
+    /// Like `update_historical_il`, but a deviation guard trip is counted against the caller
+    /// and reported as `false` instead of reverting, so it stays on record
+    function tryUpdateHistoricalIl(bytes32 pool_id, uint256 il, uint256 sequence) external returns (bool);
+
+    /// Sets the bond authorized updaters must hold to write IL (zero disables the requirement)
+    /// and how long it stays locked after their last write (zero restores the default)
+    function setUpdaterBondParams(uint256 min_bond, uint256 cooldown) external;
+
+    /// Returns the (min_bond, cooldown) updater bond parameters
+    function getUpdaterBondParams() external view returns (uint256, uint256);
+
+    /// Returns how long an updater's bond stays locked after its last IL write, in seconds
+    function updaterBondCooldown() external view returns (uint256);
+
+    /// Adds the attached ETH to the caller's updater bond, returning the new bond
+    function postUpdaterBond() external payable returns (uint256);
+
+    /// Withdraws the caller's whole bond once the cooldown since its last IL write has passed
+    /// and none of its staged IL is still inside the dispute window
+    function withdrawUpdaterBond() external returns (uint256);
+
+    /// Seizes `amount` of an updater's bond for provable misbehavior, paying it to `recipient`
+    function slashUpdater(address updater, uint256 amount, address recipient) external;
+
+    /// Returns an updater's bonded ETH
+    function getUpdaterBond(address updater) external view returns (uint256);
+
+    /// Returns an updater's (accepted, guarded, last_update, pools_touched) statistics
+    /// Reverted calls leave nothing on chain, so guard trips are only counted through
+    /// `try_update_historical_il`.
+    function getUpdaterStats(address updater) external view returns (uint256, uint256, uint256, uint256);
+
+    /// Stores a pool's historical IL without the deviation guard, callable only by the owner
+    /// Takes effect at once and discards any update still inside its dispute window.
+    function forceUpdateHistoricalIl(bytes32 pool_id, uint256 il, uint256 sequence) external;
+
+    /// Sets how long non-owner IL updates wait before taking effect, in seconds (zero applies
+    /// them at once)
+    function setIlDisputeWindow(uint256 window) external;
+
+    /// Returns the IL dispute window in seconds
+    function ilDisputeWindow() external view returns (uint256);
+
+    /// Returns a pool's (il, effective_at, updater) update waiting out its dispute window;
+    /// zero `effective_at` means none is pending
+    function getPendingIl(bytes32 pool_id) external view returns (uint256, uint256, address);
+
+    /// Discards a pool's pending IL update before its window ends, callable by the owner or
+    /// the emergency admin
+    function rejectPendingIl(bytes32 pool_id) external;
+
+    /// Writes a pool's pending IL into storage once its window has passed, callable by anyone;
+    /// returns whether anything was promoted. Reads already see a matured value without this
+    function promotePendingIl(bytes32 pool_id) external returns (bool);
+
+    /// Sets the IL source slots per pool, the fresh sources a median needs, and how long an
+    /// answer stays fresh in seconds (zero restores each default)
+    function setIlSourceParams(uint256 max_sources, uint256 quorum, uint256 heartbeat) external;
+
+    /// Returns the effective (max_sources, quorum, heartbeat) for IL source aggregation
+    function getIlSourceParams() external view returns (uint256, uint256, uint256);
+
+    /// Registers an address whose IL answers feed a pool's median, callable only by the owner
+    function addIlSource(bytes32 pool_id, address source) external;
+
+    /// Deregisters one of a pool's IL sources and drops its answer, callable only by the owner
+    function removeIlSource(bytes32 pool_id, address source) external;
+
+    /// Returns a pool's registered IL sources
+    function getIlSources(bytes32 pool_id) external view returns (address[] memory);
+
+    /// Records the caller's latest IL answer for a pool it is registered as a source for
+    function submitIlSource(bytes32 pool_id, uint256 il) external;
+
+    /// Returns a source's latest (il, timestamp) answer for a pool
+    function getIlSource(bytes32 pool_id, address source) external view returns (uint256, uint256);
+
+    /// Returns the (il, fresh_sources) a pool's sources aggregate to; below quorum the IL is
+    /// the unrated default
+    function getAggregatedIl(bytes32 pool_id) external view returns (uint256, uint256);
+
+    /// Stores an IL update signed by an updater, submitted by any relayer
+    /// `signature` is the 65-byte `r || s || v` signature over `il_update_digest`. The signer
+    /// must be allowed to update the pool, and the update passes the same deviation and
+    /// sequence checks as a direct write.
+    function submitSignedIlUpdate(bytes32 pool_id, uint256 il, uint256 sequence, bytes memory signature) external;
+
+    /// Returns the digest an updater signs to authorize an IL update
+    /// `keccak256("\x19Ethereum Signed Message:\n32" ++ keccak256(abi.encode(address(this),
+    /// block.chainid, pool_id, il, sequence)))`, i.e. a standard signed message over the
+    /// inner hash, so the sequence number and deployment are bound into every signature.
+    function ilUpdateDigest(bytes32 pool_id, uint256 il, uint256 sequence) external view returns (bytes32);
+
+    /// Returns the sequence number of a pool's latest IL write
+    function getUpdateSequence(bytes32 pool_id) external view returns (uint256);
+
+    /// Sets the relative band a single IL update must stay within, in basis points
+    /// (zero restores the default)
+    function setMaxIlDeviationBps(uint256 bps) external;
+
+    /// Returns the relative band a single IL update must stay within, in basis points
+    function maxIlDeviationBps() external view returns (uint256);
+
+    /// Sets the ceiling on the IL quotes price with (WAD, at most 1.0; zero restores 1.0)
+    /// Applied when quoting rather than when writing, so even a corrupted stored value cannot
+    /// push the IL multiplier past `1 + il_weight * cap`.
+    function setMaxEffectiveIl(uint256 cap) external;
+
+    /// Returns the ceiling on the IL quotes price with
+    function maxEffectiveIl() external view returns (uint256);
+
+    /// Returns whether a pool's quotes are currently clamping its IL to the ceiling
+    function isIlClamped(bytes32 pool_id) external view returns (bool);
+
+    /// Sets the seconds that must pass between a pool's accepted IL writes (zero disables)
+    /// Owner writes, including forced updates, are never rate limited.
+    function setMinIlUpdateInterval(uint256 interval) external;
+
+    /// Returns the seconds that must pass between a pool's accepted IL writes
+    function minIlUpdateInterval() external view returns (uint256);
+
+    /// Returns the timestamp of a pool's last IL update, counting a matured pending update
+    function getLastIlUpdate(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns how many IL writes a pool's history log retains (at most 128)
+    function getIlHistoryLength(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns up to `limit` retained IL writes as (values, timestamps, updaters), newest first,
+    /// skipping the `offset` most recent
+    function getIlHistory(bytes32 pool_id, uint256 offset, uint256 limit) external view returns (uint256[] memory, uint256[] memory, address[] memory);
+
+    /// Hashes per-pool oracle state so a migrated deployment can be checked against its source
+    /// Starting from `bytes32(0)`, each pool in the given order folds in as
+    /// `acc = keccak256(abi.encode(acc, pool_id, historical_il, default_flash_fee_multiplier,
+    /// last_il_update))`, all values as stored (unset reads as zero). An empty list returns
+    /// `bytes32(0)`; reordering or repeating ids changes the result.
+    function computeStateCommitment(bytes32[] memory pool_ids) external view returns (bytes32);
+
+    /// Sets how long IL data stays fresh, in seconds (zero disables staleness flagging)
+    function setStaleThreshold(uint256 threshold) external;
+
+    /// Returns the IL staleness threshold in seconds
+    function staleThreshold() external view returns (uint256);
+
+    /// Sets the conservative IL (WAD fraction) quoted for unrated pools
+    function setUnratedDefaultIl(uint256 il) external;
+
+    /// Returns the conservative IL quoted for unrated pools
+    function unratedDefaultIl() external view returns (uint256);
+
+    /// Flags a pool whose IL data has gone stale, callable by anyone
+    function markPoolUnrated(bytes32 pool_id) external;
+
+    /// Sets the oldest IL or price observation a pool quotes from, in seconds (zero defers to
+    /// the global default)
+    function setMaxDataAge(bytes32 pool_id, uint256 max_age) external;
+
+    /// Sets the data age pools without their own heartbeat accept (zero imposes none)
+    function setDefaultMaxDataAge(uint256 max_age) external;
+
+    /// Returns the global data age default, in seconds
+    function defaultMaxDataAge() external view returns (uint256);
+
+    /// Returns the oldest data a pool quotes from, in seconds (zero means no requirement)
+    function getMaxDataAge(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns whether a pool is quoting off the unrated default IL
+    function isPoolUnrated(bytes32 pool_id) external view returns (bool);
+
+    /// Sets how long issued quotes stay valid, in seconds (zero restores the default)
+    function setQuoteValidity(uint256 validity) external;
+
+    /// Returns how long issued quotes stay valid, in seconds
+    function quoteValidity() external view returns (uint256);
+
+    /// Quotes an insurance fee and locks it in for the caller, returning (quote_id, fee)
+    function issueQuote(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external returns (bytes32, uint256);
+
+    /// Consumes a live quote issued to the caller, returning its locked fee
+    function settleQuote(bytes32 quote_id) external returns (uint256);
+
+    /// Returns a quote's (params_hash, issuer, fee, expiry, settled) record
+    function getQuote(bytes32 quote_id) external view returns (bytes32, address, uint256, uint256, bool);
+
+    /// Sets how many blocks a commitment stays revealable (zero restores the default)
+    function setRevealWindow(uint256 blocks) external;
+
+    /// Returns how many blocks a commitment stays revealable
+    function revealWindow() external view returns (uint256);
+
+    /// Commits to a quote request without revealing its size
+    /// The commitment is `keccak256(abi.encode(pool_id, amount, salt, msg.sender))`.
+    function commitQuote(bytes32 commitment) external;
+
+    /// Reveals a committed quote request and prices it against current pool state
+    function revealAndQuote(bytes32 pool_id, uint256 amount, bytes32 salt, uint256 total_liquidity, uint256 total_volume) external returns (uint256);
+
+    /// Replaces the loyalty tiers; thresholds must be strictly increasing
+    function setLoyaltyTiers(uint256[] memory thresholds, uint256[] memory discounts_bps) external;
+
+    /// Returns the configured (thresholds, discounts_bps) loyalty tiers
+    function getLoyaltyTiers() external view returns (uint256[] memory, uint256[] memory);
+
+    /// Returns an address's (cumulative_volume, current_discount_bps)
+    function getLoyaltyStatus(address addr) external view returns (uint256, uint256);
+
+    /// Adds or removes an address from the flash fee exemption list
+    function setFlashFeeExempt(address account, bool exempt) external;
+
+    /// Returns whether an address flash-borrows without fees
+    function isFlashFeeExempt(address account) external view returns (bool);
+
+    /// Waives insurance fees on a pool until `until`; a past timestamp clears the holiday
+    function setFeeHoliday(bytes32 pool_id, uint256 until) external;
+
+    /// Returns when a pool's fee holiday ends (zero if none is scheduled)
+    function getFeeHolidayUntil(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns whether a pool is currently in a fee holiday
+    function isFeeHoliday(bytes32 pool_id) external view returns (bool);
+
+    /// Waives insurance on a pool's trades below `threshold` (WAD, zero disables the waiver)
+    /// Trade amounts are normalized from the pool's token0 decimals before the comparison.
+    function setDustThreshold(bytes32 pool_id, uint256 threshold) external;
+
+    /// Returns the amount below which a pool's insurance is waived
+    function getDustThreshold(bytes32 pool_id) external view returns (uint256);
+
+    /// Installs a pool's size curve: up to 8 (size_fraction, multiplier) WAD breakpoints with
+    /// strictly increasing size fractions, linearly interpolated and flat outside the table
+    function setFeeCurve(bytes32 pool_id, uint256[] memory size_fractions, uint256[] memory multipliers) external;
+
+    /// Removes a pool's size curve, restoring the parametric size multiplier
+    function clearFeeCurve(bytes32 pool_id) external;
+
+    /// Returns a pool's size curve as (size_fractions, multipliers); empty when none is installed
+    function getFeeCurve(bytes32 pool_id) external view returns (uint256[] memory, uint256[] memory);
+
+    /// Configures the new-pool grace period (at most 365 days) and its fee factor (WAD, at most 1.0)
+    function setGracePeriod(uint256 duration, uint256 factor) external;
+
+    /// Returns the configured (duration, factor) grace period
+    function getGracePeriod() external view returns (uint256, uint256);
+
+    /// Returns the grace multiplier currently applied to a pool's insurance fee (WAD)
+    /// Holds at the grace factor for the first three quarters of the window, then ramps
+    /// linearly back to 1.0 by its end.
+    function getGraceMultiplier(bytes32 pool_id) external view returns (uint256);
+
+    /// Sets the WAD premium `k` a pool with no IL observations is priced with; the fee
+    /// multiplier `1 + k / (observations + 1)` decays toward 1.0 as observations accrue
+    function setUncertaintyPremium(uint256 premium) external;
+
+    /// Returns the WAD uncertainty premium
+    function uncertaintyPremium() external view returns (uint256);
+
+    /// Returns the number of IL observations behind a pool's rating
+    function getIlObservations(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns the fee multiplier (WAD) for a pool's data confidence, `1 + k / (observations + 1)`
+    function getUncertaintyMultiplier(bytes32 pool_id) external view returns (uint256);
+
+    /// Clears a pool's IL observation count, returning it to full uncertainty pricing
+    function resetPoolObservations(bytes32 pool_id) external;
+
+    /// Configures a pool's flash fee surge; a zero multiplier disables it
+    /// The threshold must sit below 100% utilization and the multiplier between 1x and 10x.
+    function setSurgeParams(bytes32 pool_id, uint256 threshold, uint256 multiplier) external;
+
+    /// Returns a pool's (threshold, multiplier) surge configuration
+    function getSurgeParams(bytes32 pool_id) external view returns (uint256, uint256);
+
+    /// Records a swap for analytics and loyalty, returning the insurance fee it was quoted
+    /// The fee is debited from the caller's prepaid coverage budget for the pool and collected
+    /// as premium. Dust swaps below the pool's threshold quote zero and leave every counter
+    /// and the budget untouched.
+    function recordSwapVolume(bytes32 pool_id, address trader, uint256 amount, uint256 total_liquidity, uint256 total_volume) external returns (uint256);
+
+    /// Sets how far a pool's swap fee rate may move per block from the last recorded rate, in
+    /// basis points (zero disables smoothing)
+    function setFeeSmoothing(bytes32 pool_id, uint256 max_change_bps) external;
+
+    /// Returns a pool's (max_change_bps, last_recorded_fee_rate, last_recorded_block) smoothing
+    /// state, the rate in WAD fee per unit swapped
+    function getFeeSmoothing(bytes32 pool_id) external view returns (uint256, uint256, uint256);
+
+    /// Quotes a swap's insurance fee as (raw, smoothed), where smoothed is what
+    /// `record_swap_volume` would charge the caller as trader in this block
+    function calculateSmoothedInsuranceFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external view returns (uint256, uint256);
+
+    /// Returns up to `count` of a pool's most recently recorded swap and flash fees as
+    /// (fees, amounts, timestamps), newest first
+    /// Only the last 32 are retained; asking for more returns what is there.
+    function getRecentFees(bytes32 pool_id, uint256 count) external view returns (uint256[] memory, uint256[] memory, uint256[] memory);
+
+    /// Records a swap like `record_swap_volume` unless the caller already used `key`, in which
+    /// case the first call's fee is returned and nothing is recorded again
+    function recordSwapVolumeIdempotent(bytes32 key, bytes32 pool_id, address trader, uint256 amount, uint256 total_liquidity, uint256 total_volume) external returns (uint256);
+
+    /// Returns whether the caller has consumed an idempotency key
+    function isIdempotencyKeyUsed(address caller, bytes32 key) external view returns (bool);
+
+    /// Prepays ETH toward the insurance fees of the swaps the caller records on a pool
+    function depositCoverageBudget(bytes32 pool_id) external payable returns (uint256);
+
+    /// Withdraws unused coverage budget back to the caller
+    function withdrawCoverageBudget(bytes32 pool_id, uint256 amount) external;
+
+    /// Returns a payer's remaining coverage budget on a pool
+    function getCoverageBudget(bytes32 pool_id, address payer) external view returns (uint256);
+
+    /// Assigns the swap hook allowed to push a pool's realized figures, callable only by the owner
+    /// The hook's IL writes bypass the updater guards, so it is never left to pool operators.
+    function setPoolHook(bytes32 pool_id, address hook) external;
+
+    /// Returns a pool's swap hook
+    function getPoolHook(bytes32 pool_id) external view returns (address);
+
+    /// Records a swap pushed by the pool's hook and folds its price into the smoothed IL
+    /// The price joins a ring of the last 16 observations. Once the ring is full, the IL of
+    /// holding from the oldest price to `price_after`, `1 - 2 * sqrt(r) / (1 + r)`, is blended
+    /// into the stored IL with a 10% weight, and each swap moves it no further than the pool's
+    /// IL deviation band lets an updater.
+    function onAfterSwap(bytes32 pool_id, uint256 swap_volume, uint256 price_after) external returns (uint256);
+
+    /// Sets how far a price-aware quote's price may stray from the pool's reference, in basis
+    /// points (zero disables the breaker)
+    function setCircuitBreakerBps(bytes32 pool_id, uint256 max_deviation_bps) external;
+
+    /// Skips or restores a pool's circuit breaker while its reference recovers
+    function setCircuitBreakerBypass(bytes32 pool_id, bool bypassed) external;
+
+    /// Returns a pool's (max_deviation_bps, bypassed) circuit breaker configuration
+    function getCircuitBreaker(bytes32 pool_id) external view returns (uint256, bool);
+
+    /// Returns the mean of a pool's retained price observations (zero before the first)
+    function getReferencePrice(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns a pool's recorded (volume, swap_count, fees)
+    function getSwapStats(bytes32 pool_id) external view returns (uint256, uint256, uint256);
+
+    /// Computes an LP's pro-rata share of a period's fees, rounded down
+    function calculateLpShare(bytes32 pool_id, uint256 lp_liquidity, uint256 total_liquidity, uint256 period_fees) external view returns (uint256);
+
+    /// Records fees collected for a pool's LPs outside the contract, along with the pool's
+    /// current total liquidity, and advances its fee growth accumulator
+    /// Premiums credited to reserves and the LP share of flash fees advance the accumulator
+    /// as they are collected, spread over the liquidity last recorded here or by a lender; a
+    /// zero `fees` only updates that liquidity.
+    function recordLpFees(bytes32 pool_id, uint256 fees, uint256 total_liquidity) external;
+
+    /// Returns a pool's cumulative fees per unit of liquidity, WAD-scaled
+    function getFeeGrowthGlobal(bytes32 pool_id) external view returns (uint256);
+
+    /// Computes the fees owed to an LP since its fee growth checkpoint, rounded down
+    function claimableFees(bytes32 pool_id, uint256 lp_liquidity, uint256 fee_growth_checkpoint) external view returns (uint256);
+
+    /// Returns the liquidity a pool's collected fees are currently spread over (zero until
+    /// its admin or a lender reports it)
+    function getFeeGrowthLiquidity(bytes32 pool_id) external view returns (uint256);
+
+    /// Calculates insurance fee for a trade
+    /// `timestamp` must be within 15 seconds of the block time; pricing always uses the block
+    /// time itself.
+    function calculateInsuranceFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 current_price, uint256 timestamp) external view returns (uint256);
+
+    /// Derives a pool id as `keccak256(abi.encode(token0, token1, fee_tier))` with the two
+    /// tokens sorted ascending, so either order gives the same id
+    /// This is the Uniswap v3 pool salt (`PoolAddress.computeAddress` in v3-periphery), whose
+    /// `uint24` fee encodes to the same 32-byte word; off-chain it is
+    /// `keccak256(AbiCoder.defaultAbiCoder().encode(["address", "address", "uint256"], [token0, token1, feeTier]))`
+    /// in ethers v6 with the tokens sorted first. Identical tokens or a zero address revert
+    /// with `InvalidInput`.
+    function computePoolId(address token0, address token1, uint256 fee_tier) external view returns (bytes32);
+
+    /// Calculates insurance fee for a trade on the pool identified by its tokens and fee tier,
+    /// as `calculate_insurance_fee` on `compute_pool_id`
+    function calculateInsuranceFeeForTokens(address token0, address token1, uint256 fee_tier, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 current_price, uint256 timestamp) external view returns (uint256);
+
+    /// Calculates the insurance fee for an exact-output trade, priced on the input it implies
+    /// `current_price` is output per unit of input (WAD). The spot input `amount_out / price`
+    /// is grossed up for constant-product impact against `total_liquidity` as the input-side
+    /// reserve, `spot * liquidity / (liquidity - spot)`, rounding up, and the result is quoted
+    /// like `calculate_insurance_fee_now`. The quote therefore matches the exact-in quote for
+    /// that implied input exactly, and the exact-in quote for the spot input to within the
+    /// impact, a relative `spot / (liquidity - spot)` more input. A zero price, or an implied
+    /// input reaching the pool's liquidity, reverts with `InvalidInput`.
+    function calculateInsuranceFeeExactOut(bytes32 pool_id, uint256 amount_out, uint256 total_liquidity, uint256 total_volume, uint256 current_price) external view returns (uint256);
+
+    /// Calculates the premium to cover an LP withdrawing `lp_notional` from a pool
+    /// The IL realized as the price moved from `entry_price` to `current_price` replaces the
+    /// pool's stored IL, and the withdrawal's share of `total_liquidity` drives the size term,
+    /// with the pool's other risk multipliers applied as for a trade and volume left neutral.
+    /// An exit at the entry price therefore prices close to the base fee. Zero prices or
+    /// notional, or a withdrawal above the pool's liquidity, revert with `InvalidInput`.
+    function calculateExitFee(bytes32 pool_id, uint256 lp_notional, uint256 total_liquidity, uint256 entry_price, uint256 current_price) external view returns (uint256);
+
+    /// Estimates a trade's insurance fee without ever reverting, returning (fee, saturated)
+    /// Returns the live quote whenever it succeeds. Otherwise the internal formula is rerun
+    /// with every step saturating at `U256::MAX` instead of overflowing, skipping the quote's
+    /// gates (registration, data age) and any external formula, and `saturated` is set to flag
+    /// the result as an estimate. For dashboards and estimators only: payment paths always
+    /// price with the checked quote.
+    function calculateInsuranceFeeSaturating(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external view returns (uint256, bool);
+
+    /// Calculates insurance fee for a trade at the current block time
+    /// Reverts with `PriceCircuitBreaker` when `current_price` trips the pool's breaker.
+    function calculateInsuranceFeeNow(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 current_price) external view returns (uint256);
+
+    /// Calculates insurance fee for a trade in token units of the pool's token0 or token1
+    /// The fee is computed in token0 (WAD) and, with `denominate_in_token1`, converted at
+    /// `current_price` (token1 per token0, WAD). Either way it is rescaled to that token's
+    /// configured decimals, every step rounding up. A zero price reverts in both modes.
+    function calculateInsuranceFeeDenominated(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 current_price, bool denominate_in_token1) external view returns (uint256);
+
+    /// Quotes the insurance fee a trade would pay if the pool's IL were `il_override`
+    /// With `use_override` unset this is exactly the live quote; either way nothing is written.
+    function simulateInsuranceFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 il_override, bool use_override) external view returns (uint256);
+
+    /// Quotes a swap's insurance fee with a surcharge for the price impact its expected output
+    /// implies
+    /// The execution price is compared with the pool's token1-per-token0 reference price:
+    /// `expected_amount_out / amount_in` (WAD) for a token0-in swap (`zero_for_one`), inverted
+    /// to `amount_in / expected_amount_out` for a token1-in swap. The shortfall against the
+    /// reference, in the direction that hurts the trader, as a fraction of the reference adds
+    /// `fee * 5 * impact`, with impact capped at 20%. Execution at or better than the
+    /// reference, or a pool without a reference price, quotes the standard fee.
+    function calculateInsuranceFeeWithSlippage(bytes32 pool_id, uint256 amount_in, uint256 expected_amount_out, bool zero_for_one, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Stores a named stress scenario: an IL shock multiplier (WAD, zero leaves IL live), a
+    /// utilization override for flash quotes (WAD, zero leaves it live), and a haircut on
+    /// volume in basis points
+    function setStressScenario(bytes32 name, uint256 il_shock, uint256 utilization, uint256 volume_haircut_bps) external;
+
+    /// Returns a stress scenario's (il_shock, utilization, volume_haircut_bps)
+    function getStressScenario(bytes32 name) external view returns (uint256, uint256, uint256);
+
+    /// Quotes a trade's insurance fee under a stress scenario applied to live data
+    /// The pool's IL is multiplied by the shock (capped at 100%) and the volume cut by the
+    /// haircut. An unset scenario quotes exactly the live fee; nothing is written either way.
+    function calculateInsuranceFeeStressed(bytes32 pool_id, bytes32 scenario, uint256 amount, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Quotes the insurance fee for a trade together with the address that should receive it
+    function quoteWithRecipient(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external view returns (uint256, address);
+
+    /// Sets the address a pool's fees are routed to; zero falls back to the global default
+    function setFeeRecipient(bytes32 pool_id, address recipient) external;
+
+    /// Sets the address fees are routed to for pools without their own recipient
+    function setDefaultFeeRecipient(address recipient) external;
+
+    /// Returns the global default fee recipient (zero if unset)
+    function defaultFeeRecipient() external view returns (address);
+
+    /// Returns the address a pool's fees should be sent to, reverting if none is configured
+    function getFeeRecipient(bytes32 pool_id) external view returns (address);
+
+    /// Converts a WAD fee into settlement currency units with `target_decimals` decimals
+    /// `conversion_rate_wad` is the settlement amount one unit of the fee's token is worth and
+    /// must lie within [1e-12, 1e12]. Both the conversion and the rescale round up, so the
+    /// settlement amount never undercharges the fee.
+    function convertFee(uint256 fee, uint256 conversion_rate_wad, uint8 target_decimals) external view returns (uint256);
+
+    /// Calculates the insurance fee for a trade in settlement currency units, via `convert_fee`
+    function calculateInsuranceFeeConverted(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 conversion_rate_wad, uint8 target_decimals) external view returns (uint256);
+
+    /// Calculates the insurance fee for a trade in USD (WAD) at the pool's price feed
+    /// The feed's answer is normalized from its own decimals to WAD, checked against the pool's
+    /// circuit breaker like any other price-aware quote, and applied as in `convert_fee`,
+    /// rounding up.
+    function calculateInsuranceFeeUsd(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Sets the USD price feed for a pool's token (zero removes it)
+    function setPriceFeed(bytes32 pool_id, address feed) external;
+
+    /// Returns a pool's USD price feed
+    function getPriceFeed(bytes32 pool_id) external view returns (address);
+
+    /// Sets the oldest price feed answer accepted, in seconds (zero restores the default)
+    function setOracleMaxAge(uint256 max_age) external;
+
+    /// Returns the oldest price feed answer accepted, in seconds
+    function oracleMaxAge() external view returns (uint256);
+
+    /// Calculates insurance fee for a trade against liquidity and volume read from the pool's
+    /// data provider
+    function calculateInsuranceFeeFromProvider(bytes32 pool_id, uint256 amount) external view returns (uint256);
+
+    /// Calculates flash loan fee for a borrowing from a pool whose liquidity is read from its
+    /// data provider
+    function calculatePoolFlashLoanFeeFromProvider(bytes32 pool_id, uint256 amount, uint256 utilization_rate, uint256 default_history) external view returns (uint256);
+
+    /// Registers the adapter a pool's liquidity and volume are read from (zero removes it)
+    function setDataProvider(bytes32 pool_id, address provider) external;
+
+    /// Returns a pool's data provider
+    function getDataProvider(bytes32 pool_id) external view returns (address);
+
+    /// Calculates the fee for insuring a trade under a product
+    function calculateProductInsuranceFee(bytes32 pool_id, uint8 product_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Calculates insurance fee for a trade whose inputs are in token units rather than WAD
+    /// `amount`, `total_liquidity` and `total_volume` carry `amount_decimals` decimals and
+    /// `current_price` carries `price_decimals`; both must be at most 36. Everything is
+    /// rescaled to WAD (rounding down) and priced as `calculate_insurance_fee_now`. The fee is
+    /// a WAD rate like every other quote, so it is returned as is rather than in token units.
+    function calculateInsuranceFeeUnits(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 current_price, uint8 amount_decimals, uint8 price_decimals) external view returns (uint256);
+
+    /// Returns the largest trade whose insurance fee fits within `fee_budget`
+    /// The fee only grows with amount, so this binary searches the full `U256` range in a
+    /// fixed 256 steps: the result's fee is within budget and one wei more would exceed it
+    /// (or overflow). Returns zero for a zero budget or when even a zero-size trade exceeds it,
+    /// and `U256::MAX` when the fee never does.
+    function maxAmountForFee(bytes32 pool_id, uint256 fee_budget, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Returns the marginal insurance fee per unit of amount at `amount` (WAD)
+    /// Computed as the discrete slope `(fee(amount + step) - fee(amount)) * 1e18 / step` with
+    /// `step = amount / 1000` (0.1%), never below 1 wei, so amounts under 1000 wei step by one.
+    /// When `amount + step` overflows or cannot be priced, the slope is taken backwards over
+    /// `[amount - step, amount]` instead.
+    function getFeeSensitivity(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume) external view returns (uint256);
+
+    /// Prices insurance across several correlated pools with a diversification discount
+    /// Blends linearly between the root-sum-of-squares of the per-pool fees at zero
+    /// correlation and their plain sum at full correlation (1e18).
+    function calculatePortfolioFee(bytes32[] memory pool_ids, uint256[] memory notionals, uint256 correlation_wad, uint256[] memory total_liquidity, uint256[] memory total_volume) external view returns (uint256);
+
+    /// Prices a trade split across several pools, returning (total_fee, per-leg fees)
+    /// Each leg is quoted exactly as a standalone trade on its own pool, and the total is the
+    /// plain sum of the legs. At most 16 legs are accepted.
+    function calculateSplitInsuranceFee(bytes32[] memory pool_ids, uint256[] memory leg_amounts, uint256[] memory leg_liquidity, uint256[] memory total_volume) external view returns (uint256, uint256[] memory);
+
+    /// Calculates flash loan fee for a borrowing
+    /// `default_history` above the configured ceiling is priced at the ceiling.
+    function calculateFlashLoanFee(uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256);
+
+    /// Calculates flash loan fee for a borrowing from the pool identified by its tokens and fee
+    /// tier, as `calculate_pool_flash_loan_fee` on `compute_pool_id`
+    function calculatePoolFlashLoanFeeForTokens(address token0, address token1, uint256 fee_tier, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256);
+
+    /// Estimates a flash loan's fee without ever reverting, returning (fee, saturated)
+    /// Returns `calculate_flash_loan_fee` whenever it succeeds. Otherwise utilization is
+    /// clamped to 100% and each multiplication saturates at `U256::MAX`, with `saturated`
+    /// reporting whether anything was clamped. For dashboards and estimators only: payment
+    /// paths always price with the checked calculators.
+    function calculateFlashLoanFeeSaturating(uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256, bool);
+
+    /// Calculates flash loan fee for a borrowing from a specific pool, applying its multiplier
+    /// Flash amounts already recorded against the pool in this block count towards utilization
+    /// on top of `utilization_rate`.
+    function calculatePoolFlashLoanFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256);
+
+    /// Returns what a borrower repays for a pool flash loan: `amount` plus the fee rounded up
+    /// The fee part is never below `calculate_pool_flash_loan_fee` for the same inputs and at
+    /// most 1 wei above it.
+    function flashLoanRepaymentAmount(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256);
+
+    /// Calculates a pool's flash loan fee as if `already_borrowed_this_block` had been recorded
+    /// against it earlier in the block, for simulating a sequence of loans
+    function simulatePoolFlashLoanFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history, uint256 already_borrowed_this_block) external view returns (uint256);
+
+    /// Quotes a pool flash loan's fee under a stress scenario, pricing at the scenario's
+    /// utilization in place of `utilization_rate` and this block's earlier loans
+    /// A scenario without a utilization override quotes exactly the live fee.
+    function calculatePoolFlashLoanFeeStressed(bytes32 pool_id, bytes32 scenario, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256);
+
+    /// Returns the flash amount recorded against a pool so far in the current block
+    function getFlashBorrowedThisBlock(bytes32 pool_id) external view returns (uint256);
+
+    /// Calculates a pool's flash loan fee using its tracked default score as the default history
+    function calculateTrackedFlashLoanFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate) external view returns (uint256);
+
+    /// Calculates a pool's flash loan fee in settlement currency units, via `convert_fee`
+    function calculatePoolFlashLoanFeeConverted(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history, uint256 conversion_rate_wad, uint8 target_decimals) external view returns (uint256);
+
+    /// Calculates a pool's flash loan fee split into its (lp_portion, insurance_portion)
+    /// The insurance portion is the pool's insurance share of the fee rounded up, and the LP
+    /// portion is the remainder, so the two always sum to the full fee.
+    function calculatePoolFlashLoanFeeSplit(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256, uint256);
+
+    /// Records a flash loan's ETH fee, crediting the insurance portion to the pool's fund and
+    /// holding the LP portion for the pool's admin, returning (lp_portion, insurance_portion)
+    /// Callable only by lender contracts. Any value above the fee is refunded.
+    function recordFlashLoan(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external payable returns (uint256, uint256);
+
+    /// Records a flash loan like `record_flash_loan` unless the caller already used `key`, in
+    /// which case the first call's split is returned, any attached value is refunded, and
+    /// nothing is recorded again
+    function recordFlashLoanIdempotent(bytes32 key, bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external payable returns (uint256, uint256);
+
+    /// Sets the share of a pool's flash fees routed to its insurance fund, in basis points
+    function setInsuranceShareBps(bytes32 pool_id, uint256 share_bps) external;
+
+    /// Returns the share of a pool's flash fees routed to its insurance fund, in basis points
+    function getInsuranceShareBps(bytes32 pool_id) external view returns (uint256);
+
+    /// Returns the LP share of a pool's recorded flash fees awaiting withdrawal
+    function getFlashLpFees(bytes32 pool_id) external view returns (uint256);
+
+    /// Withdraws the LP share of a pool's recorded flash fees, callable by the pool's admin
+    function withdrawFlashLpFees(bytes32 pool_id, address to, uint256 amount) external;
+
+    /// Calculates a pool's flash loan fee at spot utilization blended with its time-weighted
+    /// utilization over the configured window
+    /// The blend is `twu * weight_bps / 10_000 + spot * (10_000 - weight_bps) / 10_000`. Pools
+    /// without checkpoints price at spot.
+    function calculateTimeWeightedFlashLoanFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 utilization_rate, uint256 default_history) external view returns (uint256);
+
+    /// Records a pool's current utilization (WAD fraction) and advances its running total
+    function checkpointUtilization(bytes32 pool_id, uint256 utilization) external;
+
+    /// Returns a pool's average utilization over the last `window` seconds (WAD)
+    /// A window reaching past the oldest retained checkpoint averages from that checkpoint
+    /// instead. Reverts for a zero window or a pool with no checkpoints.
+    function getTimeWeightedUtilization(bytes32 pool_id, uint256 window) external view returns (uint256);
+
+    /// Configures the time-weighted utilization window in seconds (zero restores the default)
+    /// and its blend weight against spot in basis points (zero prices off spot alone)
+    function setTwuParams(uint256 window, uint256 weight_bps) external;
+
+    /// Returns the effective (window, weight_bps) time-weighted utilization parameters
+    function getTwuParams() external view returns (uint256, uint256);
+
+    /// Sets the ceiling on the default history flash fees are priced with (WAD, zero restores
+    /// the default)
+    function setMaxDefaultHistory(uint256 cap) external;
+
+    /// Returns the ceiling on the default history flash fees are priced with
+    function maxDefaultHistory() external view returns (uint256);
+
+    /// Grants or revokes a lender contract's right to report flash loan outcomes
+    function setLender(address lender, bool authorized) external;
+
+    /// Returns whether an address may report flash loan outcomes
+    function isLender(address lender) external view returns (bool);
+
+    /// Configures the default score ceiling (WAD) and the share each clean repayment decays it
+    /// by, in basis points; zero restores either default
+    function setDefaultScoreParams(uint256 max_score, uint256 decay_bps) external;
+
+    /// Returns the effective (max_score, decay_bps) default score parameters
+    function getDefaultScoreParams() external view returns (uint256, uint256);
+
+    /// Returns a pool's tracked default score (WAD)
+    function getDefaultScore(bytes32 pool_id) external view returns (uint256);
+
+    /// Records a flash loan default on a pool, raising its score by `severity` up to the ceiling
+    function recordDefault(bytes32 pool_id, uint256 severity) external returns (uint256);
+
+    /// Records a clean flash loan repayment on a pool, decaying its score
+    /// The remaining score is rounded down, so repeated repayments always reach zero.
+    function recordRepayment(bytes32 pool_id) external returns (uint256);
+}
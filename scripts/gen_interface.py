@@ -0,0 +1,288 @@
+#!/usr/bin/env python3
+"""Regenerates IInsuranceCalculator.sol and the conformance lists in src/interface.rs.
+
+The interface is derived from the contract exactly as `cargo stylus export-abi` derives the
+ABI: every `pub fn` of the `#[public]` impl in src/lib.rs becomes an external function named
+in lowerCamelCase, with Rust types mapped to their Solidity equivalents, `&self` methods marked
+`view` and `#[payable]` ones `payable`. Each function, and each error from its `Error` variant,
+carries the Rust doc comment as NatSpec. Events and errors come from the `sol!` block.
+
+Run from the crate root after changing any public method, error or event:
+
+    python3 scripts/gen_interface.py
+
+The build then checks the result: `src/interface.rs` asserts every listed method's Rust
+signature and exported selector against the generated file, so a stale file fails to compile.
+"""
+
+import re
+import sys
+import textwrap
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+LIB = ROOT / "src" / "lib.rs"
+INTERFACE_RS = ROOT / "src" / "interface.rs"
+INTERFACE_SOL = ROOT / "IInsuranceCalculator.sol"
+
+# The published ERC-165 id covers only the pricing functions, so it never moves as the wider
+# interface grows
+PRICING_FUNCTIONS = [
+    "calculateInsuranceFee",
+    "calculateInsuranceFeeNow",
+    "calculatePortfolioFee",
+    "calculateFlashLoanFee",
+    "calculatePoolFlashLoanFee",
+    "quoteCoveragePremium",
+]
+
+HEADER = """// SPDX-License-Identifier: MIT-OR-APACHE-2.0
+pragma solidity ^0.8.23;
+
+// Generated by scripts/gen_interface.py from src/lib.rs; regenerate rather than editing by hand.
+
+/// Canonical interface of the insurance calculator: every external function, error and event.
+/// Its ERC-165 id, 0x5fa5751e, is the XOR of the pricing function selectors only:
+/// {pricing}
+interface IInsuranceCalculator {{
+"""
+
+
+def split_top(text, sep=","):
+    """Splits on `sep` outside any brackets"""
+    parts, depth, current = [], 0, ""
+    for ch in text:
+        if ch in "(<[":
+            depth += 1
+        elif ch in ")>]":
+            depth -= 1
+        if ch == sep and depth == 0:
+            parts.append(current.strip())
+            current = ""
+        else:
+            current += ch
+    if current.strip():
+        parts.append(current.strip())
+    return parts
+
+
+def lower_camel(name):
+    head, *rest = name.split("_")
+    return head + "".join(part[:1].upper() + part[1:] for part in rest)
+
+
+def doc_lines(lines):
+    return [line for line in lines if line]
+
+
+def read_docs(lines, index):
+    """Collects the `///` lines above `lines[index]`, skipping attributes"""
+    docs = []
+    i = index - 1
+    while i >= 0:
+        line = lines[i].strip()
+        if line.startswith("///"):
+            docs.insert(0, line[3:].strip())
+        elif not line.startswith("#["):
+            break
+        i -= 1
+    return docs
+
+
+def parse_aliases(src):
+    """Returns {name: (fields, doc)} for the `pub type X = (...);` tuple aliases"""
+    aliases = {}
+    lines = src.split("\n")
+    for i, line in enumerate(lines):
+        m = re.match(r"pub type (\w+) = \((.*)\);", line)
+        if m:
+            aliases[m.group(1)] = (split_top(m.group(2)), read_docs(lines, i))
+    return aliases
+
+
+def parse_sol_block(src):
+    block = src[src.index("\nsol! {") : src.index("\nsol_interface! {")]
+    errors = [(name, " ".join(params.split())) for name, params in re.findall(r"^\s*error (\w+)\((.*?)\);", block, re.M | re.S)]
+    events = [" ".join(event.split()).replace("( ", "(") for event in re.findall(r"^\s*event (\w+\(.*?\));", block, re.M | re.S)]
+    return errors, events
+
+
+def parse_error_docs(src):
+    body = src[src.index("pub enum Error {") :]
+    body = body[: body.index("\n}")]
+    docs, pending = {}, []
+    for line in body.split("\n"):
+        line = line.strip()
+        if line.startswith("///"):
+            pending.append(line[3:].strip())
+        elif re.match(r"\w+\(\w+\),", line):
+            docs[line.split("(")[0]] = pending
+            pending = []
+    return docs
+
+
+def parse_methods(src):
+    lines = src.split("\n")
+    start = next(i for i, line in enumerate(lines) if line.startswith("#[public]"))
+    end = next(i for i in range(start + 2, len(lines)) if lines[i] == "}")
+    methods = []
+    i = start + 2
+    while i < end:
+        if not lines[i].strip().startswith("pub fn "):
+            i += 1
+            continue
+        docs = read_docs(lines, i)
+        attrs = []
+        j = i - 1
+        while lines[j].strip().startswith(("#[", "///")):
+            if lines[j].strip().startswith("#["):
+                attrs.append(lines[j].strip())
+            j -= 1
+        sig = lines[i].strip()
+        while "{" not in sig.split(")")[-1] or sig.count("(") != sig.count(")"):
+            i += 1
+            sig += " " + lines[i].strip()
+        name = re.match(r"pub fn (\w+)\(", sig).group(1)
+        open_at = sig.index("(") + 1
+        depth, k = 1, open_at
+        while depth:
+            depth += {"(": 1, ")": -1}.get(sig[k], 0)
+            k += 1
+        params = split_top(sig[open_at : k - 1])
+        rest = sig[k:].strip()
+        ret = rest[2:].rsplit("{", 1)[0].strip() if rest.startswith("->") else None
+        receiver, params = params[0], [tuple(p.strip() for p in param.split(":", 1)) for param in params[1:]]
+        methods.append(
+            {
+                "name": name,
+                "docs": docs,
+                "mut": receiver == "&mut self",
+                "payable": "#[payable]" in attrs,
+                "params": params,
+                "ret": ret,
+            }
+        )
+        i += 1
+    return methods
+
+
+class Types:
+    """Maps Rust types to Solidity, collecting the tuple aliases that need a struct"""
+
+    SCALARS = {"U256": "uint256", "Address": "address", "bool": "bool", "u8": "uint8", "Bytes": "bytes"}
+
+    def __init__(self, aliases):
+        self.aliases = aliases
+        self.structs = []
+
+    def sol(self, rust):
+        if rust in self.SCALARS:
+            return self.SCALARS[rust]
+        m = re.fullmatch(r"FixedBytes<(\d+)>", rust)
+        if m:
+            return "bytes" + m.group(1)
+        m = re.fullmatch(r"Vec<(.*)>", rust)
+        if m:
+            return self.sol(m.group(1)) + "[]"
+        if rust in self.aliases:
+            if rust not in self.structs:
+                self.structs.append(rust)
+            return rust
+        raise SystemExit(f"no Solidity mapping for Rust type `{rust}`")
+
+    def located(self, rust):
+        sol = self.sol(rust)
+        dynamic = sol.endswith("[]") or sol == "bytes" or rust in self.aliases
+        return sol + " memory" if dynamic else sol
+
+    def returns(self, ret):
+        """Flattens a return type into its Solidity return list, as the router encodes it"""
+        if ret is None:
+            return []
+        m = re.fullmatch(r"Result<(.*), Error>", ret)
+        if m:
+            ret = m.group(1)
+        if ret in self.aliases:
+            return [self.located(t) for t in self.aliases[ret][0]]
+        if ret.startswith("("):
+            return [self.located(t) for t in split_top(ret[1:-1])]
+        return [self.located(ret)]
+
+    def struct(self, name):
+        fields, doc = self.aliases[name]
+        names = re.search(r"\(([a-z0-9_,\s]+)\)", " ".join(doc)).group(1).replace(" ", "").split(",")
+        if len(names) != len(fields):
+            raise SystemExit(f"`{name}` documents {len(names)} fields but has {len(fields)}")
+        body = "".join(f"        {self.sol(t)} {n};\n" for t, n in zip(fields, names))
+        docs = "".join(f"    /// {line}\n" for line in doc_lines(doc))
+        return f"{docs}    struct {name} {{\n{body}    }}\n"
+
+
+def render_sol(methods, errors, events, error_docs, types):
+    functions = []
+    for method in methods:
+        params = ", ".join(f"{types.located(t)} {n}" for n, t in method["params"])
+        mutability = " payable" if method["payable"] else "" if method["mut"] else " view"
+        returns = types.returns(method["ret"])
+        returns = f" returns ({', '.join(returns)})" if returns else ""
+        docs = "".join(f"    /// {line}\n" for line in doc_lines(method["docs"]))
+        functions.append(f"{docs}    function {lower_camel(method['name'])}({params}) external{mutability}{returns};\n")
+    pricing = textwrap.wrap(", ".join(f"`{name}`" for name in PRICING_FUNCTIONS) + ".", width=96)
+    out = HEADER.format(pricing="\n/// ".join(pricing))
+    out += "".join(types.struct(name) + "\n" for name in types.structs)
+    for name, params in errors:
+        out += "".join(f"    /// {line}\n" for line in doc_lines(error_docs.get(name, [])))
+        out += f"    error {name}({params});\n"
+    out += "\n"
+    out += "".join(f"    event {event};\n" for event in events)
+    out += "\n" + "\n".join(functions) + "}\n"
+    return out
+
+
+def wrap_names(names, indent="    ", width=100):
+    lines, line = [], indent
+    for name in names:
+        item = name + ","
+        if len(line) + len(item) + 1 > width and line.strip():
+            lines.append(line.rstrip())
+            line = indent
+        line += item + " "
+    lines.append(line.rstrip())
+    return "\n".join(lines)
+
+
+def render_conformance(methods):
+    entries = []
+    for method in methods:
+        receiver = "&mut InsuranceCalculator" if method["mut"] else "&InsuranceCalculator"
+        name = lower_camel(method["name"])
+        args = "".join(f", {t}" for _, t in method["params"])
+        ret = method["ret"] or "()"
+        entries.append(f"    {method['name']}({receiver}) => {name}Call(\"{name}\"{args}) -> {ret};")
+    return "\n".join(entries)
+
+
+def replace_block(text, opener, closer, body):
+    start = text.index(opener) + len(opener)
+    end = text.index(closer, start)
+    return text[:start] + "\n" + body + "\n" + text[end:]
+
+
+def main():
+    src = LIB.read_text()
+    aliases = parse_aliases(src)
+    errors, events = parse_sol_block(src)
+    methods = parse_methods(src)
+    types = Types(aliases)
+    INTERFACE_SOL.write_text(render_sol(methods, errors, events, parse_error_docs(src), types))
+
+    rs = INTERFACE_RS.read_text()
+    rs = replace_block(rs, "same_errors!(", ");", wrap_names([name for name, _ in errors]))
+    rs = replace_block(rs, "same_events!(", ");", wrap_names([event.split("(")[0] for event in events]))
+    rs = replace_block(rs, "conforms! {", "\n}", render_conformance(methods))
+    INTERFACE_RS.write_text(rs)
+    print(f"{len(methods)} functions, {len(errors)} errors, {len(events)} events", file=sys.stderr)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,445 @@
+//! Canonical `IInsuranceCalculator` interface, checked against the contract at compile time
+
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, FixedBytes, U256},
+    alloy_sol_types::{sol, SolCall, SolError, SolEvent},
+    function_selector,
+};
+
+use crate::{Error, InsuranceCalculator, ParameterBundle, PoolConfig};
+
+sol!("IInsuranceCalculator.sol");
+
+/// Solidity source of the interface with every function, error and event and their NatSpec,
+/// generated by `scripts/gen_interface.py`, printed alongside the exported ABI and written to
+/// `target/abi/IInsuranceCalculator.sol`
+pub const INTERFACE_SOURCE: &str = include_str!("../IInsuranceCalculator.sol");
+
+/// Selectors of the interface's pricing functions
+pub const CALCULATE_INSURANCE_FEE_SELECTOR: [u8; 4] = IInsuranceCalculator::calculateInsuranceFeeCall::SELECTOR;
+pub const CALCULATE_INSURANCE_FEE_NOW_SELECTOR: [u8; 4] = IInsuranceCalculator::calculateInsuranceFeeNowCall::SELECTOR;
+pub const CALCULATE_PORTFOLIO_FEE_SELECTOR: [u8; 4] = IInsuranceCalculator::calculatePortfolioFeeCall::SELECTOR;
+pub const CALCULATE_FLASH_LOAN_FEE_SELECTOR: [u8; 4] = IInsuranceCalculator::calculateFlashLoanFeeCall::SELECTOR;
+pub const CALCULATE_POOL_FLASH_LOAN_FEE_SELECTOR: [u8; 4] = IInsuranceCalculator::calculatePoolFlashLoanFeeCall::SELECTOR;
+pub const QUOTE_COVERAGE_PREMIUM_SELECTOR: [u8; 4] = IInsuranceCalculator::quoteCoveragePremiumCall::SELECTOR;
+
+/// ERC-165 id of the interface: the XOR of its pricing function selectors (0x5fa5751e)
+pub const INTERFACE_ID: FixedBytes<4> = FixedBytes::new(xor_selectors(&[
+    CALCULATE_INSURANCE_FEE_SELECTOR,
+    CALCULATE_INSURANCE_FEE_NOW_SELECTOR,
+    CALCULATE_PORTFOLIO_FEE_SELECTOR,
+    CALCULATE_FLASH_LOAN_FEE_SELECTOR,
+    CALCULATE_POOL_FLASH_LOAN_FEE_SELECTOR,
+    QUOTE_COVERAGE_PREMIUM_SELECTOR,
+]));
+
+const fn xor_selectors(selectors: &[[u8; 4]]) -> [u8; 4] {
+    let mut id = [0u8; 4];
+    let mut i = 0;
+    while i < selectors.len() {
+        let mut j = 0;
+        while j < 4 {
+            id[j] ^= selectors[i][j];
+            j += 1;
+        }
+        i += 1;
+    }
+    id
+}
+
+// Only referenced from unnamed consts, which the dead-code lint does not see
+#[allow(dead_code)]
+const fn bytes_equal<const N: usize>(a: [u8; N], b: [u8; N]) -> bool {
+    let mut i = 0;
+    while i < N {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Fails the build unless each `#[public]` method has exactly the Rust signature given and its
+/// exported selector equals the interface's
+macro_rules! conforms {
+    ($($method:ident($recv:ty) => $call:ident($name:literal $(, $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            const _: fn($recv $(, $ty)*) -> $ret = InsuranceCalculator::$method;
+            const _: () = assert!(bytes_equal(
+                function_selector!($name $(, $ty)*),
+                <IInsuranceCalculator::$call as SolCall>::SELECTOR,
+            ));
+        )*
+    };
+}
+
+conforms! {
+    initialize(&mut InsuranceCalculator) => initializeCall("initialize") -> Result<(), Error>;
+    owner(&InsuranceCalculator) => ownerCall("owner") -> Address;
+    set_emergency_admin(&mut InsuranceCalculator) => setEmergencyAdminCall("setEmergencyAdmin", Address) -> Result<(), Error>;
+    emergency_admin(&InsuranceCalculator) => emergencyAdminCall("emergencyAdmin") -> Address;
+    emergency_lower_base_fee(&mut InsuranceCalculator) => emergencyLowerBaseFeeCall("emergencyLowerBaseFee", FixedBytes<32>, U256) -> Result<(), Error>;
+    emergency_lower_flash_fee_multiplier(&mut InsuranceCalculator) => emergencyLowerFlashFeeMultiplierCall("emergencyLowerFlashFeeMultiplier", FixedBytes<32>, U256) -> Result<(), Error>;
+    emergency_lower_surge_multiplier(&mut InsuranceCalculator) => emergencyLowerSurgeMultiplierCall("emergencyLowerSurgeMultiplier", FixedBytes<32>, U256) -> Result<(), Error>;
+    emergency_tighten_exposure_cap(&mut InsuranceCalculator) => emergencyTightenExposureCapCall("emergencyTightenExposureCap", FixedBytes<32>, U256) -> Result<(), Error>;
+    supports_interface(&InsuranceCalculator) => supportsInterfaceCall("supportsInterface", FixedBytes<4>) -> bool;
+    apply_parameter_bundle(&mut InsuranceCalculator) => applyParameterBundleCall("applyParameterBundle", ParameterBundle) -> Result<U256, Error>;
+    get_parameters(&InsuranceCalculator) => getParametersCall("getParameters") -> ParameterBundle;
+    parameter_epoch(&InsuranceCalculator) => parameterEpochCall("parameterEpoch") -> U256;
+    get_parameters_at_epoch(&InsuranceCalculator) => getParametersAtEpochCall("getParametersAtEpoch", U256) -> Result<(ParameterBundle, U256), Error>;
+    set_protocol_fee_bps(&mut InsuranceCalculator) => setProtocolFeeBpsCall("setProtocolFeeBps", U256) -> Result<(), Error>;
+    set_treasury(&mut InsuranceCalculator) => setTreasuryCall("setTreasury", Address) -> Result<(), Error>;
+    protocol_fee_bps(&InsuranceCalculator) => protocolFeeBpsCall("protocolFeeBps") -> U256;
+    treasury(&InsuranceCalculator) => treasuryCall("treasury") -> Address;
+    treasury_balance(&InsuranceCalculator) => treasuryBalanceCall("treasuryBalance") -> U256;
+    get_pool_reserves(&InsuranceCalculator) => getPoolReservesCall("getPoolReserves", FixedBytes<32>) -> U256;
+    set_accrual_epoch_length(&mut InsuranceCalculator) => setAccrualEpochLengthCall("setAccrualEpochLength", U256) -> Result<(), Error>;
+    accrual_epoch_length(&InsuranceCalculator) => accrualEpochLengthCall("accrualEpochLength") -> U256;
+    close_epoch(&mut InsuranceCalculator) => closeEpochCall("closeEpoch", FixedBytes<32>) -> Result<U256, Error>;
+    get_streaming_rate(&InsuranceCalculator) => getStreamingRateCall("getStreamingRate", FixedBytes<32>, U256) -> Result<U256, Error>;
+    open_stream(&mut InsuranceCalculator) => openStreamCall("openStream", FixedBytes<32>, U256) -> Result<U256, Error>;
+    checkpoint_stream(&mut InsuranceCalculator) => checkpointStreamCall("checkpointStream", U256) -> Result<U256, Error>;
+    close_stream(&mut InsuranceCalculator) => closeStreamCall("closeStream", U256) -> Result<U256, Error>;
+    get_stream(&InsuranceCalculator) => getStreamCall("getStream", U256) -> (Address, FixedBytes<32>, U256, U256, U256, U256, bool);
+    get_premium_accrual(&InsuranceCalculator) => getPremiumAccrualCall("getPremiumAccrual", FixedBytes<32>) -> (U256, U256, U256);
+    collect_premium(&mut InsuranceCalculator) => collectPremiumCall("collectPremium", FixedBytes<32>) -> Result<(), Error>;
+    withdraw_treasury(&mut InsuranceCalculator) => withdrawTreasuryCall("withdrawTreasury", Address, U256) -> Result<(), Error>;
+    register_pool(&mut InsuranceCalculator) => registerPoolCall("registerPool", FixedBytes<32>, Address, Address, U256) -> Result<(), Error>;
+    register_pool_with_type(&mut InsuranceCalculator) => registerPoolWithTypeCall("registerPoolWithType", FixedBytes<32>, Address, Address, U256, u8) -> Result<(), Error>;
+    get_pool_type(&InsuranceCalculator) => getPoolTypeCall("getPoolType", FixedBytes<32>) -> u8;
+    set_token_decimals(&mut InsuranceCalculator) => setTokenDecimalsCall("setTokenDecimals", FixedBytes<32>, u8, u8) -> Result<(), Error>;
+    get_token_decimals(&InsuranceCalculator) => getTokenDecimalsCall("getTokenDecimals", FixedBytes<32>) -> (u8, u8);
+    set_fee_formula(&mut InsuranceCalculator) => setFeeFormulaCall("setFeeFormula", FixedBytes<32>, Address) -> Result<(), Error>;
+    get_fee_formula(&InsuranceCalculator) => getFeeFormulaCall("getFeeFormula", FixedBytes<32>) -> Address;
+    set_fee_formula_bounds(&mut InsuranceCalculator) => setFeeFormulaBoundsCall("setFeeFormulaBounds", FixedBytes<32>, U256, U256) -> Result<(), Error>;
+    get_fee_formula_bounds(&InsuranceCalculator) => getFeeFormulaBoundsCall("getFeeFormulaBounds", FixedBytes<32>) -> (U256, U256);
+    get_pool_config(&InsuranceCalculator) => getPoolConfigCall("getPoolConfig", FixedBytes<32>) -> PoolConfig;
+    deactivate_pool(&mut InsuranceCalculator) => deactivatePoolCall("deactivatePool", FixedBytes<32>) -> Result<(), Error>;
+    reactivate_pool(&mut InsuranceCalculator) => reactivatePoolCall("reactivatePool", FixedBytes<32>) -> Result<(), Error>;
+    is_pool_active(&InsuranceCalculator) => isPoolActiveCall("isPoolActive", FixedBytes<32>) -> bool;
+    get_pool_metadata(&InsuranceCalculator) => getPoolMetadataCall("getPoolMetadata", FixedBytes<32>) -> (Address, Address, U256, bool, U256);
+    pool_count(&InsuranceCalculator) => poolCountCall("poolCount") -> U256;
+    pool_at(&InsuranceCalculator) => poolAtCall("poolAt", U256) -> Result<FixedBytes<32>, Error>;
+    set_require_registration(&mut InsuranceCalculator) => setRequireRegistrationCall("setRequireRegistration", bool) -> Result<(), Error>;
+    require_registration(&InsuranceCalculator) => requireRegistrationCall("requireRegistration") -> bool;
+    set_pool_operator(&mut InsuranceCalculator) => setPoolOperatorCall("setPoolOperator", FixedBytes<32>, Address) -> Result<(), Error>;
+    get_pool_operator(&InsuranceCalculator) => getPoolOperatorCall("getPoolOperator", FixedBytes<32>) -> Address;
+    set_default_flash_fee_multiplier(&mut InsuranceCalculator) => setDefaultFlashFeeMultiplierCall("setDefaultFlashFeeMultiplier", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_default_flash_fee_multiplier(&InsuranceCalculator) => getDefaultFlashFeeMultiplierCall("getDefaultFlashFeeMultiplier", FixedBytes<32>) -> U256;
+    set_max_flash_amount(&mut InsuranceCalculator) => setMaxFlashAmountCall("setMaxFlashAmount", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_max_flash_amount(&InsuranceCalculator) => getMaxFlashAmountCall("getMaxFlashAmount", FixedBytes<32>) -> U256;
+    max_flash_loan(&InsuranceCalculator) => maxFlashLoanCall("maxFlashLoan", FixedBytes<32>, U256, U256) -> Result<U256, Error>;
+    set_base_fee(&mut InsuranceCalculator) => setBaseFeeCall("setBaseFee", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_base_fee(&InsuranceCalculator) => getBaseFeeCall("getBaseFee", FixedBytes<32>) -> U256;
+    set_il_weight(&mut InsuranceCalculator) => setIlWeightCall("setIlWeight", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_il_weight(&InsuranceCalculator) => getIlWeightCall("getIlWeight", FixedBytes<32>) -> U256;
+    set_risk_preset(&mut InsuranceCalculator) => setRiskPresetCall("setRiskPreset", u8, FixedBytes<32>, U256, U256, U256, U256, U256, U256) -> Result<(), Error>;
+    get_risk_preset(&InsuranceCalculator) => getRiskPresetCall("getRiskPreset", u8) -> Result<(FixedBytes<32>, U256, U256, U256, U256, U256, U256), Error>;
+    apply_preset(&mut InsuranceCalculator) => applyPresetCall("applyPreset", FixedBytes<32>, u8) -> Result<(), Error>;
+    set_product_config(&mut InsuranceCalculator) => setProductConfigCall("setProductConfig", FixedBytes<32>, u8, bool, U256, U256, U256) -> Result<(), Error>;
+    get_product_config(&InsuranceCalculator) => getProductConfigCall("getProductConfig", FixedBytes<32>, u8) -> Result<(U256, U256, U256), Error>;
+    pay_premium(&mut InsuranceCalculator) => payPremiumCall("payPremium", FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    pay_premium_with_tolerance(&mut InsuranceCalculator) => payPremiumWithToleranceCall("payPremiumWithTolerance", FixedBytes<32>, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    deposit_to_fund(&mut InsuranceCalculator) => depositToFundCall("depositToFund", FixedBytes<32>) -> Result<(), Error>;
+    withdraw_from_fund(&mut InsuranceCalculator) => withdrawFromFundCall("withdrawFromFund", FixedBytes<32>, Address, U256) -> Result<(), Error>;
+    set_min_reserve(&mut InsuranceCalculator) => setMinReserveCall("setMinReserve", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_min_reserve(&InsuranceCalculator) => getMinReserveCall("getMinReserve", FixedBytes<32>) -> U256;
+    get_total_reserves(&InsuranceCalculator) => getTotalReservesCall("getTotalReserves") -> U256;
+    quote_coverage_premium(&InsuranceCalculator) => quoteCoveragePremiumCall("quoteCoveragePremium", FixedBytes<32>, U256, U256, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    quote_product_coverage_premium(&InsuranceCalculator) => quoteProductCoveragePremiumCall("quoteProductCoveragePremium", FixedBytes<32>, u8, U256, U256, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    set_apr_reference_size(&mut InsuranceCalculator) => setAprReferenceSizeCall("setAprReferenceSize", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_apr_reference_size(&InsuranceCalculator) => getAprReferenceSizeCall("getAprReferenceSize", FixedBytes<32>) -> U256;
+    get_required_premium_apr(&InsuranceCalculator) => getRequiredPremiumAprCall("getRequiredPremiumApr", FixedBytes<32>, U256, U256) -> Result<U256, Error>;
+    close_rebate_epoch(&mut InsuranceCalculator) => closeRebateEpochCall("closeRebateEpoch", FixedBytes<32>, U256) -> Result<U256, Error>;
+    current_rebate_epoch(&InsuranceCalculator) => currentRebateEpochCall("currentRebateEpoch", FixedBytes<32>) -> U256;
+    get_rebate_epoch(&InsuranceCalculator) => getRebateEpochCall("getRebateEpoch", FixedBytes<32>, U256) -> (U256, U256, U256, bool);
+    calculate_rebate(&InsuranceCalculator) => calculateRebateCall("calculateRebate", FixedBytes<32>, U256, U256) -> Result<U256, Error>;
+    set_max_rebate_bps(&mut InsuranceCalculator) => setMaxRebateBpsCall("setMaxRebateBps", U256) -> Result<(), Error>;
+    max_rebate_bps(&InsuranceCalculator) => maxRebateBpsCall("maxRebateBps") -> U256;
+    set_allowed_deductible(&mut InsuranceCalculator) => setAllowedDeductibleCall("setAllowedDeductible", U256, bool) -> Result<(), Error>;
+    is_deductible_allowed(&InsuranceCalculator) => isDeductibleAllowedCall("isDeductibleAllowed", U256) -> bool;
+    buy_coverage(&mut InsuranceCalculator) => buyCoverageCall("buyCoverage", FixedBytes<32>, U256, U256, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    buy_product_coverage(&mut InsuranceCalculator) => buyProductCoverageCall("buyProductCoverage", FixedBytes<32>, u8, U256, U256, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    buy_coverage_with_tolerance(&mut InsuranceCalculator) => buyCoverageWithToleranceCall("buyCoverageWithTolerance", FixedBytes<32>, U256, U256, U256, U256, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_claim_payout(&InsuranceCalculator) => calculateClaimPayoutCall("calculateClaimPayout", U256, U256) -> Result<U256, Error>;
+    claim_policy(&mut InsuranceCalculator) => claimPolicyCall("claimPolicy", U256) -> Result<U256, Error>;
+    expire_policy(&mut InsuranceCalculator) => expirePolicyCall("expirePolicy", U256) -> Result<U256, Error>;
+    renew_policy(&mut InsuranceCalculator) => renewPolicyCall("renewPolicy", U256, U256, U256, U256) -> Result<U256, Error>;
+    renew_policy_with_tolerance(&mut InsuranceCalculator) => renewPolicyWithToleranceCall("renewPolicyWithTolerance", U256, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    set_auto_renew(&mut InsuranceCalculator) => setAutoRenewCall("setAutoRenew", U256, bool) -> Result<(), Error>;
+    fund_renewal(&mut InsuranceCalculator) => fundRenewalCall("fundRenewal", U256) -> Result<U256, Error>;
+    withdraw_renewal_balance(&mut InsuranceCalculator) => withdrawRenewalBalanceCall("withdrawRenewalBalance", U256) -> Result<U256, Error>;
+    get_renewal_status(&InsuranceCalculator) => getRenewalStatusCall("getRenewalStatus", U256) -> (bool, U256, U256);
+    process_renewal(&mut InsuranceCalculator) => processRenewalCall("processRenewal", U256) -> Result<U256, Error>;
+    transfer_policy(&mut InsuranceCalculator) => transferPolicyCall("transferPolicy", U256, Address) -> Result<(), Error>;
+    get_holder_policies(&InsuranceCalculator) => getHolderPoliciesCall("getHolderPolicies", Address) -> Vec<U256>;
+    cancel_policy(&mut InsuranceCalculator) => cancelPolicyCall("cancelPolicy", U256) -> Result<U256, Error>;
+    calculate_cancellation_refund(&InsuranceCalculator) => calculateCancellationRefundCall("calculateCancellationRefund", U256) -> Result<U256, Error>;
+    set_cancellation_penalty_bps(&mut InsuranceCalculator) => setCancellationPenaltyBpsCall("setCancellationPenaltyBps", U256) -> Result<(), Error>;
+    cancellation_penalty_bps(&InsuranceCalculator) => cancellationPenaltyBpsCall("cancellationPenaltyBps") -> U256;
+    get_refund_balance(&InsuranceCalculator) => getRefundBalanceCall("getRefundBalance", Address) -> U256;
+    withdraw_refund(&mut InsuranceCalculator) => withdrawRefundCall("withdrawRefund") -> Result<U256, Error>;
+    get_policy(&InsuranceCalculator) => getPolicyCall("getPolicy", U256) -> (Address, FixedBytes<32>, U256, U256, U256, U256, u8, U256, U256);
+    get_policy_product(&InsuranceCalculator) => getPolicyProductCall("getPolicyProduct", U256) -> u8;
+    get_policy_coverage_fraction(&InsuranceCalculator) => getPolicyCoverageFractionCall("getPolicyCoverageFraction", U256) -> U256;
+    get_outstanding_coverage(&InsuranceCalculator) => getOutstandingCoverageCall("getOutstandingCoverage", FixedBytes<32>) -> U256;
+    set_open_interest_weight(&mut InsuranceCalculator) => setOpenInterestWeightCall("setOpenInterestWeight", U256) -> Result<(), Error>;
+    open_interest_weight(&InsuranceCalculator) => openInterestWeightCall("openInterestWeight") -> U256;
+    get_total_outstanding_coverage(&InsuranceCalculator) => getTotalOutstandingCoverageCall("getTotalOutstandingCoverage") -> U256;
+    set_systemic_risk_params(&mut InsuranceCalculator) => setSystemicRiskParamsCall("setSystemicRiskParams", U256, U256, U256) -> Result<(), Error>;
+    get_systemic_risk_params(&InsuranceCalculator) => getSystemicRiskParamsCall("getSystemicRiskParams") -> (U256, U256, U256);
+    get_systemic_multiplier(&InsuranceCalculator) => getSystemicMultiplierCall("getSystemicMultiplier") -> Result<U256, Error>;
+    get_open_interest_ratio(&InsuranceCalculator) => getOpenInterestRatioCall("getOpenInterestRatio", FixedBytes<32>, U256) -> Result<U256, Error>;
+    set_max_exposure(&mut InsuranceCalculator) => setMaxExposureCall("setMaxExposure", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_max_exposure(&InsuranceCalculator) => getMaxExposureCall("getMaxExposure", FixedBytes<32>) -> U256;
+    get_remaining_capacity(&InsuranceCalculator) => getRemainingCapacityCall("getRemainingCapacity", FixedBytes<32>) -> U256;
+    set_target_reserve_ratio(&mut InsuranceCalculator) => setTargetReserveRatioCall("setTargetReserveRatio", U256) -> Result<(), Error>;
+    target_reserve_ratio(&InsuranceCalculator) => targetReserveRatioCall("targetReserveRatio") -> U256;
+    set_skim_incentive_bps(&mut InsuranceCalculator) => setSkimIncentiveBpsCall("setSkimIncentiveBps", U256) -> Result<(), Error>;
+    skim_incentive_bps(&InsuranceCalculator) => skimIncentiveBpsCall("skimIncentiveBps") -> U256;
+    set_expiry_incentive_bps(&mut InsuranceCalculator) => setExpiryIncentiveBpsCall("setExpiryIncentiveBps", U256) -> Result<(), Error>;
+    expiry_incentive_bps(&InsuranceCalculator) => expiryIncentiveBpsCall("expiryIncentiveBps") -> U256;
+    skim_excess(&mut InsuranceCalculator) => skimExcessCall("skimExcess", FixedBytes<32>) -> Result<U256, Error>;
+    estimate_hedging_cost(&InsuranceCalculator) => estimateHedgingCostCall("estimateHedgingCost", U256, U256, U256) -> Result<U256, Error>;
+    compute_range_il(&InsuranceCalculator) => computeRangeIlCall("computeRangeIl", U256, U256, U256, U256) -> Result<U256, Error>;
+    set_hedging_fee_mode(&mut InsuranceCalculator) => setHedgingFeeModeCall("setHedgingFeeMode", FixedBytes<32>, bool, U256) -> Result<(), Error>;
+    get_hedging_fee_mode(&InsuranceCalculator) => getHedgingFeeModeCall("getHedgingFeeMode", FixedBytes<32>) -> (bool, U256);
+    set_updater(&mut InsuranceCalculator) => setUpdaterCall("setUpdater", Address, bool) -> Result<(), Error>;
+    is_updater(&InsuranceCalculator) => isUpdaterCall("isUpdater", Address) -> bool;
+    update_historical_il(&mut InsuranceCalculator) => updateHistoricalIlCall("updateHistoricalIl", FixedBytes<32>, U256, U256) -> Result<(), Error>;
+    try_update_historical_il(&mut InsuranceCalculator) => tryUpdateHistoricalIlCall("tryUpdateHistoricalIl", FixedBytes<32>, U256, U256) -> Result<bool, Error>;
+    set_updater_bond_params(&mut InsuranceCalculator) => setUpdaterBondParamsCall("setUpdaterBondParams", U256, U256) -> Result<(), Error>;
+    get_updater_bond_params(&InsuranceCalculator) => getUpdaterBondParamsCall("getUpdaterBondParams") -> (U256, U256);
+    updater_bond_cooldown(&InsuranceCalculator) => updaterBondCooldownCall("updaterBondCooldown") -> U256;
+    post_updater_bond(&mut InsuranceCalculator) => postUpdaterBondCall("postUpdaterBond") -> Result<U256, Error>;
+    withdraw_updater_bond(&mut InsuranceCalculator) => withdrawUpdaterBondCall("withdrawUpdaterBond") -> Result<U256, Error>;
+    slash_updater(&mut InsuranceCalculator) => slashUpdaterCall("slashUpdater", Address, U256, Address) -> Result<(), Error>;
+    get_updater_bond(&InsuranceCalculator) => getUpdaterBondCall("getUpdaterBond", Address) -> U256;
+    get_updater_stats(&InsuranceCalculator) => getUpdaterStatsCall("getUpdaterStats", Address) -> (U256, U256, U256, U256);
+    force_update_historical_il(&mut InsuranceCalculator) => forceUpdateHistoricalIlCall("forceUpdateHistoricalIl", FixedBytes<32>, U256, U256) -> Result<(), Error>;
+    set_il_dispute_window(&mut InsuranceCalculator) => setIlDisputeWindowCall("setIlDisputeWindow", U256) -> Result<(), Error>;
+    il_dispute_window(&InsuranceCalculator) => ilDisputeWindowCall("ilDisputeWindow") -> U256;
+    get_pending_il(&InsuranceCalculator) => getPendingIlCall("getPendingIl", FixedBytes<32>) -> (U256, U256, Address);
+    reject_pending_il(&mut InsuranceCalculator) => rejectPendingIlCall("rejectPendingIl", FixedBytes<32>) -> Result<(), Error>;
+    promote_pending_il(&mut InsuranceCalculator) => promotePendingIlCall("promotePendingIl", FixedBytes<32>) -> bool;
+    set_il_source_params(&mut InsuranceCalculator) => setIlSourceParamsCall("setIlSourceParams", U256, U256, U256) -> Result<(), Error>;
+    get_il_source_params(&InsuranceCalculator) => getIlSourceParamsCall("getIlSourceParams") -> (U256, U256, U256);
+    add_il_source(&mut InsuranceCalculator) => addIlSourceCall("addIlSource", FixedBytes<32>, Address) -> Result<(), Error>;
+    remove_il_source(&mut InsuranceCalculator) => removeIlSourceCall("removeIlSource", FixedBytes<32>, Address) -> Result<(), Error>;
+    get_il_sources(&InsuranceCalculator) => getIlSourcesCall("getIlSources", FixedBytes<32>) -> Vec<Address>;
+    submit_il_source(&mut InsuranceCalculator) => submitIlSourceCall("submitIlSource", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_il_source(&InsuranceCalculator) => getIlSourceCall("getIlSource", FixedBytes<32>, Address) -> (U256, U256);
+    get_aggregated_il(&InsuranceCalculator) => getAggregatedIlCall("getAggregatedIl", FixedBytes<32>) -> (U256, U256);
+    submit_signed_il_update(&mut InsuranceCalculator) => submitSignedIlUpdateCall("submitSignedIlUpdate", FixedBytes<32>, U256, U256, Bytes) -> Result<(), Error>;
+    il_update_digest(&InsuranceCalculator) => ilUpdateDigestCall("ilUpdateDigest", FixedBytes<32>, U256, U256) -> FixedBytes<32>;
+    get_update_sequence(&InsuranceCalculator) => getUpdateSequenceCall("getUpdateSequence", FixedBytes<32>) -> U256;
+    set_max_il_deviation_bps(&mut InsuranceCalculator) => setMaxIlDeviationBpsCall("setMaxIlDeviationBps", U256) -> Result<(), Error>;
+    max_il_deviation_bps(&InsuranceCalculator) => maxIlDeviationBpsCall("maxIlDeviationBps") -> U256;
+    set_max_effective_il(&mut InsuranceCalculator) => setMaxEffectiveIlCall("setMaxEffectiveIl", U256) -> Result<(), Error>;
+    max_effective_il(&InsuranceCalculator) => maxEffectiveIlCall("maxEffectiveIl") -> U256;
+    is_il_clamped(&InsuranceCalculator) => isIlClampedCall("isIlClamped", FixedBytes<32>) -> bool;
+    set_min_il_update_interval(&mut InsuranceCalculator) => setMinIlUpdateIntervalCall("setMinIlUpdateInterval", U256) -> Result<(), Error>;
+    min_il_update_interval(&InsuranceCalculator) => minIlUpdateIntervalCall("minIlUpdateInterval") -> U256;
+    get_last_il_update(&InsuranceCalculator) => getLastIlUpdateCall("getLastIlUpdate", FixedBytes<32>) -> U256;
+    get_il_history_length(&InsuranceCalculator) => getIlHistoryLengthCall("getIlHistoryLength", FixedBytes<32>) -> U256;
+    get_il_history(&InsuranceCalculator) => getIlHistoryCall("getIlHistory", FixedBytes<32>, U256, U256) -> (Vec<U256>, Vec<U256>, Vec<Address>);
+    compute_state_commitment(&InsuranceCalculator) => computeStateCommitmentCall("computeStateCommitment", Vec<FixedBytes<32>>) -> FixedBytes<32>;
+    set_stale_threshold(&mut InsuranceCalculator) => setStaleThresholdCall("setStaleThreshold", U256) -> Result<(), Error>;
+    stale_threshold(&InsuranceCalculator) => staleThresholdCall("staleThreshold") -> U256;
+    set_unrated_default_il(&mut InsuranceCalculator) => setUnratedDefaultIlCall("setUnratedDefaultIl", U256) -> Result<(), Error>;
+    unrated_default_il(&InsuranceCalculator) => unratedDefaultIlCall("unratedDefaultIl") -> U256;
+    mark_pool_unrated(&mut InsuranceCalculator) => markPoolUnratedCall("markPoolUnrated", FixedBytes<32>) -> Result<(), Error>;
+    set_max_data_age(&mut InsuranceCalculator) => setMaxDataAgeCall("setMaxDataAge", FixedBytes<32>, U256) -> Result<(), Error>;
+    set_default_max_data_age(&mut InsuranceCalculator) => setDefaultMaxDataAgeCall("setDefaultMaxDataAge", U256) -> Result<(), Error>;
+    default_max_data_age(&InsuranceCalculator) => defaultMaxDataAgeCall("defaultMaxDataAge") -> U256;
+    get_max_data_age(&InsuranceCalculator) => getMaxDataAgeCall("getMaxDataAge", FixedBytes<32>) -> U256;
+    is_pool_unrated(&InsuranceCalculator) => isPoolUnratedCall("isPoolUnrated", FixedBytes<32>) -> bool;
+    set_quote_validity(&mut InsuranceCalculator) => setQuoteValidityCall("setQuoteValidity", U256) -> Result<(), Error>;
+    quote_validity(&InsuranceCalculator) => quoteValidityCall("quoteValidity") -> U256;
+    issue_quote(&mut InsuranceCalculator) => issueQuoteCall("issueQuote", FixedBytes<32>, U256, U256, U256) -> Result<(FixedBytes<32>, U256), Error>;
+    settle_quote(&mut InsuranceCalculator) => settleQuoteCall("settleQuote", FixedBytes<32>) -> Result<U256, Error>;
+    get_quote(&InsuranceCalculator) => getQuoteCall("getQuote", FixedBytes<32>) -> (FixedBytes<32>, Address, U256, U256, bool);
+    set_reveal_window(&mut InsuranceCalculator) => setRevealWindowCall("setRevealWindow", U256) -> Result<(), Error>;
+    reveal_window(&InsuranceCalculator) => revealWindowCall("revealWindow") -> U256;
+    commit_quote(&mut InsuranceCalculator) => commitQuoteCall("commitQuote", FixedBytes<32>) -> Result<(), Error>;
+    reveal_and_quote(&mut InsuranceCalculator) => revealAndQuoteCall("revealAndQuote", FixedBytes<32>, U256, FixedBytes<32>, U256, U256) -> Result<U256, Error>;
+    set_loyalty_tiers(&mut InsuranceCalculator) => setLoyaltyTiersCall("setLoyaltyTiers", Vec<U256>, Vec<U256>) -> Result<(), Error>;
+    get_loyalty_tiers(&InsuranceCalculator) => getLoyaltyTiersCall("getLoyaltyTiers") -> (Vec<U256>, Vec<U256>);
+    get_loyalty_status(&InsuranceCalculator) => getLoyaltyStatusCall("getLoyaltyStatus", Address) -> (U256, U256);
+    set_flash_fee_exempt(&mut InsuranceCalculator) => setFlashFeeExemptCall("setFlashFeeExempt", Address, bool) -> Result<(), Error>;
+    is_flash_fee_exempt(&InsuranceCalculator) => isFlashFeeExemptCall("isFlashFeeExempt", Address) -> bool;
+    set_fee_holiday(&mut InsuranceCalculator) => setFeeHolidayCall("setFeeHoliday", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_fee_holiday_until(&InsuranceCalculator) => getFeeHolidayUntilCall("getFeeHolidayUntil", FixedBytes<32>) -> U256;
+    is_fee_holiday(&InsuranceCalculator) => isFeeHolidayCall("isFeeHoliday", FixedBytes<32>) -> bool;
+    set_dust_threshold(&mut InsuranceCalculator) => setDustThresholdCall("setDustThreshold", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_dust_threshold(&InsuranceCalculator) => getDustThresholdCall("getDustThreshold", FixedBytes<32>) -> U256;
+    set_fee_curve(&mut InsuranceCalculator) => setFeeCurveCall("setFeeCurve", FixedBytes<32>, Vec<U256>, Vec<U256>) -> Result<(), Error>;
+    clear_fee_curve(&mut InsuranceCalculator) => clearFeeCurveCall("clearFeeCurve", FixedBytes<32>) -> Result<(), Error>;
+    get_fee_curve(&InsuranceCalculator) => getFeeCurveCall("getFeeCurve", FixedBytes<32>) -> (Vec<U256>, Vec<U256>);
+    set_grace_period(&mut InsuranceCalculator) => setGracePeriodCall("setGracePeriod", U256, U256) -> Result<(), Error>;
+    get_grace_period(&InsuranceCalculator) => getGracePeriodCall("getGracePeriod") -> (U256, U256);
+    get_grace_multiplier(&InsuranceCalculator) => getGraceMultiplierCall("getGraceMultiplier", FixedBytes<32>) -> Result<U256, Error>;
+    set_uncertainty_premium(&mut InsuranceCalculator) => setUncertaintyPremiumCall("setUncertaintyPremium", U256) -> Result<(), Error>;
+    uncertainty_premium(&InsuranceCalculator) => uncertaintyPremiumCall("uncertaintyPremium") -> U256;
+    get_il_observations(&InsuranceCalculator) => getIlObservationsCall("getIlObservations", FixedBytes<32>) -> U256;
+    get_uncertainty_multiplier(&InsuranceCalculator) => getUncertaintyMultiplierCall("getUncertaintyMultiplier", FixedBytes<32>) -> U256;
+    reset_pool_observations(&mut InsuranceCalculator) => resetPoolObservationsCall("resetPoolObservations", FixedBytes<32>) -> Result<(), Error>;
+    set_surge_params(&mut InsuranceCalculator) => setSurgeParamsCall("setSurgeParams", FixedBytes<32>, U256, U256) -> Result<(), Error>;
+    get_surge_params(&InsuranceCalculator) => getSurgeParamsCall("getSurgeParams", FixedBytes<32>) -> (U256, U256);
+    record_swap_volume(&mut InsuranceCalculator) => recordSwapVolumeCall("recordSwapVolume", FixedBytes<32>, Address, U256, U256, U256) -> Result<U256, Error>;
+    set_fee_smoothing(&mut InsuranceCalculator) => setFeeSmoothingCall("setFeeSmoothing", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_fee_smoothing(&InsuranceCalculator) => getFeeSmoothingCall("getFeeSmoothing", FixedBytes<32>) -> (U256, U256, U256);
+    calculate_smoothed_insurance_fee(&InsuranceCalculator) => calculateSmoothedInsuranceFeeCall("calculateSmoothedInsuranceFee", FixedBytes<32>, U256, U256, U256) -> Result<(U256, U256), Error>;
+    get_recent_fees(&InsuranceCalculator) => getRecentFeesCall("getRecentFees", FixedBytes<32>, U256) -> (Vec<U256>, Vec<U256>, Vec<U256>);
+    record_swap_volume_idempotent(&mut InsuranceCalculator) => recordSwapVolumeIdempotentCall("recordSwapVolumeIdempotent", FixedBytes<32>, FixedBytes<32>, Address, U256, U256, U256) -> Result<U256, Error>;
+    is_idempotency_key_used(&InsuranceCalculator) => isIdempotencyKeyUsedCall("isIdempotencyKeyUsed", Address, FixedBytes<32>) -> bool;
+    deposit_coverage_budget(&mut InsuranceCalculator) => depositCoverageBudgetCall("depositCoverageBudget", FixedBytes<32>) -> Result<U256, Error>;
+    withdraw_coverage_budget(&mut InsuranceCalculator) => withdrawCoverageBudgetCall("withdrawCoverageBudget", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_coverage_budget(&InsuranceCalculator) => getCoverageBudgetCall("getCoverageBudget", FixedBytes<32>, Address) -> U256;
+    set_pool_hook(&mut InsuranceCalculator) => setPoolHookCall("setPoolHook", FixedBytes<32>, Address) -> Result<(), Error>;
+    get_pool_hook(&InsuranceCalculator) => getPoolHookCall("getPoolHook", FixedBytes<32>) -> Address;
+    on_after_swap(&mut InsuranceCalculator) => onAfterSwapCall("onAfterSwap", FixedBytes<32>, U256, U256) -> Result<U256, Error>;
+    set_circuit_breaker_bps(&mut InsuranceCalculator) => setCircuitBreakerBpsCall("setCircuitBreakerBps", FixedBytes<32>, U256) -> Result<(), Error>;
+    set_circuit_breaker_bypass(&mut InsuranceCalculator) => setCircuitBreakerBypassCall("setCircuitBreakerBypass", FixedBytes<32>, bool) -> Result<(), Error>;
+    get_circuit_breaker(&InsuranceCalculator) => getCircuitBreakerCall("getCircuitBreaker", FixedBytes<32>) -> (U256, bool);
+    get_reference_price(&InsuranceCalculator) => getReferencePriceCall("getReferencePrice", FixedBytes<32>) -> U256;
+    get_swap_stats(&InsuranceCalculator) => getSwapStatsCall("getSwapStats", FixedBytes<32>) -> (U256, U256, U256);
+    calculate_lp_share(&InsuranceCalculator) => calculateLpShareCall("calculateLpShare", FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    record_lp_fees(&mut InsuranceCalculator) => recordLpFeesCall("recordLpFees", FixedBytes<32>, U256, U256) -> Result<(), Error>;
+    get_fee_growth_global(&InsuranceCalculator) => getFeeGrowthGlobalCall("getFeeGrowthGlobal", FixedBytes<32>) -> U256;
+    claimable_fees(&InsuranceCalculator) => claimableFeesCall("claimableFees", FixedBytes<32>, U256, U256) -> Result<U256, Error>;
+    get_fee_growth_liquidity(&InsuranceCalculator) => getFeeGrowthLiquidityCall("getFeeGrowthLiquidity", FixedBytes<32>) -> U256;
+    calculate_insurance_fee(&InsuranceCalculator) => calculateInsuranceFeeCall("calculateInsuranceFee", FixedBytes<32>, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    compute_pool_id(&InsuranceCalculator) => computePoolIdCall("computePoolId", Address, Address, U256) -> Result<FixedBytes<32>, Error>;
+    calculate_insurance_fee_for_tokens(&InsuranceCalculator) => calculateInsuranceFeeForTokensCall("calculateInsuranceFeeForTokens", Address, Address, U256, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_insurance_fee_exact_out(&InsuranceCalculator) => calculateInsuranceFeeExactOutCall("calculateInsuranceFeeExactOut", FixedBytes<32>, U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_exit_fee(&InsuranceCalculator) => calculateExitFeeCall("calculateExitFee", FixedBytes<32>, U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_insurance_fee_saturating(&InsuranceCalculator) => calculateInsuranceFeeSaturatingCall("calculateInsuranceFeeSaturating", FixedBytes<32>, U256, U256, U256) -> (U256, bool);
+    calculate_insurance_fee_now(&InsuranceCalculator) => calculateInsuranceFeeNowCall("calculateInsuranceFeeNow", FixedBytes<32>, U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_insurance_fee_denominated(&InsuranceCalculator) => calculateInsuranceFeeDenominatedCall("calculateInsuranceFeeDenominated", FixedBytes<32>, U256, U256, U256, U256, bool) -> Result<U256, Error>;
+    simulate_insurance_fee(&InsuranceCalculator) => simulateInsuranceFeeCall("simulateInsuranceFee", FixedBytes<32>, U256, U256, U256, U256, bool) -> Result<U256, Error>;
+    calculate_insurance_fee_with_slippage(&InsuranceCalculator) => calculateInsuranceFeeWithSlippageCall("calculateInsuranceFeeWithSlippage", FixedBytes<32>, U256, U256, bool, U256, U256) -> Result<U256, Error>;
+    set_stress_scenario(&mut InsuranceCalculator) => setStressScenarioCall("setStressScenario", FixedBytes<32>, U256, U256, U256) -> Result<(), Error>;
+    get_stress_scenario(&InsuranceCalculator) => getStressScenarioCall("getStressScenario", FixedBytes<32>) -> (U256, U256, U256);
+    calculate_insurance_fee_stressed(&InsuranceCalculator) => calculateInsuranceFeeStressedCall("calculateInsuranceFeeStressed", FixedBytes<32>, FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    quote_with_recipient(&InsuranceCalculator) => quoteWithRecipientCall("quoteWithRecipient", FixedBytes<32>, U256, U256, U256) -> Result<(U256, Address), Error>;
+    set_fee_recipient(&mut InsuranceCalculator) => setFeeRecipientCall("setFeeRecipient", FixedBytes<32>, Address) -> Result<(), Error>;
+    set_default_fee_recipient(&mut InsuranceCalculator) => setDefaultFeeRecipientCall("setDefaultFeeRecipient", Address) -> Result<(), Error>;
+    default_fee_recipient(&InsuranceCalculator) => defaultFeeRecipientCall("defaultFeeRecipient") -> Address;
+    get_fee_recipient(&InsuranceCalculator) => getFeeRecipientCall("getFeeRecipient", FixedBytes<32>) -> Result<Address, Error>;
+    convert_fee(&InsuranceCalculator) => convertFeeCall("convertFee", U256, U256, u8) -> Result<U256, Error>;
+    calculate_insurance_fee_converted(&InsuranceCalculator) => calculateInsuranceFeeConvertedCall("calculateInsuranceFeeConverted", FixedBytes<32>, U256, U256, U256, U256, u8) -> Result<U256, Error>;
+    calculate_insurance_fee_usd(&InsuranceCalculator) => calculateInsuranceFeeUsdCall("calculateInsuranceFeeUsd", FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    set_price_feed(&mut InsuranceCalculator) => setPriceFeedCall("setPriceFeed", FixedBytes<32>, Address) -> Result<(), Error>;
+    get_price_feed(&InsuranceCalculator) => getPriceFeedCall("getPriceFeed", FixedBytes<32>) -> Address;
+    set_oracle_max_age(&mut InsuranceCalculator) => setOracleMaxAgeCall("setOracleMaxAge", U256) -> Result<(), Error>;
+    oracle_max_age(&InsuranceCalculator) => oracleMaxAgeCall("oracleMaxAge") -> U256;
+    calculate_insurance_fee_from_provider(&InsuranceCalculator) => calculateInsuranceFeeFromProviderCall("calculateInsuranceFeeFromProvider", FixedBytes<32>, U256) -> Result<U256, Error>;
+    calculate_pool_flash_loan_fee_from_provider(&InsuranceCalculator) => calculatePoolFlashLoanFeeFromProviderCall("calculatePoolFlashLoanFeeFromProvider", FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    set_data_provider(&mut InsuranceCalculator) => setDataProviderCall("setDataProvider", FixedBytes<32>, Address) -> Result<(), Error>;
+    get_data_provider(&InsuranceCalculator) => getDataProviderCall("getDataProvider", FixedBytes<32>) -> Address;
+    calculate_product_insurance_fee(&InsuranceCalculator) => calculateProductInsuranceFeeCall("calculateProductInsuranceFee", FixedBytes<32>, u8, U256, U256, U256) -> Result<U256, Error>;
+    calculate_insurance_fee_units(&InsuranceCalculator) => calculateInsuranceFeeUnitsCall("calculateInsuranceFeeUnits", FixedBytes<32>, U256, U256, U256, U256, u8, u8) -> Result<U256, Error>;
+    max_amount_for_fee(&InsuranceCalculator) => maxAmountForFeeCall("maxAmountForFee", FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    get_fee_sensitivity(&InsuranceCalculator) => getFeeSensitivityCall("getFeeSensitivity", FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    calculate_portfolio_fee(&InsuranceCalculator) => calculatePortfolioFeeCall("calculatePortfolioFee", Vec<FixedBytes<32>>, Vec<U256>, U256, Vec<U256>, Vec<U256>) -> Result<U256, Error>;
+    calculate_split_insurance_fee(&InsuranceCalculator) => calculateSplitInsuranceFeeCall("calculateSplitInsuranceFee", Vec<FixedBytes<32>>, Vec<U256>, Vec<U256>, Vec<U256>) -> Result<(U256, Vec<U256>), Error>;
+    calculate_flash_loan_fee(&InsuranceCalculator) => calculateFlashLoanFeeCall("calculateFlashLoanFee", U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_pool_flash_loan_fee_for_tokens(&InsuranceCalculator) => calculatePoolFlashLoanFeeForTokensCall("calculatePoolFlashLoanFeeForTokens", Address, Address, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_flash_loan_fee_saturating(&InsuranceCalculator) => calculateFlashLoanFeeSaturatingCall("calculateFlashLoanFeeSaturating", U256, U256, U256, U256) -> (U256, bool);
+    calculate_pool_flash_loan_fee(&InsuranceCalculator) => calculatePoolFlashLoanFeeCall("calculatePoolFlashLoanFee", FixedBytes<32>, U256, U256, U256, U256) -> Result<U256, Error>;
+    flash_loan_repayment_amount(&InsuranceCalculator) => flashLoanRepaymentAmountCall("flashLoanRepaymentAmount", FixedBytes<32>, U256, U256, U256, U256) -> Result<U256, Error>;
+    simulate_pool_flash_loan_fee(&InsuranceCalculator) => simulatePoolFlashLoanFeeCall("simulatePoolFlashLoanFee", FixedBytes<32>, U256, U256, U256, U256, U256) -> Result<U256, Error>;
+    calculate_pool_flash_loan_fee_stressed(&InsuranceCalculator) => calculatePoolFlashLoanFeeStressedCall("calculatePoolFlashLoanFeeStressed", FixedBytes<32>, FixedBytes<32>, U256, U256, U256, U256) -> Result<U256, Error>;
+    get_flash_borrowed_this_block(&InsuranceCalculator) => getFlashBorrowedThisBlockCall("getFlashBorrowedThisBlock", FixedBytes<32>) -> U256;
+    calculate_tracked_flash_loan_fee(&InsuranceCalculator) => calculateTrackedFlashLoanFeeCall("calculateTrackedFlashLoanFee", FixedBytes<32>, U256, U256, U256) -> Result<U256, Error>;
+    calculate_pool_flash_loan_fee_converted(&InsuranceCalculator) => calculatePoolFlashLoanFeeConvertedCall("calculatePoolFlashLoanFeeConverted", FixedBytes<32>, U256, U256, U256, U256, U256, u8) -> Result<U256, Error>;
+    calculate_pool_flash_loan_fee_split(&InsuranceCalculator) => calculatePoolFlashLoanFeeSplitCall("calculatePoolFlashLoanFeeSplit", FixedBytes<32>, U256, U256, U256, U256) -> Result<(U256, U256), Error>;
+    record_flash_loan(&mut InsuranceCalculator) => recordFlashLoanCall("recordFlashLoan", FixedBytes<32>, U256, U256, U256, U256) -> Result<(U256, U256), Error>;
+    record_flash_loan_idempotent(&mut InsuranceCalculator) => recordFlashLoanIdempotentCall("recordFlashLoanIdempotent", FixedBytes<32>, FixedBytes<32>, U256, U256, U256, U256) -> Result<(U256, U256), Error>;
+    set_insurance_share_bps(&mut InsuranceCalculator) => setInsuranceShareBpsCall("setInsuranceShareBps", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_insurance_share_bps(&InsuranceCalculator) => getInsuranceShareBpsCall("getInsuranceShareBps", FixedBytes<32>) -> U256;
+    get_flash_lp_fees(&InsuranceCalculator) => getFlashLpFeesCall("getFlashLpFees", FixedBytes<32>) -> U256;
+    withdraw_flash_lp_fees(&mut InsuranceCalculator) => withdrawFlashLpFeesCall("withdrawFlashLpFees", FixedBytes<32>, Address, U256) -> Result<(), Error>;
+    calculate_time_weighted_flash_loan_fee(&InsuranceCalculator) => calculateTimeWeightedFlashLoanFeeCall("calculateTimeWeightedFlashLoanFee", FixedBytes<32>, U256, U256, U256, U256) -> Result<U256, Error>;
+    checkpoint_utilization(&mut InsuranceCalculator) => checkpointUtilizationCall("checkpointUtilization", FixedBytes<32>, U256) -> Result<(), Error>;
+    get_time_weighted_utilization(&InsuranceCalculator) => getTimeWeightedUtilizationCall("getTimeWeightedUtilization", FixedBytes<32>, U256) -> Result<U256, Error>;
+    set_twu_params(&mut InsuranceCalculator) => setTwuParamsCall("setTwuParams", U256, U256) -> Result<(), Error>;
+    get_twu_params(&InsuranceCalculator) => getTwuParamsCall("getTwuParams") -> (U256, U256);
+    set_max_default_history(&mut InsuranceCalculator) => setMaxDefaultHistoryCall("setMaxDefaultHistory", U256) -> Result<(), Error>;
+    max_default_history(&InsuranceCalculator) => maxDefaultHistoryCall("maxDefaultHistory") -> U256;
+    set_lender(&mut InsuranceCalculator) => setLenderCall("setLender", Address, bool) -> Result<(), Error>;
+    is_lender(&InsuranceCalculator) => isLenderCall("isLender", Address) -> bool;
+    set_default_score_params(&mut InsuranceCalculator) => setDefaultScoreParamsCall("setDefaultScoreParams", U256, U256) -> Result<(), Error>;
+    get_default_score_params(&InsuranceCalculator) => getDefaultScoreParamsCall("getDefaultScoreParams") -> (U256, U256);
+    get_default_score(&InsuranceCalculator) => getDefaultScoreCall("getDefaultScore", FixedBytes<32>) -> U256;
+    record_default(&mut InsuranceCalculator) => recordDefaultCall("recordDefault", FixedBytes<32>, U256) -> Result<U256, Error>;
+    record_repayment(&mut InsuranceCalculator) => recordRepaymentCall("recordRepayment", FixedBytes<32>) -> Result<U256, Error>;
+
+}
+
+// The published id must not move without a new interface version
+const _: () = assert!(bytes_equal(INTERFACE_ID.0, [0x5f, 0xa5, 0x75, 0x1e]));
+
+// Every error and event the contract declares must appear in the interface and encode exactly as
+// the contract's own; a missing one fails to resolve, a drifted one fails the assert
+macro_rules! same_errors {
+    ($($name:ident),* $(,)?) => {
+        $(const _: () = assert!(bytes_equal(
+            <IInsuranceCalculator::$name as SolError>::SELECTOR,
+            <crate::$name as SolError>::SELECTOR,
+        ));)*
+    };
+}
+
+macro_rules! same_events {
+    ($($name:ident),* $(,)?) => {
+        $(const _: () = assert!(bytes_equal(
+            <IInsuranceCalculator::$name as SolEvent>::SIGNATURE_HASH.0,
+            <crate::$name as SolEvent>::SIGNATURE_HASH.0,
+        ));)*
+    };
+}
+
+same_errors!(
+    CalculationError, InvalidInput, Unauthorized, UnauthorizedForPool, AlreadyInitialized,
+    InsufficientBalance, TransferFailed, MinimumReserveBreached, InsufficientPayment,
+    PolicyNotActive, ClaimTooEarly, NoExcessReserves, PoolAlreadyRegistered, PoolNotRegistered,
+    QuoteNotFound, QuoteExpired, QuoteAlreadySettled, CommitmentMismatch, RevealTooEarly,
+    CommitmentExpired, PoolNotStale, ExposureCapExceeded, DeviationTooLarge, StaleSequence,
+    NoFeeRecipient, NoOracle, StaleData, InvalidOracleResponse, NoDataProvider, PoolInactive,
+    StreamNotActive, InsufficientBudget, InsufficientBond, BondLocked, NoPendingIl,
+    FlashLoanCapExceeded, InvariantViolated, DataProviderFailed, PriceCircuitBreaker,
+    NotMoreConservative, FeeSlippage, TooFrequent,
+);
+
+same_events!(
+    ProtocolFeeUpdated, TreasuryUpdated, PremiumCollected, TreasuryWithdrawn, PoolRegistered,
+    RequireRegistrationSet, PoolOperatorSet, FlashFeeMultiplierSet, BaseFeeSet, UpdaterSet,
+    MinIlUpdateIntervalSet, HistoricalIlUpdated, PoolMarkedUnrated, QuoteIssued, QuoteSettled,
+    QuoteCommitted, QuoteRevealed, RevealWindowSet, PremiumPaid, FundDeposited, FundWithdrawn,
+    MinReserveSet, CoveragePurchased, DeductibleAllowed, PolicyClaimed, PolicyExpired,
+    ExpiryIncentivePaid, ExpiryIncentiveSet, PolicyRenewed, PolicyTransferred, PolicyCancelled,
+    RefundWithdrawn, ReservesSkimmed, HedgingFeeModeSet, LoyaltyTiersUpdated, FlashFeeExemptSet,
+    FeeHolidaySet, RebateEpochClosed, MaxRebateBpsSet, DustThresholdSet, SwapRecorded,
+    GracePeriodSet, SurgeParamsSet, LpFeesRecorded, MaxExposureSet, PoolHookSet, SwapPushed,
+    ProductConfigured, LenderSet, DefaultScoreParamsSet, DefaultRecorded, RepaymentRecorded,
+    UtilizationCheckpointed, TwuParamsSet, InsuranceShareSet, FlashLoanRecorded,
+    FlashLpFeesWithdrawn, FeeRecipientSet, DefaultFeeRecipientSet, PriceFeedSet, OracleMaxAgeSet,
+    MaxEffectiveIlSet, MaxDefaultHistorySet, CancellationPenaltySet, TargetReserveRatioSet,
+    SkimIncentiveSet, MaxIlDeviationSet, StaleThresholdSet, UnratedDefaultIlSet, QuoteValiditySet,
+    ParametersUpdated, EmergencyAdminSet, IlWeightSet, RiskPresetSet, RiskPresetApplied,
+    CircuitBreakerSet, CircuitBreakerBypassSet, DefaultHistoryClamped, DataProviderSet,
+    PoolTypeSet, FeeCurveSet, FeeCurveCleared, UncertaintyPremiumSet, PoolObservationsReset,
+    MaxDataAgeSet, DefaultMaxDataAgeSet, PoolActiveSet, OpenInterestWeightSet,
+    SystemicRiskParamsSet, AccrualEpochLengthSet, PremiumEpochsClosed, StreamOpened,
+    StreamCheckpointed, StreamClosed, CoverageBudgetDeposited, CoverageBudgetWithdrawn,
+    AutoRenewSet, RenewalFunded, RenewalBalanceWithdrawn, PolicyLapsed, IlUpdateGuarded,
+    UpdaterBondParamsSet, UpdaterBondPosted, UpdaterBondWithdrawn, UpdaterSlashed,
+    IlDisputeWindowSet, IlUpdatePending, PendingIlRejected, IlSourceParamsSet, IlSourceAdded,
+    IlSourceRemoved, IlSourceSubmitted, MaxFlashAmountSet, IdempotentReplay, AprReferenceSizeSet,
+    StressScenarioSet, FeeSmoothingSet, TokenDecimalsSet, FeeFormulaSet, FeeFormulaBoundsSet,
+    FeeFormulaFallback,
+);
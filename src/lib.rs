@@ -1,157 +1,7512 @@
-#![cfg_attr(all(not(feature = "std"), not(feature = "export-abi")), no_main)]
+#![cfg_attr(all(not(test), not(feature = "std"), not(feature = "export-abi")), no_main)]
 extern crate alloc;
 
+pub mod interface;
+mod math;
+
 use stylus_sdk::{
-    alloy_primitives::{U256, FixedBytes}, 
+    abi::Bytes,
+    alloy_primitives::{address, Address, FixedBytes, I256, U256, U8},
     prelude::*,
-    alloy_sol_types::sol,
+    alloy_sol_types::{sol, SolValue},
+    block,
+    call::{transfer_eth, Call, RawCall},
+    contract,
+    crypto::keccak,
+    evm, msg,
     stylus_proc::{public, sol_storage, SolidityError},
 };
 
-sol! {
-    #[derive(Debug)]
-    error CalculationError();
-    
-    #[derive(Debug)] 
-    error InvalidInput();
-}
+/// Fixed-point unit (1e18) used for all WAD-denominated values
+const WAD: u64 = 1_000_000_000_000_000_000;
+
+/// Higher-precision unit (1e27) the insurance fee pipeline carries its intermediates in
+const RAY: u128 = 1_000_000_000_000_000_000_000_000_000;
+
+/// Denominator for all basis-point parameters
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Bisection steps needed to search the full U256 range
+const MAX_AMOUNT_SEARCH_STEPS: usize = 256;
+
+/// Finite-difference step used for fee sensitivity, as a divisor of the amount (0.1%)
+const SENSITIVITY_STEP_DIVISOR: u64 = 1_000;
+
+/// Most decimals a token-unit input may declare
+const MAX_DECIMALS: u8 = 36;
+
+/// Band a settlement conversion rate must fall in, 1e-12 to 1e12 (WAD)
+const MIN_CONVERSION_RATE: u128 = 1_000_000;
+const MAX_CONVERSION_RATE: u128 = 1_000_000_000_000_000_000_000_000_000_000;
+
+/// Insurance base fee used when a pool has no override
+const DEFAULT_INSURANCE_BASE_FEE: u64 = 100_000_000_000_000_000;
+
+/// Flash loan base fee before multipliers, fixed at 0.05% (WAD)
+const FLASH_BASE_FEE: u64 = 500_000_000_000_000;
+
+/// Upper bound on a pool's flash fee multiplier (10x)
+const MAX_FLASH_FEE_MULTIPLIER: u64 = 10_000_000_000_000_000_000;
+
+/// ERC-165 id of `supportsInterface(bytes4)` itself
+const ERC165_INTERFACE_ID: FixedBytes<4> = FixedBytes::new([0x01, 0xff, 0xc9, 0xa7]);
+
+/// Precompile that recovers the signer of an ECDSA signature
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+/// Largest relative move a single IL update may make when no band is configured (50%)
+const DEFAULT_MAX_IL_DEVIATION_BPS: u64 = 5_000;
+
+/// Prices kept per pool by the swap hook; IL is measured across this window
+const OBSERVATION_WINDOW: u64 = 16;
+
+/// Weight of each new hook measurement in the smoothed IL (10%)
+const IL_SMOOTHING_BPS: u64 = 1_000;
+
+/// Largest drift allowed between a caller-supplied timestamp and the block time, in seconds
+const TIMESTAMP_TOLERANCE: u64 = 15;
+
+/// Oldest price feed answer accepted when no max age is configured, in seconds (1 hour)
+const DEFAULT_ORACLE_MAX_AGE: u64 = 60 * 60;
+
+/// Quote lifetime used when no validity window is configured (three 12s blocks)
+const DEFAULT_QUOTE_VALIDITY: u64 = 36;
+
+/// Blocks a commitment stays revealable when no window is configured
+const DEFAULT_REVEAL_WINDOW: u64 = 256;
+
+/// Coverage duration a policy's premium is quoted against (30 days)
+const POLICY_TERM: u64 = 30 * 24 * 60 * 60;
+
+/// Longest coverage duration a policy can be bought for (365 days)
+const MAX_POLICY_DURATION: u64 = 365 * 24 * 60 * 60;
+
+/// Longest grace window a newly registered pool can be discounted for (365 days)
+const MAX_GRACE_DURATION: u64 = 365 * 24 * 60 * 60;
+
+/// Shortest coverage duration a policy can be bought for (7 days)
+const MIN_POLICY_DURATION: u64 = 7 * 24 * 60 * 60;
+
+/// Seconds a policy must have run before it can be claimed (1 day)
+const CLAIM_WAITING_PERIOD: u64 = 24 * 60 * 60;
+
+/// Time past expiry `process_renewal` has before anyone can lapse an auto-renewing policy
+const RENEWAL_GRACE_PERIOD: u64 = 24 * 60 * 60;
+
+/// Length of the epochs policy premiums are earned over by default (30 days), and the shortest
+/// allowed, which bounds how many epochs one policy spreads its premium across (7 days)
+const DEFAULT_ACCRUAL_EPOCH_LENGTH: u64 = 30 * 24 * 60 * 60;
+const MIN_ACCRUAL_EPOCH_LENGTH: u64 = 7 * 24 * 60 * 60;
+
+/// Most accrual epochs one close recognizes; a longer backlog takes further calls
+const MAX_EPOCHS_PER_CLOSE: u64 = 64;
+
+/// Seconds an updater's bond stays locked after its last IL write by default (7 days)
+const DEFAULT_BOND_COOLDOWN: u64 = 7 * 24 * 60 * 60;
+
+/// Most IL sources a pool can ever register, keeping the median sort cheap
+const MAX_IL_SOURCES: u64 = 9;
+
+/// Source slots per pool and fresh sources needed for a median when none are configured
+const DEFAULT_MAX_IL_SOURCES: u64 = 3;
+const DEFAULT_IL_SOURCE_QUORUM: u64 = 2;
+
+/// How long an IL source's answer stays fresh when no heartbeat is configured, in seconds (1 day)
+const DEFAULT_IL_SOURCE_HEARTBEAT: u64 = 24 * 60 * 60;
+
+/// Premium discount slope per unit of deductible: premium / (1 + 4 * deductible)
+const DEDUCTIBLE_DISCOUNT_SLOPE: u64 = 4;
+
+/// Upper bound on the share of each loss a policy holder can retain (50%)
+const MAX_COINSURANCE_BPS: u64 = 5_000;
+
+/// Product id that prices impermanent-loss cover with the pool's own base fee
+const IL_PRODUCT: u8 = 0;
+
+/// Pool types: volatile pairs keep the full IL amplification, stable pairs trade it for a
+/// depeg term; unset pools are volatile
+const POOL_VOLATILE: u8 = 0;
+const POOL_STABLE: u8 = 1;
+
+/// Share of the IL weight stable pools are priced with (10%)
+const STABLE_IL_WEIGHT_BPS: u64 = 1_000;
+
+/// Weight (WAD) on a stable pool's reference price deviation from 1.0
+const DEPEG_WEIGHT: u64 = 20_000_000_000_000_000_000;
+
+/// IL and size multiplier weights used by the IL product (WAD)
+const DEFAULT_IL_WEIGHT: u64 = 3_000_000_000_000_000_000;
+const DEFAULT_SIZE_WEIGHT: u64 = 1_000_000_000_000_000_000;
+
+/// Upper bound on a product's risk multiplier weights (10x)
+const MAX_RISK_WEIGHT: u64 = 10_000_000_000_000_000_000;
+
+/// Number of risk-tier presets the owner can define
+const MAX_RISK_PRESETS: u8 = 8;
+
+/// Policy lifecycle states
+const POLICY_ACTIVE: u8 = 1;
+const POLICY_CLAIMED: u8 = 2;
+const POLICY_EXPIRED: u8 = 3;
+const POLICY_CANCELLED: u8 = 4;
+
+/// Final share of a policy's duration in which cancelling refunds nothing (10%)
+const CANCELLATION_CUTOFF_BPS: u64 = 1_000;
+
+/// Largest share of a premium rebated when none is configured (50%)
+const DEFAULT_MAX_REBATE_BPS: u64 = 5_000;
+
+/// Upper bound on the keeper incentive paid from skimmed reserves (1%)
+const MAX_SKIM_INCENTIVE_BPS: u64 = 100;
+
+/// Upper bound on the keeper incentive paid for expiring a lapsed policy (5% of its premium)
+const MAX_EXPIRY_INCENTIVE_BPS: u64 = 500;
+
+/// Seconds in the 365-day year used to annualize volatility and premium rates
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// Position size a pool's premium APR is quoted for when none is configured (1 token in WAD)
+const DEFAULT_APR_REFERENCE_SIZE: u64 = 1_000_000_000_000_000_000;
+
+/// At-the-money straddle approximation factor, 0.4 ≈ sqrt(2 / pi) / 2 (WAD)
+const STRADDLE_FACTOR: u64 = 400_000_000_000_000_000;
+
+/// Highest annualized volatility (WAD, 500%) a pool's hedging fee mode accepts
+const MAX_HEDGING_VOLATILITY: u64 = 5_000_000_000_000_000_000;
+
+/// Most loyalty tiers the owner can configure
+const MAX_LOYALTY_TIERS: usize = 8;
+
+/// Most breakpoints a pool's custom size curve can hold
+const MAX_CURVE_POINTS: usize = 8;
+
+/// IL writes retained per pool in its history log; older entries roll off
+const IL_HISTORY_CAPACITY: u64 = 128;
+
+/// Recorded fees retained per pool for `get_recent_fees`; older entries roll off
+const RECENT_FEES_CAPACITY: u64 = 32;
+
+/// Fee added per unit of implied price impact (5x), and the impact beyond which it stops
+/// growing (20%), so a slippage-aware quote is at most double the standard one
+const SLIPPAGE_IMPACT_WEIGHT: u64 = 5_000_000_000_000_000_000;
+const MAX_SLIPPAGE_IMPACT: u64 = 200_000_000_000_000_000;
+
+/// Most legs a split-route quote may price in one call
+const MAX_ROUTE_LEGS: usize = 16;
+
+/// Upper bound on a pool's surge multiplier at full utilization (10x)
+const MAX_SURGE_MULTIPLIER: u64 = 10_000_000_000_000_000_000;
+
+/// Default band an external formula's fee is held to, relative to the internal fee (50%-100%)
+const DEFAULT_FORMULA_MIN_BPS: u64 = 5_000;
+const DEFAULT_FORMULA_MAX_BPS: u64 = 10_000;
+
+/// Upper bound on an external formula's fee relative to the internal fee (200%)
+const MAX_FORMULA_BPS: u64 = 20_000;
+
+/// Upper bound on the protocol's share of collected premiums (50%)
+const MAX_PROTOCOL_FEE_BPS: u64 = 5_000;
+
+/// Ceiling on a pool's tracked default score when none is configured (a 10x multiplier)
+const DEFAULT_MAX_DEFAULT_SCORE: u64 = 9_000_000_000_000_000_000;
+
+/// Ceiling on the default history a flash fee is priced with when none is configured (a 10x
+/// multiplier)
+const DEFAULT_MAX_DEFAULT_HISTORY: u64 = 9_000_000_000_000_000_000;
+
+/// Share of a pool's default score each clean repayment removes when none is configured (10%)
+const DEFAULT_REPAYMENT_DECAY_BPS: u64 = 1_000;
+
+/// Utilization checkpoints kept per pool; time-weighted averages reach back this far at most
+const UTILIZATION_CHECKPOINTS: u64 = 16;
+
+/// Averaging window for time-weighted utilization when none is configured (1 hour)
+const DEFAULT_TWU_WINDOW: u64 = 60 * 60;
+
+/// Weight of time-weighted utilization against spot when none is configured (100%)
+const DEFAULT_TWU_WEIGHT_BPS: u64 = 10_000;
+
+/// `InvariantViolated` codes: a flash or insurance fee above the amount it prices, a multiplier
+/// outside its configured bounds, and a fee split or discount that doesn't add back up to the fee
+#[cfg(feature = "invariant-checks")]
+const INVARIANT_FEE_EXCEEDS_AMOUNT: u8 = 1;
+#[cfg(feature = "invariant-checks")]
+const INVARIANT_MULTIPLIER_BOUNDS: u8 = 2;
+#[cfg(feature = "invariant-checks")]
+const INVARIANT_SPLIT_MISMATCH: u8 = 3;
+
+/// Returns `InvariantViolated` with `$code` from the enclosing function unless `$cond` holds;
+/// expands to nothing without the `invariant-checks` feature
+#[cfg(feature = "invariant-checks")]
+macro_rules! invariant {
+    ($cond:expr, $code:ident) => {
+        if !($cond) {
+            return Err(Error::InvariantViolated(InvariantViolated { code: $code }));
+        }
+    };
+}
+#[cfg(not(feature = "invariant-checks"))]
+macro_rules! invariant {
+    ($cond:expr, $code:ident) => {};
+}
+
+/// Returns the current block time in seconds; unit tests run on their own clock instead
+#[cfg(not(test))]
+fn block_time() -> U256 {
+    U256::from(block::timestamp())
+}
+#[cfg(test)]
+use tests::block_time;
+
+sol! {
+    #[derive(Debug)]
+    error CalculationError();
+    
+    #[derive(Debug)] 
+    error InvalidInput();
+
+    #[derive(Debug)]
+    error Unauthorized(address caller);
+
+    #[derive(Debug)]
+    error UnauthorizedForPool(address caller, bytes32 pool_id);
+
+    #[derive(Debug)]
+    error AlreadyInitialized();
+
+    #[derive(Debug)]
+    error InsufficientBalance(uint256 available, uint256 requested);
+
+    #[derive(Debug)]
+    error TransferFailed();
+
+    #[derive(Debug)]
+    error MinimumReserveBreached(uint256 reserves, uint256 minimum, uint256 requested);
+
+    #[derive(Debug)]
+    error InsufficientPayment(uint256 required, uint256 provided);
+
+    #[derive(Debug)]
+    error PolicyNotActive(uint256 policy_id);
+
+    #[derive(Debug)]
+    error ClaimTooEarly(uint256 policy_id, uint256 claimable_at);
+
+    #[derive(Debug)]
+    error NoExcessReserves(uint256 reserves, uint256 required);
+
+    #[derive(Debug)]
+    error PoolAlreadyRegistered(bytes32 pool_id);
+
+    #[derive(Debug)]
+    error PoolNotRegistered(bytes32 pool_id);
+
+    #[derive(Debug)]
+    error QuoteNotFound(bytes32 quote_id);
+
+    #[derive(Debug)]
+    error QuoteExpired(bytes32 quote_id, uint256 expiry);
+
+    #[derive(Debug)]
+    error QuoteAlreadySettled(bytes32 quote_id);
+
+    #[derive(Debug)]
+    error CommitmentMismatch(bytes32 commitment);
+
+    #[derive(Debug)]
+    error RevealTooEarly(uint256 commit_block, uint256 current_block);
+
+    #[derive(Debug)]
+    error CommitmentExpired(uint256 commit_block, uint256 current_block);
+
+    #[derive(Debug)]
+    error PoolNotStale(uint256 last_update, uint256 stale_threshold);
+
+    #[derive(Debug)]
+    error ExposureCapExceeded(uint256 current, uint256 cap, uint256 requested);
+
+    #[derive(Debug)]
+    error DeviationTooLarge(uint256 old_value, uint256 proposed);
+
+    #[derive(Debug)]
+    error StaleSequence(uint256 stored, uint256 provided);
+
+    #[derive(Debug)]
+    error NoFeeRecipient(bytes32 pool_id);
+
+    #[derive(Debug)]
+    error NoOracle(bytes32 pool_id);
+
+    #[derive(Debug)]
+    error StaleData(uint256 age, uint256 max_age);
+
+    #[derive(Debug)]
+    error InvalidOracleResponse(address feed);
+
+    #[derive(Debug)]
+    error NoDataProvider(bytes32 pool_id);
+
+    #[derive(Debug)]
+    error PoolInactive(bytes32 pool_id);
+
+    #[derive(Debug)]
+    error StreamNotActive(uint256 stream_id);
+
+    #[derive(Debug)]
+    error InsufficientBudget(uint256 shortfall);
+
+    #[derive(Debug)]
+    error InsufficientBond(uint256 bond, uint256 required);
+
+    #[derive(Debug)]
+    error BondLocked(uint256 unlocks_at);
+
+    #[derive(Debug)]
+    error NoPendingIl(bytes32 pool_id);
+
+    #[derive(Debug)]
+    error FlashLoanCapExceeded(uint256 cap, uint256 requested);
+
+    #[derive(Debug)]
+    error InvariantViolated(uint8 code);
+
+    #[derive(Debug)]
+    error DataProviderFailed(address provider);
+
+    #[derive(Debug)]
+    error PriceCircuitBreaker(uint256 current_price, uint256 reference_price);
+
+    #[derive(Debug)]
+    error NotMoreConservative(uint256 current, uint256 proposed);
+
+    #[derive(Debug)]
+    error FeeSlippage(uint256 expected, uint256 actual);
+
+    #[derive(Debug)]
+    error TooFrequent(uint256 remaining);
+
+    event ProtocolFeeUpdated(uint256 old_bps, uint256 new_bps);
+    event TreasuryUpdated(address indexed old_treasury, address indexed new_treasury);
+    event PremiumCollected(bytes32 indexed pool_id, uint256 to_reserves, uint256 to_treasury);
+    event TreasuryWithdrawn(address indexed to, uint256 amount);
+    event PoolRegistered(bytes32 indexed pool_id, address token0, address token1, uint256 fee_tier);
+    event RequireRegistrationSet(bool required);
+    event PoolOperatorSet(bytes32 indexed pool_id, address indexed operator);
+    event FlashFeeMultiplierSet(bytes32 indexed pool_id, uint256 multiplier);
+    event BaseFeeSet(bytes32 indexed pool_id, uint256 base_fee);
+    event UpdaterSet(address indexed updater, bool authorized);
+    event MinIlUpdateIntervalSet(uint256 interval);
+    event HistoricalIlUpdated(bytes32 indexed pool_id, uint256 old_il, uint256 new_il, address indexed updater);
+    event PoolMarkedUnrated(bytes32 indexed pool_id, address indexed caller, uint256 last_update);
+    event QuoteIssued(bytes32 indexed quote_id, bytes32 indexed pool_id, address indexed issuer, uint256 fee, uint256 expiry, uint256 epoch);
+    event QuoteSettled(bytes32 indexed quote_id, address indexed issuer, uint256 fee);
+    event QuoteCommitted(bytes32 indexed commitment, address indexed committer, uint256 commit_block);
+    event QuoteRevealed(bytes32 indexed commitment, bytes32 indexed pool_id, uint256 amount, uint256 fee, uint256 epoch);
+    event RevealWindowSet(uint256 blocks);
+    event PremiumPaid(bytes32 indexed pool_id, address indexed payer, uint256 fee, uint256 epoch);
+    event FundDeposited(bytes32 indexed pool_id, address indexed depositor, uint256 amount);
+    event FundWithdrawn(bytes32 indexed pool_id, address indexed to, uint256 amount);
+    event MinReserveSet(bytes32 indexed pool_id, uint256 minimum);
+    event CoveragePurchased(uint256 indexed policy_id, bytes32 indexed pool_id, address indexed holder, uint256 notional, uint256 premium, uint256 expiry, uint256 epoch);
+    event DeductibleAllowed(uint256 deductible, bool allowed);
+    event PolicyClaimed(uint256 indexed policy_id, address indexed holder, uint256 payout);
+    event PolicyExpired(uint256 indexed policy_id);
+    event ExpiryIncentivePaid(uint256 indexed policy_id, address indexed keeper, uint256 incentive);
+    event ExpiryIncentiveSet(uint256 bps);
+    event PolicyRenewed(uint256 indexed policy_id, uint256 premium, uint256 expiry, uint256 epoch);
+    event PolicyTransferred(uint256 indexed policy_id, address indexed old_holder, address indexed new_holder);
+    event PolicyCancelled(uint256 indexed policy_id, address indexed holder, uint256 refund);
+    event RefundWithdrawn(address indexed holder, uint256 amount);
+    event ReservesSkimmed(bytes32 indexed pool_id, address indexed caller, uint256 excess, uint256 incentive);
+    event HedgingFeeModeSet(bytes32 indexed pool_id, bool enabled, uint256 volatility);
+    event LoyaltyTiersUpdated(uint256[] thresholds, uint256[] discounts_bps);
+    event FlashFeeExemptSet(address indexed account, bool exempt);
+    event FeeHolidaySet(bytes32 indexed pool_id, uint256 until);
+    event RebateEpochClosed(bytes32 indexed pool_id, uint256 indexed epoch, uint256 projected_il, uint256 realized_il);
+    event MaxRebateBpsSet(uint256 bps);
+    event DustThresholdSet(bytes32 indexed pool_id, uint256 threshold);
+    event SwapRecorded(bytes32 indexed pool_id, address indexed trader, uint256 amount, uint256 fee, uint256 epoch);
+    event GracePeriodSet(uint256 duration, uint256 factor);
+    event SurgeParamsSet(bytes32 indexed pool_id, uint256 threshold, uint256 multiplier);
+    event LpFeesRecorded(bytes32 indexed pool_id, uint256 fees, uint256 fee_growth_global);
+    event MaxExposureSet(bytes32 indexed pool_id, uint256 cap);
+    event PoolHookSet(bytes32 indexed pool_id, address indexed hook);
+    event SwapPushed(bytes32 indexed pool_id, uint256 volume, uint256 price_after, uint256 il);
+    event ProductConfigured(bytes32 indexed pool_id, uint8 indexed product_id, bool enabled, uint256 base_fee, uint256 il_weight, uint256 size_weight);
+    event LenderSet(address indexed lender, bool authorized);
+    event DefaultScoreParamsSet(uint256 max_score, uint256 decay_bps);
+    event DefaultRecorded(bytes32 indexed pool_id, address indexed lender, uint256 severity, uint256 score);
+    event RepaymentRecorded(bytes32 indexed pool_id, address indexed lender, uint256 score);
+    event UtilizationCheckpointed(bytes32 indexed pool_id, address indexed lender, uint256 utilization, uint256 cumulative);
+    event TwuParamsSet(uint256 window, uint256 weight_bps);
+    event InsuranceShareSet(bytes32 indexed pool_id, uint256 share_bps);
+    event FlashLoanRecorded(bytes32 indexed pool_id, address indexed lender, uint256 amount, uint256 lp_portion, uint256 insurance_portion, uint256 epoch);
+    event FlashLpFeesWithdrawn(bytes32 indexed pool_id, address indexed to, uint256 amount);
+    event FeeRecipientSet(bytes32 indexed pool_id, address indexed recipient);
+    event DefaultFeeRecipientSet(address indexed old_recipient, address indexed new_recipient);
+    event PriceFeedSet(bytes32 indexed pool_id, address indexed feed);
+    event OracleMaxAgeSet(uint256 max_age);
+    event MaxEffectiveIlSet(uint256 cap);
+    event MaxDefaultHistorySet(uint256 cap);
+    event CancellationPenaltySet(uint256 bps);
+    event TargetReserveRatioSet(uint256 ratio);
+    event SkimIncentiveSet(uint256 bps);
+    event MaxIlDeviationSet(uint256 bps);
+    event StaleThresholdSet(uint256 threshold);
+    event UnratedDefaultIlSet(uint256 il);
+    event QuoteValiditySet(uint256 validity);
+    event ParametersUpdated(
+        uint256 indexed epoch,
+        uint256 protocol_fee_bps,
+        uint256 cancellation_penalty_bps,
+        uint256 target_reserve_ratio,
+        uint256 skim_incentive_bps,
+        uint256 stale_threshold,
+        uint256 unrated_default_il,
+        uint256 max_il_deviation_bps,
+        uint256 quote_validity,
+        uint256 max_effective_il,
+        uint256 max_default_history,
+        uint256 grace_duration,
+        uint256 grace_factor
+    );
+    event EmergencyAdminSet(address indexed old_admin, address indexed new_admin);
+    event IlWeightSet(bytes32 indexed pool_id, uint256 il_weight);
+    event RiskPresetSet(uint8 indexed tier, bytes32 name);
+    event RiskPresetApplied(bytes32 indexed pool_id, uint8 indexed tier, bytes32 name);
+    event CircuitBreakerSet(bytes32 indexed pool_id, uint256 max_deviation_bps);
+    event CircuitBreakerBypassSet(bytes32 indexed pool_id, bool bypassed);
+    event DefaultHistoryClamped(bytes32 indexed pool_id, address indexed lender, uint256 provided, uint256 cap);
+    event DataProviderSet(bytes32 indexed pool_id, address indexed provider);
+    event PoolTypeSet(bytes32 indexed pool_id, uint8 pool_type);
+    event FeeCurveSet(bytes32 indexed pool_id, uint256[] size_fractions, uint256[] multipliers);
+    event FeeCurveCleared(bytes32 indexed pool_id);
+    event UncertaintyPremiumSet(uint256 premium);
+    event PoolObservationsReset(bytes32 indexed pool_id, uint256 observations);
+    event MaxDataAgeSet(bytes32 indexed pool_id, uint256 max_age);
+    event DefaultMaxDataAgeSet(uint256 max_age);
+    event PoolActiveSet(bytes32 indexed pool_id, bool active);
+    event OpenInterestWeightSet(uint256 weight);
+    event SystemicRiskParamsSet(uint256 soft_threshold, uint256 hard_threshold, uint256 max_multiplier);
+    event AccrualEpochLengthSet(uint256 length);
+    event PremiumEpochsClosed(bytes32 indexed pool_id, uint256 next_epoch, uint256 earned);
+    event StreamOpened(uint256 indexed stream_id, bytes32 indexed pool_id, address indexed payer, uint256 notional, uint256 rate);
+    event StreamCheckpointed(uint256 indexed stream_id, uint256 owed, uint256 rate);
+    event StreamClosed(uint256 indexed stream_id, uint256 paid);
+    event CoverageBudgetDeposited(bytes32 indexed pool_id, address indexed payer, uint256 amount);
+    event CoverageBudgetWithdrawn(bytes32 indexed pool_id, address indexed payer, uint256 amount);
+    event AutoRenewSet(uint256 indexed policy_id, bool enabled);
+    event RenewalFunded(uint256 indexed policy_id, uint256 amount, uint256 balance);
+    event RenewalBalanceWithdrawn(uint256 indexed policy_id, address indexed holder, uint256 amount);
+    event PolicyLapsed(uint256 indexed policy_id, uint256 premium, uint256 balance);
+    event IlUpdateGuarded(bytes32 indexed pool_id, address indexed updater, uint256 old_value, uint256 proposed);
+    event UpdaterBondParamsSet(uint256 min_bond, uint256 cooldown);
+    event UpdaterBondPosted(address indexed updater, uint256 amount, uint256 bond);
+    event UpdaterBondWithdrawn(address indexed updater, uint256 amount);
+    event UpdaterSlashed(address indexed updater, address indexed recipient, uint256 amount);
+    event IlDisputeWindowSet(uint256 window);
+    event IlUpdatePending(bytes32 indexed pool_id, uint256 il, uint256 effective_at, address indexed updater);
+    event PendingIlRejected(bytes32 indexed pool_id, uint256 il, address indexed guardian);
+    event IlSourceParamsSet(uint256 max_sources, uint256 quorum, uint256 heartbeat);
+    event IlSourceAdded(bytes32 indexed pool_id, address indexed source);
+    event IlSourceRemoved(bytes32 indexed pool_id, address indexed source);
+    event IlSourceSubmitted(bytes32 indexed pool_id, address indexed source, uint256 il);
+    event MaxFlashAmountSet(bytes32 indexed pool_id, uint256 cap);
+    event IdempotentReplay(address indexed caller, bytes32 indexed key);
+    event AprReferenceSizeSet(bytes32 indexed pool_id, uint256 size);
+    event StressScenarioSet(bytes32 indexed name, uint256 il_shock, uint256 utilization, uint256 volume_haircut_bps);
+    event FeeSmoothingSet(bytes32 indexed pool_id, uint256 max_change_bps);
+    event TokenDecimalsSet(bytes32 indexed pool_id, uint8 decimals0, uint8 decimals1);
+    event FeeFormulaSet(bytes32 indexed pool_id, address formula);
+    event FeeFormulaBoundsSet(bytes32 indexed pool_id, uint256 min_bps, uint256 max_bps);
+    event FeeFormulaFallback(bytes32 indexed pool_id, address indexed formula);
+}
+
+sol_interface! {
+    /// Chainlink-style USD price feed for a pool's token
+    interface IPriceFeed {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (uint80, int256, uint256, uint256, uint80);
+    }
+
+    /// Source of a pool's live liquidity and volume on whichever AMM hosts it
+    interface IPoolDataProvider {
+        function getLiquidity(bytes32 pool_id) external view returns (uint256);
+        function getVolume(bytes32 pool_id) external view returns (uint256);
+    }
+
+    /// Partner-supplied insurance fee curve, fed the same WAD inputs as the internal formula
+    interface IFeeFormula {
+        function calculateFee(bytes32 pool_id, uint256 amount, uint256 total_liquidity, uint256 total_volume, uint256 historical_il, uint256 base_fee, uint256 il_weight, uint256 size_weight) external view returns (uint256);
+    }
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    /// Math calculation error
+    CalculationError(CalculationError),
+    /// Invalid input parameters 
+    InvalidInput(InvalidInput),
+    /// Caller lacks the required role
+    Unauthorized(Unauthorized),
+    /// Caller lacks the required role for this pool
+    UnauthorizedForPool(UnauthorizedForPool),
+    /// Owner has already been set
+    AlreadyInitialized(AlreadyInitialized),
+    /// Requested amount exceeds the tracked balance
+    InsufficientBalance(InsufficientBalance),
+    /// Native ETH transfer was rejected
+    TransferFailed(TransferFailed),
+    /// Withdrawal would leave the pool below its minimum reserve
+    MinimumReserveBreached(MinimumReserveBreached),
+    /// Attached value does not cover the required amount
+    InsufficientPayment(InsufficientPayment),
+    /// Policy is not in a state that allows this action
+    PolicyNotActive(PolicyNotActive),
+    /// Policy is still inside its claim waiting period
+    ClaimTooEarly(ClaimTooEarly),
+    /// Pool reserves do not exceed what its coverage requires
+    NoExcessReserves(NoExcessReserves),
+    /// Pool id has already been registered
+    PoolAlreadyRegistered(PoolAlreadyRegistered),
+    /// Pool id must be registered before quoting
+    PoolNotRegistered(PoolNotRegistered),
+    /// No quote was issued under this id
+    QuoteNotFound(QuoteNotFound),
+    /// Quote validity window has passed
+    QuoteExpired(QuoteExpired),
+    /// Quote has already been settled
+    QuoteAlreadySettled(QuoteAlreadySettled),
+    /// Revealed parameters do not match any commitment
+    CommitmentMismatch(CommitmentMismatch),
+    /// Reveal landed in the same block as its commitment
+    RevealTooEarly(RevealTooEarly),
+    /// Reveal window for the commitment has passed
+    CommitmentExpired(CommitmentExpired),
+    /// Pool data is still within the staleness threshold
+    PoolNotStale(PoolNotStale),
+    /// Coverage purchase would push the pool past its exposure cap
+    ExposureCapExceeded(ExposureCapExceeded),
+    /// IL update moves the stored value further than the deviation band allows
+    DeviationTooLarge(DeviationTooLarge),
+    /// IL update carries a sequence number at or below the pool's latest
+    StaleSequence(StaleSequence),
+    /// Neither the pool nor the contract has a fee recipient configured
+    NoFeeRecipient(NoFeeRecipient),
+    /// Pool has no price feed configured
+    NoOracle(NoOracle),
+    /// Data is older than its maximum age
+    StaleData(StaleData),
+    /// Price feed reverted or answered with a non-positive price
+    InvalidOracleResponse(InvalidOracleResponse),
+    /// Pool has no data provider registered
+    NoDataProvider(NoDataProvider),
+    /// Data provider reverted when queried
+    DataProviderFailed(DataProviderFailed),
+    /// Pool has been deactivated and no longer quotes
+    PoolInactive(PoolInactive),
+    /// Premium stream does not exist or has been closed
+    StreamNotActive(StreamNotActive),
+    /// Prepaid coverage budget cannot cover a swap's insurance fee
+    InsufficientBudget(InsufficientBudget),
+    /// Updater's bond is below the minimum required to write IL
+    InsufficientBond(InsufficientBond),
+    /// Updater's bond is still within its cooldown after the last IL write
+    BondLocked(BondLocked),
+    /// Pool has no IL update waiting out its dispute window
+    NoPendingIl(NoPendingIl),
+    /// Flash loan is larger than the pool's per-loan cap
+    FlashLoanCapExceeded(FlashLoanCapExceeded),
+    /// A self-check on a computed fee failed (builds with `invariant-checks` only)
+    InvariantViolated(InvariantViolated),
+    /// Quoted price strays too far from the pool's reference price
+    PriceCircuitBreaker(PriceCircuitBreaker),
+    /// Emergency change would raise a fee or loosen a cap
+    NotMoreConservative(NotMoreConservative),
+    /// Fee at execution exceeds the caller's expected fee plus tolerance
+    FeeSlippage(FeeSlippage),
+    /// IL update arrived before the pool's minimum update interval elapsed
+    TooFrequent(TooFrequent),
+}
+
+/// Global parameters governance applies as one set: (protocol_fee_bps, cancellation_penalty_bps,
+/// target_reserve_ratio, skim_incentive_bps, stale_threshold, unrated_default_il,
+/// max_il_deviation_bps, quote_validity, max_effective_il, max_default_history, grace_duration,
+/// grace_factor), each in the units of its individual setter with zero meaning the same default
+pub type ParameterBundle = (U256, U256, U256, U256, U256, U256, U256, U256, U256, U256, U256, U256);
+
+/// Everything configured for one pool, ABI-encoded as the static tuple (uint256 base_fee,
+/// uint256 flash_fee_multiplier, uint256 max_exposure, uint8 pool_type, address operator,
+/// address hook, uint256 max_data_age, bool active, uint256 fee_floor_bps, uint256 fee_cap_bps,
+/// uint256 surge_threshold, uint256 surge_multiplier, uint256 il_weight); unset fields read as
+/// zero except the base fee, data age, fee band and IL weight, which report their effective
+/// defaults, and `active`, which defaults to true. The fee floor/cap are the band of the
+/// internal fee an external formula is clamped to. Fields are only ever appended, so existing
+/// offsets stay put
+pub type PoolConfig = (U256, U256, U256, u8, Address, Address, U256, bool, U256, U256, U256, U256, U256);
+
+sol_storage! {
+    /// On-chain description of what a pool id corresponds to
+    pub struct PoolMetadata {
+        address token0;
+        address token1;
+        uint256 fee_tier;
+        bool registered;
+        uint256 registered_at;
+        uint8 pool_type;
+        // Token decimals for denominated quotes; both read as 18 until set
+        bool decimals_set;
+        uint8 decimals0;
+        uint8 decimals1;
+    }
+
+    /// Fee locked in for a caller until its expiry
+    pub struct Quote {
+        bytes32 params_hash;
+        address issuer;
+        uint256 fee;
+        uint256 expiry;
+        bool settled;
+    }
+
+    /// Coverage bought against a pool's reserves
+    pub struct Policy {
+        address holder;
+        bytes32 pool_id;
+        uint256 notional;
+        uint256 premium;
+        uint256 start;
+        uint256 expiry;
+        uint8 status;
+        uint256 deductible;
+        uint256 coinsurance_bps;
+        uint8 product_id;
+        uint256 coverage_fraction;
+        uint256 term;
+        bool auto_renew;
+        uint256 renewal_balance;
+        // Pool IL (as pricing resolves it) when the policy started; claims pay only the IL
+        // realized since
+        uint256 il_at_start;
+    }
+
+    /// Pay-as-you-go coverage accruing premium per second until closed
+    pub struct PremiumStream {
+        address payer;
+        bytes32 pool_id;
+        uint256 notional;
+        uint256 rate;
+        uint256 last_checkpoint;
+        uint256 owed;
+        bool open;
+    }
+
+    /// An updater's track record across its own IL writes
+    pub struct UpdaterStats {
+        uint256 accepted;
+        uint256 guarded;
+        uint256 last_update;
+        uint256 pools_touched;
+    }
+
+    /// Outcome of a keyed recording call, returned again when the key is replayed
+    pub struct IdempotentResult {
+        bool consumed;
+        uint256 first;
+        uint256 second;
+    }
+
+    /// Shocks a stressed quote applies on top of live data; zero fields leave that input live
+    pub struct StressScenario {
+        uint256 il_shock;
+        uint256 utilization;
+        uint256 volume_haircut_bps;
+    }
+
+    /// One fee charged through a recording call
+    pub struct FeeRecord {
+        uint256 fee;
+        uint256 amount;
+        uint256 timestamp;
+    }
+
+    /// One IL value written for a pool, with when and by whom
+    pub struct IlHistoryEntry {
+        uint256 value;
+        uint256 timestamp;
+        address updater;
+    }
+
+    /// Running utilization-seconds total as of a lender's checkpoint
+    pub struct UtilizationCheckpoint {
+        uint256 timestamp;
+        uint256 utilization;
+        uint256 cumulative;
+    }
+
+    /// One bundle parameter's changes: the epochs it changed in, ascending, and the values it took
+    pub struct ParameterHistory {
+        uint256[] epochs;
+        uint256[] values;
+    }
+
+    /// Premiums a pool collected during one rebate epoch and the IL they were priced at
+    pub struct RebateEpoch {
+        uint256 premium_total;
+        // Sum of premium * IL (WAD) over the epoch's premiums
+        uint256 premium_weighted_il;
+        uint256 realized_il;
+        bool closed;
+    }
+
+    /// Named bundle of pool risk parameters copied in one call
+    pub struct RiskPreset {
+        bytes32 name;
+        bool defined;
+        uint256 base_fee;
+        uint256 il_weight;
+        uint256 surge_threshold;
+        uint256 surge_multiplier;
+        uint256 flash_multiplier;
+        uint256 max_exposure;
+    }
+
+    /// Pricing parameters for a non-IL insurance product on a pool
+    pub struct ProductConfig {
+        bool enabled;
+        uint256 base_fee;
+        uint256 il_weight;
+        uint256 size_weight;
+    }
+
+    #[entrypoint]
+    pub struct InsuranceCalculator {
+        mapping(bytes32 => uint256) historical_il;
+        mapping(bytes32 => uint256) default_flash_fee_multiplier;
+
+        address owner;
+        // Incident-response role that may only lower fees or tighten caps
+        address emergency_admin;
+
+        mapping(bytes32 => PoolMetadata) pools;
+        // Append-only index of every pool ever registered
+        bytes32[] all_pools;
+        mapping(bytes32 => bool) pool_listed;
+        // When set, quotes revert for pools that were never registered
+        bool require_registration;
+
+        // Partner team allowed to manage each pool's parameters
+        mapping(bytes32 => address) pool_operator;
+        // Per-pool insurance base fee override; zero means the default
+        mapping(bytes32 => uint256) base_fee;
+        // Per-pool IL weight for the IL product; zero means the default
+        mapping(bytes32 => uint256) il_weight;
+        // Per-(pool, product) pricing for products other than IL cover
+        mapping(bytes32 => mapping(uint8 => ProductConfig)) products;
+        // Owner-defined risk tiers ("stable", "blue-chip", ...) pools can be configured from
+        mapping(uint8 => RiskPreset) risk_presets;
+
+        // Protocol share of every collected premium, routed to the treasury
+        uint256 protocol_fee_bps;
+        address treasury;
+        uint256 treasury_balance;
+        mapping(bytes32 => uint256) pool_reserves;
+
+        // Insurance fund: pool_reserves per pool, their sum, and the floor owner withdrawals must respect
+        uint256 total_reserves;
+        mapping(bytes32 => uint256) min_reserve;
+
+        // Policy premium accrual: the pool's share of each premium waits in unearned_premium
+        // and moves to reserves as the epochs it covers are closed. Pools keep what each epoch
+        // earns and the next one to close; policies keep their per-epoch share so a
+        // cancellation can unwind it. The epoch length (zero means the default) can only
+        // change while nothing is unearned
+        uint256 accrual_epoch_length;
+        uint256 total_unearned_premium;
+        mapping(bytes32 => uint256) unearned_premium;
+        mapping(bytes32 => uint256) earned_premium;
+        mapping(bytes32 => uint256) next_accrual_epoch;
+        mapping(bytes32 => mapping(uint256 => uint256)) epoch_premium;
+        mapping(uint256 => mapping(uint256 => uint256)) policy_epoch_premium;
+
+        // Streaming coverage, priced per second off the pool's data provider
+        mapping(uint256 => PremiumStream) streams;
+        uint256 next_stream_id;
+
+        // Prepaid ETH each recorder holds per pool, debited by the insurance fee of every swap
+        // it records
+        mapping(bytes32 => mapping(address => uint256)) coverage_budget;
+
+        // Per-updater accountability over the guarded write paths (owner force-updates excluded),
+        // and which pools each has written
+        mapping(address => UpdaterStats) updater_stats;
+        mapping(address => mapping(bytes32 => bool)) updater_pools;
+
+        // ETH bonded by each updater, the minimum an authorized updater needs to write IL (zero
+        // disables) and how long a bond stays locked after its last write (zero means the default)
+        mapping(address => uint256) updater_bond;
+        uint256 min_updater_bond;
+        uint256 updater_bond_cooldown;
+
+        // Non-owner IL updates wait out the dispute window (zero applies them at once) as each
+        // pool's pending value, promoted once effective_at passes; zero effective_at means none
+        uint256 il_dispute_window;
+        mapping(bytes32 => uint256) pending_il;
+        mapping(bytes32 => uint256) pending_il_effective_at;
+        mapping(bytes32 => address) pending_il_updater;
+        // Latest effective_at among each updater's staged values; its bond stays locked until then
+        mapping(address => uint256) updater_pending_until;
+
+        // Pools with registered sources price off the median of their fresh answers instead of
+        // the single stored IL; zero params fall back to the defaults
+        uint256 max_il_sources;
+        uint256 il_source_quorum;
+        uint256 il_source_heartbeat;
+        mapping(bytes32 => address[]) il_sources;
+        mapping(bytes32 => mapping(address => bool)) is_il_source;
+        mapping(bytes32 => mapping(address => uint256)) il_source_value;
+        mapping(bytes32 => mapping(address => uint256)) il_source_updated_at;
+
+        // Flash amounts recorded against each pool in flash_block; a newer block starts from zero
+        mapping(bytes32 => uint256) flash_block;
+        mapping(bytes32 => uint256) flash_borrowed_in_block;
+
+        // Largest single flash loan each pool prices (zero is unlimited)
+        mapping(bytes32 => uint256) max_flash_amount;
+
+        // Keys consumed by each caller's idempotent recording calls; scoping by caller keeps
+        // one relayer from burning another's keys
+        mapping(address => mapping(bytes32 => IdempotentResult)) idempotent_results;
+
+        // Position size each pool's required premium APR is quoted for (zero uses the default)
+        mapping(bytes32 => uint256) apr_reference_size;
+
+        // Owner-published stress scenarios by name
+        mapping(bytes32 => StressScenario) stress_scenarios;
+
+        // Ring of the last RECENT_FEES_CAPACITY recorded fees per pool, written at
+        // recent_fee_count % capacity
+        mapping(bytes32 => mapping(uint256 => FeeRecord)) recent_fees;
+        mapping(bytes32 => uint256) recent_fee_count;
+
+        // Per-block bound on how far a pool's swap fee rate (fee per unit swapped, WAD, before
+        // loyalty discounts) may move from the last one (zero disables smoothing), and that last
+        // rate with the block it was recorded in
+        mapping(bytes32 => uint256) fee_smoothing_bps;
+        mapping(bytes32 => uint256) last_recorded_fee_rate;
+        mapping(bytes32 => uint256) last_recorded_fee_block;
+
+        // External fee formula per pool, and the band its output is clamped to in bps of the
+        // internal formula's fee (zero selects the defaults)
+        mapping(bytes32 => address) fee_formula;
+        mapping(bytes32 => uint256) formula_min_bps;
+        mapping(bytes32 => uint256) formula_max_bps;
+
+        // Coverage policies and the notional they leave outstanding per pool
+        mapping(uint256 => Policy) policies;
+        uint256 next_policy_id;
+        mapping(bytes32 => uint256) outstanding_coverage;
+        // WAD weight on a pool's open coverage as a share of its liquidity (zero disables)
+        uint256 open_interest_weight;
+        // Open coverage across every pool, and the systemic multiplier it drives: 1.0 up to the
+        // soft threshold, ramping to max_multiplier at the hard threshold (zero hard disables)
+        uint256 total_outstanding_coverage;
+        uint256 systemic_soft_threshold;
+        uint256 systemic_hard_threshold;
+        uint256 systemic_max_multiplier;
+        // Cap on each pool's outstanding coverage; zero leaves the pool uncapped
+        mapping(bytes32 => uint256) max_exposure;
+        // Policy ids held by each address, with each policy's position in its holder's list
+        mapping(address => uint256[]) holder_policies;
+        mapping(uint256 => uint256) holder_policy_index;
+        // Cancellation refunds awaiting withdrawal, and the penalty withheld from them
+        mapping(address => uint256) refund_balance;
+        uint256 cancellation_penalty_bps;
+        // Open rebate epoch per pool and each epoch's projected versus realized IL, with the
+        // share of a premium a rebate may return (zero means the default)
+        mapping(bytes32 => uint256) rebate_epoch;
+        mapping(bytes32 => mapping(uint256 => RebateEpoch)) rebate_epochs;
+        uint256 max_rebate_bps;
+        // Deductibles (WAD fraction of loss) buyers may choose besides zero
+        mapping(uint256 => bool) allowed_deductibles;
+
+        // Optional premium floor at the cost of hedging IL with options, with the annualized volatility to price it at
+        mapping(bytes32 => bool) hedging_fee_mode;
+        mapping(bytes32 => uint256) hedging_volatility;
+
+        // Reserves held per unit of outstanding coverage (WAD, zero means 1.0) before surplus can be skimmed
+        uint256 target_reserve_ratio;
+        uint256 skim_incentive_bps;
+        // Share of a lapsed policy's premium paid from reserves to whoever expires it
+        uint256 expiry_incentive_bps;
+
+        // Addresses allowed to push IL data
+        mapping(address => bool) authorized_updaters;
+
+        // IL freshness tracking; stale pools can be flagged to quote off a conservative default
+        mapping(bytes32 => uint256) last_il_update;
+        // Sequence number of each pool's latest IL write; writes must strictly increase it
+        mapping(bytes32 => uint256) update_sequence;
+        // Seconds that must pass between accepted IL writes from anyone but the owner (zero disables),
+        // measured from each pool's last updater write rather than hook or promotion refreshes
+        uint256 min_il_update_interval;
+        mapping(bytes32 => uint256) last_updater_write;
+
+        // Swap hook allowed to push each pool's realized figures, and its ring of recent prices
+        mapping(bytes32 => address) pool_hook;
+        mapping(bytes32 => mapping(uint256 => uint256)) price_observations;
+        mapping(bytes32 => uint256) observation_count;
+        mapping(bytes32 => uint256) last_price_observation;
+        // Oldest IL or price observation each pool quotes from, in seconds; zero defers to the
+        // global default, and a zero default imposes no freshness requirement
+        mapping(bytes32 => uint256) max_data_age;
+        uint256 default_max_data_age;
+        // Largest deviation from the observed reference a price-aware quote accepts, in basis
+        // points (zero disables), and the owner's recovery override
+        mapping(bytes32 => uint256) circuit_breaker_bps;
+        mapping(bytes32 => bool) circuit_breaker_bypassed;
+        mapping(bytes32 => bool) unrated;
+        uint256 stale_threshold;
+        uint256 unrated_default_il;
+        // Relative band a single IL update must stay within, in basis points (zero means the default)
+        uint256 max_il_deviation_bps;
+        // Ceiling on the IL any quote prices with, whatever storage holds (zero means 1.0)
+        uint256 max_effective_il;
+
+        // Issued quotes, keyed by quote id
+        mapping(bytes32 => Quote) quotes;
+        uint256 quote_nonce;
+        uint256 quote_validity;
+
+        // Commit-reveal quoting: commitment => block it was committed in
+        mapping(bytes32 => uint256) commitment_block;
+        uint256 reveal_window;
+
+        // Cumulative insured volume per address and the (threshold, discount) tiers it unlocks
+        mapping(address => uint256) insured_volume;
+        uint256[] loyalty_thresholds;
+        uint256[] loyalty_discounts_bps;
+
+        // Keepers that flash-borrow without fees
+        mapping(address => bool) flash_fee_exempt;
+
+        // Bootstrapping discount for newly registered pools: fee scaled by grace_factor for grace_duration
+        uint256 grace_duration;
+        uint256 grace_factor;
+
+        // Current parameter epoch, when each epoch began, and the change history of each
+        // `ParameterBundle` field keyed by its position in the bundle. A write to any of them
+        // opens a new epoch that records only the fields that changed; globals outside the
+        // bundle are not versioned.
+        uint256 parameter_epoch;
+        mapping(uint256 => uint256) parameter_epoch_started_at;
+        mapping(uint256 => ParameterHistory) parameter_history;
+
+        // Flash fee surge above a utilization threshold, ramping to surge_multiplier at 100% (zero disables)
+        mapping(bytes32 => uint256) surge_threshold;
+        mapping(bytes32 => uint256) surge_multiplier;
+
+        // Per-pool promotional window during which insurance is free
+        mapping(bytes32 => uint256) fee_holiday_until;
+        // Per-pool WAD amount below which insurance is waived and swaps go unrecorded (zero disables)
+        mapping(bytes32 => uint256) dust_threshold;
+        // Per-pool size curve of (size_fraction, multiplier) WAD breakpoints; when installed it
+        // replaces the parametric size multiplier
+        mapping(bytes32 => uint256[]) fee_curve_x;
+        mapping(bytes32 => uint256[]) fee_curve_y;
+
+        // Per-pool swap analytics from the recording entrypoint
+        mapping(bytes32 => uint256) recorded_volume;
+        mapping(bytes32 => uint256) recorded_swap_count;
+        mapping(bytes32 => uint256) recorded_fees;
+
+        // Cumulative LP fees per unit of liquidity, WAD-scaled, and the liquidity collected
+        // fees are spread over as last reported by the pool's admin or a lender
+        mapping(bytes32 => uint256) fee_growth_global;
+        mapping(bytes32 => uint256) fee_growth_liquidity;
+
+        // Lender contracts allowed to report flash loan outcomes, and the default score (WAD)
+        // they build per pool; zero params mean the defaults
+        mapping(address => bool) lenders;
+        mapping(bytes32 => uint256) default_score;
+        uint256 max_default_score;
+        uint256 repayment_decay_bps;
+        // Ceiling on any default history a flash fee is priced with (zero means the default)
+        uint256 max_default_history;
+
+        // Ring of each pool's utilization checkpoints, and the window (zero means the default)
+        // and weight the time-weighted figure is blended with spot at; the weight only counts
+        // once set, so zero can mean pure spot
+        mapping(bytes32 => mapping(uint256 => UtilizationCheckpoint)) utilization_checkpoints;
+        mapping(bytes32 => uint256) utilization_checkpoint_count;
+        uint256 twu_window;
+        uint256 twu_weight_bps;
+        bool twu_weight_set;
+
+        // Share of each pool's flash fee routed to its insurance fund, and the LP remainder
+        // held for withdrawal by the pool's admin
+        mapping(bytes32 => uint256) insurance_share_bps;
+        mapping(bytes32 => uint256) flash_lp_fees;
+
+        // Where routers should send collected fees: per-pool override, else the global default
+        mapping(bytes32 => address) fee_recipient;
+        address default_fee_recipient;
+
+        // USD price feed per pool and the oldest answer accepted from it (zero means the default)
+        mapping(bytes32 => address) price_feed;
+        uint256 oracle_max_age;
+
+        // Adapter each pool's liquidity and volume are read from
+        mapping(bytes32 => address) data_provider;
+        // Pools taken out of service; stored inverted so every pool starts active
+        mapping(bytes32 => bool) pool_deactivated;
+
+        // Ring of each pool's last IL writes and the total ever written
+        mapping(bytes32 => mapping(uint256 => IlHistoryEntry)) il_history;
+        mapping(bytes32 => uint256) il_history_count;
+
+        // Guarded updater IL writes behind each pool's rating, only resettable by the owner, and
+        // the WAD premium `k` thin data is priced with as `1 + k / (observations + 1)` (zero
+        // disables)
+        mapping(bytes32 => uint256) il_observations;
+        uint256 uncertainty_premium;
+    }
+}
+
+#[public]
+impl InsuranceCalculator {
+    /// Claims ownership of a freshly deployed calculator
+    pub fn initialize(&mut self) -> Result<(), Error> {
+        if self.owner.get() != Address::ZERO {
+            return Err(Error::AlreadyInitialized(AlreadyInitialized{}));
+        }
+        self.owner.set(msg::sender());
+        Ok(())
+    }
+
+    /// Returns the contract owner
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Sets the emergency admin (zero removes the role)
+    pub fn set_emergency_admin(&mut self, admin: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        let old_admin = self.emergency_admin.get();
+        self.emergency_admin.set(admin);
+        evm::log(EmergencyAdminSet { old_admin, new_admin: admin });
+        Ok(())
+    }
+
+    /// Returns the emergency admin
+    pub fn emergency_admin(&self) -> Address {
+        self.emergency_admin.get()
+    }
+
+    /// Lowers a pool's insurance base fee below its current effective value
+    ///
+    /// Zero is rejected since it restores the default, which may be higher.
+    pub fn emergency_lower_base_fee(&mut self, pool_id: FixedBytes<32>, new_value: U256) -> Result<(), Error> {
+        self.only_emergency_admin()?;
+        let current = self.effective_base_fee(pool_id);
+        if new_value == U256::ZERO || new_value >= current {
+            return Err(Error::NotMoreConservative(NotMoreConservative { current, proposed: new_value }));
+        }
+        self.base_fee.insert(pool_id, new_value);
+        evm::log(BaseFeeSet { pool_id, base_fee: new_value });
+        Ok(())
+    }
+
+    /// Lowers a pool's flash fee multiplier below its current effective value (zero means 1.0)
+    pub fn emergency_lower_flash_fee_multiplier(&mut self, pool_id: FixedBytes<32>, new_value: U256) -> Result<(), Error> {
+        self.only_emergency_admin()?;
+        let effective = |multiplier: U256| if multiplier == U256::ZERO { U256::from(WAD) } else { multiplier };
+        let current = self.default_flash_fee_multiplier.get(pool_id);
+        if effective(new_value) >= effective(current) {
+            return Err(Error::NotMoreConservative(NotMoreConservative { current, proposed: new_value }));
+        }
+        self.default_flash_fee_multiplier.insert(pool_id, new_value);
+        evm::log(FlashFeeMultiplierSet { pool_id, multiplier: new_value });
+        Ok(())
+    }
+
+    /// Lowers a pool's surge multiplier below its current value; zero disables surge entirely
+    pub fn emergency_lower_surge_multiplier(&mut self, pool_id: FixedBytes<32>, new_value: U256) -> Result<(), Error> {
+        self.only_emergency_admin()?;
+        let current = self.surge_multiplier.get(pool_id);
+        let threshold = self.surge_threshold.get(pool_id);
+        if new_value >= current {
+            return Err(Error::NotMoreConservative(NotMoreConservative { current, proposed: new_value }));
+        }
+        Self::check_surge_params(threshold, new_value)?;
+        self.surge_multiplier.insert(pool_id, new_value);
+        evm::log(SurgeParamsSet { pool_id, threshold, multiplier: new_value });
+        Ok(())
+    }
+
+    /// Tightens a pool's exposure cap below its current value; any non-zero cap tightens an
+    /// uncapped pool
+    pub fn emergency_tighten_exposure_cap(&mut self, pool_id: FixedBytes<32>, new_value: U256) -> Result<(), Error> {
+        self.only_emergency_admin()?;
+        let current = self.max_exposure.get(pool_id);
+        if new_value == U256::ZERO || (current != U256::ZERO && new_value >= current) {
+            return Err(Error::NotMoreConservative(NotMoreConservative { current, proposed: new_value }));
+        }
+        self.max_exposure.insert(pool_id, new_value);
+        evm::log(MaxExposureSet { pool_id, cap: new_value });
+        Ok(())
+    }
+
+    /// Returns whether the contract implements an ERC-165 interface
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        // 0xffffffff is never a valid id, so it never matches
+        interface_id == ERC165_INTERFACE_ID || interface_id == interface::INTERFACE_ID
+    }
+
+    /// Validates and writes every bundle parameter at once, returning the new parameter epoch
+    ///
+    /// Every field is checked against its individual setter's bounds before any is written, so
+    /// one invalid field reverts the whole bundle.
+    pub fn apply_parameter_bundle(&mut self, bundle: ParameterBundle) -> Result<U256, Error> {
+        self.only_owner()?;
+        let (
+            protocol_fee_bps,
+            cancellation_penalty_bps,
+            target_reserve_ratio,
+            skim_incentive_bps,
+            stale_threshold,
+            unrated_default_il,
+            max_il_deviation_bps,
+            quote_validity,
+            max_effective_il,
+            max_default_history,
+            grace_duration,
+            grace_factor,
+        ) = bundle;
+        if protocol_fee_bps > U256::from(MAX_PROTOCOL_FEE_BPS)
+            || cancellation_penalty_bps > U256::from(BPS_DENOMINATOR)
+            || skim_incentive_bps > U256::from(MAX_SKIM_INCENTIVE_BPS)
+            || unrated_default_il > U256::from(WAD)
+            || max_effective_il > U256::from(WAD)
+            || grace_factor > U256::from(WAD)
+            || grace_duration > U256::from(MAX_GRACE_DURATION)
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+
+        self.protocol_fee_bps.set(protocol_fee_bps);
+        self.cancellation_penalty_bps.set(cancellation_penalty_bps);
+        self.target_reserve_ratio.set(target_reserve_ratio);
+        self.skim_incentive_bps.set(skim_incentive_bps);
+        self.stale_threshold.set(stale_threshold);
+        self.unrated_default_il.set(unrated_default_il);
+        self.max_il_deviation_bps.set(max_il_deviation_bps);
+        self.quote_validity.set(quote_validity);
+        self.max_effective_il.set(max_effective_il);
+        self.max_default_history.set(max_default_history);
+        self.grace_duration.set(grace_duration);
+        self.grace_factor.set(grace_factor);
+        Ok(self.snapshot_parameters())
+    }
+
+    /// Returns the bundle parameters as stored, in `ParameterBundle` order
+    pub fn get_parameters(&self) -> ParameterBundle {
+        (
+            self.protocol_fee_bps.get(),
+            self.cancellation_penalty_bps.get(),
+            self.target_reserve_ratio.get(),
+            self.skim_incentive_bps.get(),
+            self.stale_threshold.get(),
+            self.unrated_default_il.get(),
+            self.max_il_deviation_bps.get(),
+            self.quote_validity.get(),
+            self.max_effective_il.get(),
+            self.max_default_history.get(),
+            self.grace_duration.get(),
+            self.grace_factor.get(),
+        )
+    }
+
+    /// Returns the current parameter epoch
+    pub fn parameter_epoch(&self) -> U256 {
+        self.parameter_epoch.get()
+    }
+
+    /// Returns the bundle parameters in force during `epoch`, in `ParameterBundle` order, and
+    /// the timestamp the epoch began (zero for the initial epoch)
+    pub fn get_parameters_at_epoch(&self, epoch: U256) -> Result<(ParameterBundle, U256), Error> {
+        if epoch > self.parameter_epoch.get() {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let value = |index| self.parameter_at_epoch(index, epoch);
+        Ok((
+            (
+                value(0),
+                value(1),
+                value(2),
+                value(3),
+                value(4),
+                value(5),
+                value(6),
+                value(7),
+                value(8),
+                value(9),
+                value(10),
+                value(11),
+            ),
+            self.parameter_epoch_started_at.get(epoch),
+        ))
+    }
+
+    /// Sets the protocol share of collected premiums, in basis points
+    pub fn set_protocol_fee_bps(&mut self, bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if bps > U256::from(MAX_PROTOCOL_FEE_BPS) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let old_bps = self.protocol_fee_bps.get();
+        self.protocol_fee_bps.set(bps);
+        evm::log(ProtocolFeeUpdated { old_bps, new_bps: bps });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Sets the address allowed to withdraw the treasury balance
+    pub fn set_treasury(&mut self, treasury: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        if treasury == Address::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let old_treasury = self.treasury.get();
+        self.treasury.set(treasury);
+        evm::log(TreasuryUpdated { old_treasury, new_treasury: treasury });
+        Ok(())
+    }
+
+    /// Returns the protocol share of collected premiums, in basis points
+    pub fn protocol_fee_bps(&self) -> U256 {
+        self.protocol_fee_bps.get()
+    }
+
+    /// Returns the treasury address
+    pub fn treasury(&self) -> Address {
+        self.treasury.get()
+    }
+
+    /// Returns the premiums accrued to the treasury and not yet withdrawn
+    pub fn treasury_balance(&self) -> U256 {
+        self.treasury_balance.get()
+    }
+
+    /// Returns the premiums held in reserve for a pool
+    pub fn get_pool_reserves(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.pool_reserves.get(pool_id)
+    }
+
+    /// Sets the length of premium accrual epochs in seconds (zero restores the default)
+    ///
+    /// Reverts while any policy premium is still unearned, since epochs are numbered by it.
+    pub fn set_accrual_epoch_length(&mut self, length: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if length != U256::ZERO && length < U256::from(MIN_ACCRUAL_EPOCH_LENGTH) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if self.total_unearned_premium.get() != U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.accrual_epoch_length.set(length);
+        evm::log(AccrualEpochLengthSet { length });
+        Ok(())
+    }
+
+    /// Returns the length of premium accrual epochs in seconds
+    pub fn accrual_epoch_length(&self) -> U256 {
+        let length = self.accrual_epoch_length.get();
+        if length == U256::ZERO {
+            U256::from(DEFAULT_ACCRUAL_EPOCH_LENGTH)
+        } else {
+            length
+        }
+    }
+
+    /// Moves the premium earned in a pool's finished epochs from unearned into reserves,
+    /// returning the amount recognized
+    ///
+    /// Closes at most 64 epochs per call; a longer backlog needs further calls.
+    pub fn close_epoch(&mut self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        self.only_pool_admin(pool_id)?;
+        let current = block_time() / self.accrual_epoch_length();
+        let mut epoch = self.next_accrual_epoch.get(pool_id);
+        let end = current.min(epoch + U256::from(MAX_EPOCHS_PER_CLOSE));
+        let mut earned = U256::ZERO;
+        while epoch < end {
+            earned += self.epoch_premium.get(pool_id).get(epoch);
+            self.epoch_premium.setter(pool_id).insert(epoch, U256::ZERO);
+            epoch += U256::from(1);
+        }
+        self.next_accrual_epoch.insert(pool_id, epoch);
+        self.release_unearned_premium(pool_id, earned)?;
+        self.recognize_premium(pool_id, earned)?;
+        evm::log(PremiumEpochsClosed { pool_id, next_epoch: epoch, earned });
+        Ok(earned)
+    }
+
+    /// Returns the premium per second (WAD-scaled, so 1e18 is one wei a second) streaming
+    /// coverage of `notional` costs at the pool's current risk parameters
+    ///
+    /// This is the IL product's insurance fee for one 30-day term spread evenly over it, priced
+    /// against liquidity and volume read from the pool's data provider.
+    pub fn get_streaming_rate(&self, pool_id: FixedBytes<32>, notional: U256) -> Result<U256, Error> {
+        if notional == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let fee = self.calculate_insurance_fee_from_provider(pool_id, notional)?;
+        math::mul_div(fee, U256::from(WAD), U256::from(POLICY_TERM))
+    }
+
+    /// Opens a premium stream covering `notional` on a pool for the caller, returning its id
+    pub fn open_stream(&mut self, pool_id: FixedBytes<32>, notional: U256) -> Result<U256, Error> {
+        let rate = self.get_streaming_rate(pool_id, notional)?;
+        let payer = msg::sender();
+        let stream_id = self.next_stream_id.get() + U256::from(1);
+        self.next_stream_id.set(stream_id);
+        let mut stream = self.streams.setter(stream_id);
+        stream.payer.set(payer);
+        stream.pool_id.set(pool_id);
+        stream.notional.set(notional);
+        stream.rate.set(rate);
+        stream.last_checkpoint.set(block_time());
+        stream.open.set(true);
+        evm::log(StreamOpened { stream_id, pool_id, payer, notional, rate });
+        Ok(stream_id)
+    }
+
+    /// Accrues a stream's premium at its current rate up to now, then re-prices the rate from
+    /// here on; callable by anyone. Returns the total owed
+    pub fn checkpoint_stream(&mut self, stream_id: U256) -> Result<U256, Error> {
+        let owed = self.accrue_stream(stream_id)?;
+        let stream = self.streams.get(stream_id);
+        let rate = self.get_streaming_rate(stream.pool_id.get(), stream.notional.get())?;
+        self.streams.setter(stream_id).rate.set(rate);
+        evm::log(StreamCheckpointed { stream_id, owed, rate });
+        Ok(owed)
+    }
+
+    /// Closes the caller's stream, paying everything it accrued in ETH and refunding any excess
+    #[payable]
+    pub fn close_stream(&mut self, stream_id: U256) -> Result<U256, Error> {
+        if self.streams.get(stream_id).payer.get() != msg::sender() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        let owed = self.accrue_stream(stream_id)?;
+        Self::check_payment(owed)?;
+        let pool_id = self.streams.get(stream_id).pool_id.get();
+        let mut stream = self.streams.setter(stream_id);
+        stream.owed.set(U256::ZERO);
+        stream.open.set(false);
+        self.split_premium(pool_id, owed)?;
+        Self::refund_excess(owed)?;
+        evm::log(StreamClosed { stream_id, paid: owed });
+        Ok(owed)
+    }
+
+    /// Returns a stream's (payer, pool_id, notional, rate, last_checkpoint, owed, open) record,
+    /// with `owed` as of its last checkpoint
+    pub fn get_stream(&self, stream_id: U256) -> (Address, FixedBytes<32>, U256, U256, U256, U256, bool) {
+        let stream = self.streams.get(stream_id);
+        (
+            stream.payer.get(),
+            stream.pool_id.get(),
+            stream.notional.get(),
+            stream.rate.get(),
+            stream.last_checkpoint.get(),
+            stream.owed.get(),
+            stream.open.get(),
+        )
+    }
+
+    /// Returns a pool's (earned, unearned, next_epoch) premium accrual: the policy premium
+    /// recognized into reserves so far, what is still waiting on its epochs, and the next
+    /// epoch `close_epoch` will recognize
+    pub fn get_premium_accrual(&self, pool_id: FixedBytes<32>) -> (U256, U256, U256) {
+        (
+            self.earned_premium.get(pool_id),
+            self.unearned_premium.get(pool_id),
+            self.next_accrual_epoch.get(pool_id),
+        )
+    }
+
+    /// Collects an ETH premium for a pool, splitting it between reserves and treasury
+    #[payable]
+    pub fn collect_premium(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        self.check_fund_credit(pool_id, msg::value())?;
+        self.split_premium(pool_id, msg::value())
+    }
+
+    /// Withdraws accrued treasury premiums, callable only by the treasury
+    pub fn withdraw_treasury(&mut self, to: Address, amount: U256) -> Result<(), Error> {
+        let treasury = self.treasury.get();
+        if treasury == Address::ZERO || msg::sender() != treasury {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        if to == Address::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let available = self.treasury_balance.get();
+        if amount > available {
+            return Err(Error::InsufficientBalance(InsufficientBalance { available, requested: amount }));
+        }
+        // Effects before the external transfer
+        self.treasury_balance.set(available - amount);
+        transfer_eth(to, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(TreasuryWithdrawn { to, amount });
+        Ok(())
+    }
+
+    /// Registers a pool's token pair and fee tier
+    pub fn register_pool(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        token0: Address,
+        token1: Address,
+        fee_tier: U256,
+    ) -> Result<(), Error> {
+        self.register_pool_with_type(pool_id, token0, token1, fee_tier, POOL_VOLATILE)
+    }
+
+    /// Registers a pool as volatile (0) or stable (1); the type selects its insurance formula
+    ///
+    /// `pool_id` stays opaque: it need not be `compute_pool_id` of the tokens, so ids derived
+    /// some other way, such as Uniswap v4 `PoolKey` hashes, register as well.
+    pub fn register_pool_with_type(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        token0: Address,
+        token1: Address,
+        fee_tier: U256,
+        pool_type: u8,
+    ) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if token0 == Address::ZERO || token1 == Address::ZERO || pool_type > POOL_STABLE {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if self.pools.get(pool_id).registered.get() {
+            return Err(Error::PoolAlreadyRegistered(PoolAlreadyRegistered { pool_id }));
+        }
+        let mut pool = self.pools.setter(pool_id);
+        pool.token0.set(token0);
+        pool.token1.set(token1);
+        pool.fee_tier.set(fee_tier);
+        pool.registered.set(true);
+        pool.registered_at.set(block_time());
+        pool.pool_type.set(U8::from(pool_type));
+        self.list_pool(pool_id);
+        evm::log(PoolRegistered { pool_id, token0, token1, fee_tier });
+        evm::log(PoolTypeSet { pool_id, pool_type });
+        Ok(())
+    }
+
+    /// Returns a pool's type: volatile (0) or stable (1)
+    pub fn get_pool_type(&self, pool_id: FixedBytes<32>) -> u8 {
+        self.pools.get(pool_id).pool_type.get().to::<u8>()
+    }
+
+    /// Sets the decimals of a pool's token0 and token1 used by denominated quotes
+    pub fn set_token_decimals(&mut self, pool_id: FixedBytes<32>, decimals0: u8, decimals1: u8) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if decimals0 > MAX_DECIMALS || decimals1 > MAX_DECIMALS {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let mut pool = self.pools.setter(pool_id);
+        pool.decimals_set.set(true);
+        pool.decimals0.set(U8::from(decimals0));
+        pool.decimals1.set(U8::from(decimals1));
+        evm::log(TokenDecimalsSet { pool_id, decimals0, decimals1 });
+        Ok(())
+    }
+
+    /// Returns a pool's (token0, token1) decimals, 18 each until configured
+    pub fn get_token_decimals(&self, pool_id: FixedBytes<32>) -> (u8, u8) {
+        let pool = self.pools.get(pool_id);
+        if !pool.decimals_set.get() {
+            return (18, 18);
+        }
+        (pool.decimals0.get().to::<u8>(), pool.decimals1.get().to::<u8>())
+    }
+
+    /// Delegates a pool's core insurance fee to an external `IFeeFormula` (zero restores the
+    /// internal formula); owner-only, since the formula prices coverage paid from reserves
+    pub fn set_fee_formula(&mut self, pool_id: FixedBytes<32>, formula: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        self.fee_formula.insert(pool_id, formula);
+        evm::log(FeeFormulaSet { pool_id, formula });
+        Ok(())
+    }
+
+    /// Returns a pool's external fee formula, zero when it prices internally
+    pub fn get_fee_formula(&self, pool_id: FixedBytes<32>) -> Address {
+        self.fee_formula.get(pool_id)
+    }
+
+    /// Sets the band a pool's external formula fee is clamped to, in bps of the internal
+    /// formula's fee; the floor must be non-zero and the cap at most `MAX_FORMULA_BPS`
+    pub fn set_fee_formula_bounds(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        min_bps: U256,
+        max_bps: U256,
+    ) -> Result<(), Error> {
+        self.only_owner()?;
+        if min_bps == U256::ZERO || min_bps > max_bps || max_bps > U256::from(MAX_FORMULA_BPS) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.formula_min_bps.insert(pool_id, min_bps);
+        self.formula_max_bps.insert(pool_id, max_bps);
+        evm::log(FeeFormulaBoundsSet { pool_id, min_bps, max_bps });
+        Ok(())
+    }
+
+    /// Returns the (min_bps, max_bps) band of the internal fee a pool's external formula fee is
+    /// clamped to
+    pub fn get_fee_formula_bounds(&self, pool_id: FixedBytes<32>) -> (U256, U256) {
+        let min_bps = self.formula_min_bps.get(pool_id);
+        let max_bps = self.formula_max_bps.get(pool_id);
+        if min_bps == U256::ZERO {
+            (U256::from(DEFAULT_FORMULA_MIN_BPS), U256::from(DEFAULT_FORMULA_MAX_BPS))
+        } else {
+            (min_bps, max_bps)
+        }
+    }
+
+    /// Returns every configured setting of a pool in one call, in `PoolConfig` order
+    pub fn get_pool_config(&self, pool_id: FixedBytes<32>) -> PoolConfig {
+        let (fee_floor_bps, fee_cap_bps) = self.get_fee_formula_bounds(pool_id);
+        (
+            self.effective_base_fee(pool_id),
+            self.default_flash_fee_multiplier.get(pool_id),
+            self.max_exposure.get(pool_id),
+            self.get_pool_type(pool_id),
+            self.pool_operator.get(pool_id),
+            self.pool_hook.get(pool_id),
+            self.get_max_data_age(pool_id),
+            self.is_pool_active(pool_id),
+            fee_floor_bps,
+            fee_cap_bps,
+            self.surge_threshold.get(pool_id),
+            self.surge_multiplier.get(pool_id),
+            self.effective_il_weight(pool_id),
+        )
+    }
+
+    /// Stops a registered pool from quoting and selling coverage; existing policies can still
+    /// be claimed
+    pub fn deactivate_pool(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        self.set_pool_active(pool_id, false)
+    }
+
+    /// Restores quoting and coverage sales on a deactivated pool
+    pub fn reactivate_pool(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        self.set_pool_active(pool_id, true)
+    }
+
+    /// Returns whether a pool quotes; pools are active unless deactivated
+    pub fn is_pool_active(&self, pool_id: FixedBytes<32>) -> bool {
+        !self.pool_deactivated.get(pool_id)
+    }
+
+    /// Returns a pool's (token0, token1, fee_tier, registered, registered_at) metadata
+    pub fn get_pool_metadata(&self, pool_id: FixedBytes<32>) -> (Address, Address, U256, bool, U256) {
+        let pool = self.pools.get(pool_id);
+        (
+            pool.token0.get(),
+            pool.token1.get(),
+            pool.fee_tier.get(),
+            pool.registered.get(),
+            pool.registered_at.get(),
+        )
+    }
+
+    /// Returns the number of pools in the index
+    pub fn pool_count(&self) -> U256 {
+        U256::from(self.all_pools.len())
+    }
+
+    /// Returns the pool id at `index` in the index
+    pub fn pool_at(&self, index: U256) -> Result<FixedBytes<32>, Error> {
+        let index: usize = index.try_into().map_err(|_| Error::InvalidInput(InvalidInput{}))?;
+        self.all_pools.get(index).ok_or(Error::InvalidInput(InvalidInput{}))
+    }
+
+    /// Toggles whether quotes require the pool to be registered
+    pub fn set_require_registration(&mut self, required: bool) -> Result<(), Error> {
+        self.only_owner()?;
+        self.require_registration.set(required);
+        evm::log(RequireRegistrationSet { required });
+        Ok(())
+    }
+
+    /// Returns whether quotes require the pool to be registered
+    pub fn require_registration(&self) -> bool {
+        self.require_registration.get()
+    }
+
+    /// Assigns the operator allowed to manage a pool's parameters
+    ///
+    /// Global updaters lose IL write access to the pool once it has an operator; clearing the
+    /// operator back to zero returns the pool to the global updater set.
+    pub fn set_pool_operator(&mut self, pool_id: FixedBytes<32>, operator: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        self.pool_operator.insert(pool_id, operator);
+        evm::log(PoolOperatorSet { pool_id, operator });
+        Ok(())
+    }
+
+    /// Returns a pool's operator
+    pub fn get_pool_operator(&self, pool_id: FixedBytes<32>) -> Address {
+        self.pool_operator.get(pool_id)
+    }
+
+    /// Sets a pool's flash fee multiplier (WAD, zero means 1.0)
+    pub fn set_default_flash_fee_multiplier(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        multiplier: U256,
+    ) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if multiplier > U256::from(MAX_FLASH_FEE_MULTIPLIER) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.default_flash_fee_multiplier.insert(pool_id, multiplier);
+        evm::log(FlashFeeMultiplierSet { pool_id, multiplier });
+        Ok(())
+    }
+
+    /// Returns a pool's flash fee multiplier
+    pub fn get_default_flash_fee_multiplier(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.default_flash_fee_multiplier.get(pool_id)
+    }
+
+    /// Sets the largest single flash loan a pool prices (zero means unlimited)
+    pub fn set_max_flash_amount(&mut self, pool_id: FixedBytes<32>, cap: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        self.max_flash_amount.insert(pool_id, cap);
+        evm::log(MaxFlashAmountSet { pool_id, cap });
+        Ok(())
+    }
+
+    /// Returns the largest single flash loan a pool prices (zero means unlimited)
+    pub fn get_max_flash_amount(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.max_flash_amount.get(pool_id)
+    }
+
+    /// Returns the most a single flash loan can draw from a pool: the liquidity left after
+    /// `utilization_rate` and this block's earlier loans, bounded by the pool's cap
+    ///
+    /// The per-pool counterpart of ERC-3156 `maxFlashLoan`.
+    pub fn max_flash_loan(
+        &self,
+        pool_id: FixedBytes<32>,
+        total_liquidity: U256,
+        utilization_rate: U256,
+    ) -> Result<U256, Error> {
+        if utilization_rate > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let drawn = math::mul_div(total_liquidity, utilization_rate, U256::from(WAD))?
+            .saturating_add(self.get_flash_borrowed_this_block(pool_id));
+        let available = total_liquidity.saturating_sub(drawn);
+        let cap = self.max_flash_amount.get(pool_id);
+        if cap == U256::ZERO {
+            Ok(available)
+        } else {
+            Ok(available.min(cap))
+        }
+    }
+
+    /// Sets a pool's insurance base fee (WAD, zero restores the default)
+    pub fn set_base_fee(&mut self, pool_id: FixedBytes<32>, base_fee: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if base_fee > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.base_fee.insert(pool_id, base_fee);
+        evm::log(BaseFeeSet { pool_id, base_fee });
+        Ok(())
+    }
+
+    /// Returns the insurance base fee applied to a pool
+    pub fn get_base_fee(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.effective_base_fee(pool_id)
+    }
+
+    /// Sets the IL weight a pool's IL product is priced with (WAD, zero restores the default)
+    pub fn set_il_weight(&mut self, pool_id: FixedBytes<32>, il_weight: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if il_weight > U256::from(MAX_RISK_WEIGHT) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.il_weight.insert(pool_id, il_weight);
+        evm::log(IlWeightSet { pool_id, il_weight });
+        Ok(())
+    }
+
+    /// Returns the IL weight a pool's IL product is priced with
+    pub fn get_il_weight(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.effective_il_weight(pool_id)
+    }
+
+    /// Defines risk tier `tier` as a named bundle of pool parameters
+    ///
+    /// Each field is validated as its individual setter would; a zero surge multiplier leaves
+    /// surge disabled and a zero exposure cap leaves pools uncapped.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_risk_preset(
+        &mut self,
+        tier: u8,
+        name: FixedBytes<32>,
+        base_fee: U256,
+        il_weight: U256,
+        surge_threshold: U256,
+        surge_multiplier: U256,
+        flash_multiplier: U256,
+        max_exposure: U256,
+    ) -> Result<(), Error> {
+        self.only_owner()?;
+        if tier >= MAX_RISK_PRESETS
+            || base_fee > U256::from(WAD)
+            || il_weight > U256::from(MAX_RISK_WEIGHT)
+            || flash_multiplier > U256::from(MAX_FLASH_FEE_MULTIPLIER)
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        Self::check_surge_params(surge_threshold, surge_multiplier)?;
+        let mut preset = self.risk_presets.setter(U8::from(tier));
+        preset.name.set(name);
+        preset.defined.set(true);
+        preset.base_fee.set(base_fee);
+        preset.il_weight.set(il_weight);
+        preset.surge_threshold.set(surge_threshold);
+        preset.surge_multiplier.set(surge_multiplier);
+        preset.flash_multiplier.set(flash_multiplier);
+        preset.max_exposure.set(max_exposure);
+        evm::log(RiskPresetSet { tier, name });
+        Ok(())
+    }
+
+    /// Returns risk tier `tier` as (name, base_fee, il_weight, surge_threshold, surge_multiplier,
+    /// flash_multiplier, max_exposure), reverting if it is undefined
+    pub fn get_risk_preset(&self, tier: u8) -> Result<(FixedBytes<32>, U256, U256, U256, U256, U256, U256), Error> {
+        let preset = self.risk_presets.get(U8::from(tier));
+        if !preset.defined.get() {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        Ok((
+            preset.name.get(),
+            preset.base_fee.get(),
+            preset.il_weight.get(),
+            preset.surge_threshold.get(),
+            preset.surge_multiplier.get(),
+            preset.flash_multiplier.get(),
+            preset.max_exposure.get(),
+        ))
+    }
+
+    /// Copies a risk tier's parameters into a pool's configuration
+    ///
+    /// The pool must be registered and active. Fields can still be overridden one at a time through
+    /// their own setters afterwards.
+    pub fn apply_preset(&mut self, pool_id: FixedBytes<32>, tier: u8) -> Result<(), Error> {
+        self.only_owner()?;
+        if !self.pools.get(pool_id).registered.get() {
+            return Err(Error::PoolNotRegistered(PoolNotRegistered { pool_id }));
+        }
+        self.check_registration(pool_id)?;
+        let (name, base_fee, il_weight, threshold, multiplier, flash_multiplier, cap) = self.get_risk_preset(tier)?;
+        self.base_fee.insert(pool_id, base_fee);
+        self.il_weight.insert(pool_id, il_weight);
+        self.surge_threshold.insert(pool_id, threshold);
+        self.surge_multiplier.insert(pool_id, multiplier);
+        self.default_flash_fee_multiplier.insert(pool_id, flash_multiplier);
+        self.max_exposure.insert(pool_id, cap);
+        evm::log(BaseFeeSet { pool_id, base_fee });
+        evm::log(IlWeightSet { pool_id, il_weight });
+        evm::log(SurgeParamsSet { pool_id, threshold, multiplier });
+        evm::log(FlashFeeMultiplierSet { pool_id, multiplier: flash_multiplier });
+        evm::log(MaxExposureSet { pool_id, cap });
+        evm::log(RiskPresetApplied { pool_id, tier, name });
+        Ok(())
+    }
+
+    /// Configures a non-IL insurance product on a pool (base fee and weights in WAD)
+    ///
+    /// The IL product (id 0) is priced by the pool's base fee and cannot be reconfigured here.
+    pub fn set_product_config(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        enabled: bool,
+        base_fee: U256,
+        il_weight: U256,
+        size_weight: U256,
+    ) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if product_id == IL_PRODUCT
+            || base_fee == U256::ZERO
+            || base_fee > U256::from(WAD)
+            || il_weight > U256::from(MAX_RISK_WEIGHT)
+            || size_weight > U256::from(MAX_RISK_WEIGHT)
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let mut products = self.products.setter(pool_id);
+        let mut product = products.setter(U8::from(product_id));
+        product.enabled.set(enabled);
+        product.base_fee.set(base_fee);
+        product.il_weight.set(il_weight);
+        product.size_weight.set(size_weight);
+        evm::log(ProductConfigured { pool_id, product_id, enabled, base_fee, il_weight, size_weight });
+        Ok(())
+    }
+
+    /// Returns the (base_fee, il_weight, size_weight) a product is priced with on a pool
+    pub fn get_product_config(&self, pool_id: FixedBytes<32>, product_id: u8) -> Result<(U256, U256, U256), Error> {
+        self.product_params(pool_id, product_id)
+    }
+
+    /// Pays a trade's insurance premium in ETH, returning the fee charged
+    ///
+    /// Any value above the fee is refunded to the caller in the same call, so wallets can
+    /// safely attach a small buffer. Underpayment reverts with the required fee.
+    #[payable]
+    pub fn pay_premium(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+        self.settle_premium(pool_id, amount, fee)
+    }
+
+    /// Pays a trade's insurance premium like `pay_premium`, reverting with `FeeSlippage` if the
+    /// fee at execution exceeds `expected_fee * (10_000 + tolerance_bps) / 10_000`
+    ///
+    /// A fee at or below the expected fee always goes through.
+    #[payable]
+    pub fn pay_premium_with_tolerance(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        expected_fee: U256,
+        tolerance_bps: U256,
+    ) -> Result<U256, Error> {
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+        Self::check_fee_tolerance(fee, expected_fee, tolerance_bps)?;
+        self.settle_premium(pool_id, amount, fee)
+    }
+
+    /// Deposits ETH into a pool's insurance fund
+    #[payable]
+    pub fn deposit_to_fund(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        let amount = msg::value();
+        self.check_fund_credit(pool_id, amount)?;
+        self.credit_reserves(pool_id, amount)?;
+        evm::log(FundDeposited { pool_id, depositor: msg::sender(), amount });
+        Ok(())
+    }
+
+    /// Withdraws from a pool's insurance fund without breaching its minimum reserve
+    pub fn withdraw_from_fund(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        to: Address,
+        amount: U256,
+    ) -> Result<(), Error> {
+        self.only_owner()?;
+        if to == Address::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let reserves = self.pool_reserves.get(pool_id);
+        let minimum = self.min_reserve.get(pool_id);
+        match reserves.checked_sub(amount) {
+            Some(remaining) if remaining >= minimum => {}
+            _ => {
+                return Err(Error::MinimumReserveBreached(MinimumReserveBreached {
+                    reserves,
+                    minimum,
+                    requested: amount,
+                }))
+            }
+        }
+        self.debit_reserves(pool_id, amount)?;
+        transfer_eth(to, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(FundWithdrawn { pool_id, to, amount });
+        Ok(())
+    }
+
+    /// Sets the reserve floor owner withdrawals must leave in a pool's fund
+    pub fn set_min_reserve(&mut self, pool_id: FixedBytes<32>, minimum: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.min_reserve.insert(pool_id, minimum);
+        evm::log(MinReserveSet { pool_id, minimum });
+        Ok(())
+    }
+
+    /// Returns a pool's minimum reserve
+    pub fn get_min_reserve(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.min_reserve.get(pool_id)
+    }
+
+    /// Returns the sum of all pool reserves
+    pub fn get_total_reserves(&self) -> U256 {
+        self.total_reserves.get()
+    }
+
+    /// Quotes the premium for IL coverage of `coverage_fraction` of `notional` for `duration` seconds
+    #[allow(clippy::too_many_arguments)]
+    pub fn quote_coverage_premium(
+        &self,
+        pool_id: FixedBytes<32>,
+        notional: U256,
+        coverage_fraction: U256,
+        duration: U256,
+        deductible_wad: U256,
+        coinsurance_bps: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.quote_product_coverage_premium(
+            pool_id,
+            IL_PRODUCT,
+            notional,
+            coverage_fraction,
+            duration,
+            deductible_wad,
+            coinsurance_bps,
+            total_liquidity,
+            total_volume,
+        )
+    }
+
+    /// Quotes the premium for covering `coverage_fraction` (WAD) of a `notional` position under a
+    /// product for `duration` seconds
+    ///
+    /// The insurance fee on the full notional buys one 30-day term; other durations are pro-rated.
+    /// A deductible (WAD fraction of loss) discounts the premium to
+    /// `premium / (1 + 4 * deductible)`, so a 5% deductible prices at 1/1.2 of full cover.
+    /// Co-insurance then scales it by the covered share, `(10_000 - coinsurance_bps) / 10_000`,
+    /// and partial cover by `coverage_fraction`, each step rounding down. Risk multipliers are
+    /// always priced off the full position.
+    #[allow(clippy::too_many_arguments)]
+    pub fn quote_product_coverage_premium(
+        &self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        notional: U256,
+        coverage_fraction: U256,
+        duration: U256,
+        deductible_wad: U256,
+        coinsurance_bps: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.price_coverage_premium(
+            pool_id,
+            product_id,
+            msg::sender(),
+            notional,
+            coverage_fraction,
+            duration,
+            deductible_wad,
+            coinsurance_bps,
+            total_liquidity,
+            total_volume,
+        )
+    }
+
+    /// Sets the position size a pool's required premium APR is quoted for (zero restores the
+    /// default of one WAD unit)
+    pub fn set_apr_reference_size(&mut self, pool_id: FixedBytes<32>, size: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        self.apr_reference_size.insert(pool_id, size);
+        evm::log(AprReferenceSizeSet { pool_id, size });
+        Ok(())
+    }
+
+    /// Returns the position size a pool's required premium APR is quoted for
+    pub fn get_apr_reference_size(&self, pool_id: FixedBytes<32>) -> U256 {
+        let size = self.apr_reference_size.get(pool_id);
+        if size == U256::ZERO {
+            U256::from(DEFAULT_APR_REFERENCE_SIZE)
+        } else {
+            size
+        }
+    }
+
+    /// Returns the annualized premium (WAD rate) a pool demands for full IL cover of its
+    /// reference position size
+    ///
+    /// Simple annualization with no compounding: the one-year premium divided by the size, so
+    /// it matches a full-cover, no-deductible `quote_coverage_premium` for 365 days up to
+    /// rounding. Priced without any caller's loyalty discount, so every caller sees the same rate.
+    pub fn get_required_premium_apr(
+        &self,
+        pool_id: FixedBytes<32>,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        let size = self.get_apr_reference_size(pool_id);
+        let fee = self.quote_insurance_fee_for(pool_id, IL_PRODUCT, Address::ZERO, size, total_liquidity, total_volume)?;
+        let mut premium = math::mul_div(fee, U256::from(SECONDS_PER_YEAR), U256::from(POLICY_TERM))?;
+        if self.hedging_fee_mode.get(pool_id) {
+            let hedging_cost = self.estimate_hedging_cost(
+                size,
+                self.hedging_volatility.get(pool_id),
+                U256::from(SECONDS_PER_YEAR),
+            )?;
+            premium = premium.max(hedging_cost);
+        }
+        math::mul_div(premium, U256::from(WAD), size)
+    }
+
+    /// Closes a pool's open rebate epoch at its realized IL (WAD) and opens the next one,
+    /// returning the closed epoch
+    pub fn close_rebate_epoch(&mut self, pool_id: FixedBytes<32>, realized_il: U256) -> Result<U256, Error> {
+        self.only_pool_admin(pool_id)?;
+        if realized_il > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let epoch = self.rebate_epoch.get(pool_id);
+        let projected_il = self.projected_il(pool_id, epoch);
+        let mut epochs = self.rebate_epochs.setter(pool_id);
+        let mut record = epochs.setter(epoch);
+        record.realized_il.set(realized_il);
+        record.closed.set(true);
+        self.rebate_epoch.insert(pool_id, epoch + U256::from(1));
+        evm::log(RebateEpochClosed { pool_id, epoch, projected_il, realized_il });
+        Ok(epoch)
+    }
+
+    /// Returns a pool's open rebate epoch
+    pub fn current_rebate_epoch(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.rebate_epoch.get(pool_id)
+    }
+
+    /// Returns a rebate epoch's (premium_total, projected_il, realized_il, closed)
+    pub fn get_rebate_epoch(&self, pool_id: FixedBytes<32>, epoch: U256) -> (U256, U256, U256, bool) {
+        let epochs = self.rebate_epochs.get(pool_id);
+        let record = epochs.get(epoch);
+        (
+            record.premium_total.get(),
+            self.projected_il(pool_id, epoch),
+            record.realized_il.get(),
+            record.closed.get(),
+        )
+    }
+
+    /// Computes the rebate owed on `premium_paid` from a closed epoch whose realized IL came in
+    /// under the premium-weighted IL it was priced at
+    ///
+    /// The rebate is `premium_paid * (projected - realized) / projected`, capped at the
+    /// configured share of the premium; epochs realizing at or above projection rebate zero.
+    pub fn calculate_rebate(&self, pool_id: FixedBytes<32>, epoch: U256, premium_paid: U256) -> Result<U256, Error> {
+        let epochs = self.rebate_epochs.get(pool_id);
+        let record = epochs.get(epoch);
+        if !record.closed.get() {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let projected_il = self.projected_il(pool_id, epoch);
+        let realized_il = record.realized_il.get();
+        if realized_il >= projected_il {
+            return Ok(U256::ZERO);
+        }
+        let rebate = math::mul_div(premium_paid, projected_il - realized_il, projected_il)?;
+        let cap = math::mul_div(premium_paid, self.max_rebate_bps(), U256::from(BPS_DENOMINATOR))?;
+        Ok(rebate.min(cap))
+    }
+
+    /// Sets the largest share of a premium a rebate may return, in basis points (zero restores
+    /// the default)
+    pub fn set_max_rebate_bps(&mut self, bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.max_rebate_bps.set(bps);
+        evm::log(MaxRebateBpsSet { bps });
+        Ok(())
+    }
+
+    /// Returns the largest share of a premium a rebate may return, in basis points
+    pub fn max_rebate_bps(&self) -> U256 {
+        let bps = self.max_rebate_bps.get();
+        if bps == U256::ZERO {
+            U256::from(DEFAULT_MAX_REBATE_BPS)
+        } else {
+            bps
+        }
+    }
+
+    /// Allows or disallows a deductible (WAD fraction of loss) for new policies
+    pub fn set_allowed_deductible(&mut self, deductible_wad: U256, allowed: bool) -> Result<(), Error> {
+        self.only_owner()?;
+        if deductible_wad == U256::ZERO || deductible_wad >= U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.allowed_deductibles.insert(deductible_wad, allowed);
+        evm::log(DeductibleAllowed { deductible: deductible_wad, allowed });
+        Ok(())
+    }
+
+    /// Returns whether a deductible may be chosen at purchase; zero is always allowed
+    pub fn is_deductible_allowed(&self, deductible_wad: U256) -> bool {
+        deductible_wad == U256::ZERO || self.allowed_deductibles.get(deductible_wad)
+    }
+
+    /// Buys IL coverage on a pool, paying the premium in ETH and refunding any excess
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn buy_coverage(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        notional: U256,
+        coverage_fraction: U256,
+        duration: U256,
+        deductible_wad: U256,
+        coinsurance_bps: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.buy_product_coverage(
+            pool_id,
+            IL_PRODUCT,
+            notional,
+            coverage_fraction,
+            duration,
+            deductible_wad,
+            coinsurance_bps,
+            total_liquidity,
+            total_volume,
+        )
+    }
+
+    /// Buys coverage under a product on a pool, paying the premium in ETH and refunding any excess
+    ///
+    /// The policy covers `notional * coverage_fraction` (rounded down), which is also what
+    /// counts towards the pool's exposure.
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn buy_product_coverage(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        notional: U256,
+        coverage_fraction: U256,
+        duration: U256,
+        deductible_wad: U256,
+        coinsurance_bps: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.issue_coverage(
+            pool_id,
+            product_id,
+            notional,
+            coverage_fraction,
+            duration,
+            deductible_wad,
+            coinsurance_bps,
+            total_liquidity,
+            total_volume,
+            None,
+        )
+    }
+
+    /// Buys IL coverage like `buy_coverage`, reverting with `FeeSlippage` if the premium at
+    /// execution exceeds `expected_fee * (10_000 + tolerance_bps) / 10_000`
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn buy_coverage_with_tolerance(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        notional: U256,
+        coverage_fraction: U256,
+        duration: U256,
+        deductible_wad: U256,
+        coinsurance_bps: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        expected_fee: U256,
+        tolerance_bps: U256,
+    ) -> Result<U256, Error> {
+        self.issue_coverage(
+            pool_id,
+            IL_PRODUCT,
+            notional,
+            coverage_fraction,
+            duration,
+            deductible_wad,
+            coinsurance_bps,
+            total_liquidity,
+            total_volume,
+            Some((expected_fee, tolerance_bps)),
+        )
+    }
+
+    /// Computes what a policy pays out for a realized loss (WAD fraction of notional)
+    ///
+    /// The policy's deductible is taken off the loss first, then co-insurance keeps the
+    /// holder's share: `max(loss - deductible, 0) * notional * (10_000 - coinsurance_bps) / 10_000`.
+    pub fn calculate_claim_payout(&self, policy_id: U256, realized_loss: U256) -> Result<U256, Error> {
+        if realized_loss > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let policy = self.policies.get(policy_id);
+        let covered_loss = realized_loss.saturating_sub(policy.deductible.get());
+        let payout = math::mul_div(policy.notional.get(), covered_loss, U256::from(WAD))?;
+        math::mul_div(
+            payout,
+            U256::from(BPS_DENOMINATOR) - policy.coinsurance_bps.get(),
+            U256::from(BPS_DENOMINATOR),
+        )
+    }
+
+    /// Claims an active policy against the IL its pool has realized since the policy started,
+    /// paying the holder from reserves
+    ///
+    /// A policy can only be claimed once it has run for `CLAIM_WAITING_PERIOD` (1 day).
+    pub fn claim_policy(&mut self, policy_id: U256) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        let holder = policy.holder.get();
+        if holder != msg::sender() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        if !self.is_policy_live(policy_id) {
+            return Err(Error::PolicyNotActive(PolicyNotActive { policy_id }));
+        }
+        let claimable_at = policy.start.get() + U256::from(CLAIM_WAITING_PERIOD);
+        if block_time() < claimable_at {
+            return Err(Error::ClaimTooEarly(ClaimTooEarly { policy_id, claimable_at }));
+        }
+        let pool_id = policy.pool_id.get();
+        let notional = policy.notional.get();
+        let realized_il = self.effective_il(pool_id).saturating_sub(policy.il_at_start.get());
+        let payout = self.calculate_claim_payout(policy_id, realized_il)?;
+        if payout == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+
+        self.set_policy_status(policy_id, POLICY_CLAIMED);
+        self.release_coverage(pool_id, notional);
+        self.debit_reserves(pool_id, payout)?;
+        transfer_eth(holder, payout).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(PolicyClaimed { policy_id, holder, payout });
+        Ok(payout)
+    }
+
+    /// Marks a lapsed policy expired and releases its notional, callable by anyone
+    ///
+    /// Expiry is lazy: a lapsed policy keeps counting toward open interest and the systemic
+    /// multiplier until this is called, so the caller receives `expiry_incentive_bps` of the
+    /// policy's premium from the pool's reserves (capped at what the reserves hold).
+    /// Auto-renewing policies are left to `process_renewal` for `RENEWAL_GRACE_PERIOD` past
+    /// expiry; after that they lapse here with their renewal balance kept withdrawable, so a
+    /// renewal that keeps failing to price cannot hold their notional open.
+    pub fn expire_policy(&mut self, policy_id: U256) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        let active = policy.status.get() == U8::from(POLICY_ACTIVE);
+        let auto_renew = policy.auto_renew.get();
+        let expires_at = if auto_renew {
+            policy.expiry.get() + U256::from(RENEWAL_GRACE_PERIOD)
+        } else {
+            policy.expiry.get()
+        };
+        if !active || block_time() <= expires_at {
+            return Err(Error::PolicyNotActive(PolicyNotActive { policy_id }));
+        }
+        let pool_id = policy.pool_id.get();
+        let notional = policy.notional.get();
+        let balance = policy.renewal_balance.get();
+        let incentive = math::mul_div(
+            policy.premium.get(),
+            self.expiry_incentive_bps.get(),
+            U256::from(BPS_DENOMINATOR),
+        )?
+        .min(self.pool_reserves.get(pool_id));
+        if auto_renew {
+            self.lapse_policy(policy_id, U256::ZERO, balance);
+        } else {
+            self.set_policy_status(policy_id, POLICY_EXPIRED);
+            self.release_coverage(pool_id, notional);
+            evm::log(PolicyExpired { policy_id });
+        }
+        if incentive > U256::ZERO {
+            self.debit_reserves(pool_id, incentive)?;
+            transfer_eth(msg::sender(), incentive).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+            evm::log(ExpiryIncentivePaid { policy_id, keeper: msg::sender(), incentive });
+        }
+        Ok(incentive)
+    }
+
+    /// Extends a live policy by `duration` at current pricing, paid in ETH
+    #[payable]
+    pub fn renew_policy(
+        &mut self,
+        policy_id: U256,
+        duration: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.extend_policy(policy_id, duration, total_liquidity, total_volume, None)
+    }
+
+    /// Renews a policy like `renew_policy`, reverting with `FeeSlippage` if the premium at
+    /// execution exceeds `expected_fee * (10_000 + tolerance_bps) / 10_000`
+    #[payable]
+    pub fn renew_policy_with_tolerance(
+        &mut self,
+        policy_id: U256,
+        duration: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        expected_fee: U256,
+        tolerance_bps: U256,
+    ) -> Result<U256, Error> {
+        self.extend_policy(policy_id, duration, total_liquidity, total_volume, Some((expected_fee, tolerance_bps)))
+    }
+
+    /// Turns automatic renewal of the caller's policy on or off
+    pub fn set_auto_renew(&mut self, policy_id: U256, enabled: bool) -> Result<(), Error> {
+        if self.policies.get(policy_id).holder.get() != msg::sender() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        self.policies.setter(policy_id).auto_renew.set(enabled);
+        evm::log(AutoRenewSet { policy_id, enabled });
+        Ok(())
+    }
+
+    /// Adds the attached ETH to a policy's prepaid renewal balance, returning the new balance
+    #[payable]
+    pub fn fund_renewal(&mut self, policy_id: U256) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        if policy.holder.get() == Address::ZERO {
+            return Err(Error::PolicyNotActive(PolicyNotActive { policy_id }));
+        }
+        let amount = msg::value();
+        let balance = policy.renewal_balance.get()
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.policies.setter(policy_id).renewal_balance.set(balance);
+        evm::log(RenewalFunded { policy_id, amount, balance });
+        Ok(balance)
+    }
+
+    /// Withdraws a policy's whole renewal balance to its holder
+    pub fn withdraw_renewal_balance(&mut self, policy_id: U256) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        let holder = policy.holder.get();
+        if holder != msg::sender() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        let amount = policy.renewal_balance.get();
+        if amount == U256::ZERO {
+            return Err(Error::InsufficientBalance(InsufficientBalance { available: amount, requested: amount }));
+        }
+        self.policies.setter(policy_id).renewal_balance.set(U256::ZERO);
+        transfer_eth(holder, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(RenewalBalanceWithdrawn { policy_id, holder, amount });
+        Ok(amount)
+    }
+
+    /// Returns a policy's (auto_renew, renewal_balance, term)
+    pub fn get_renewal_status(&self, policy_id: U256) -> (bool, U256, U256) {
+        let policy = self.policies.get(policy_id);
+        (policy.auto_renew.get(), policy.renewal_balance.get(), policy.term.get())
+    }
+
+    /// Renews an expired auto-renewing policy for another term from now at current pricing,
+    /// paid from its renewal balance; callable by anyone. Returns the premium charged
+    ///
+    /// Pricing reads liquidity and volume from the pool's data provider and applies the
+    /// holder's loyalty discount, never the caller's. When the pool has been deactivated or the
+    /// balance falls short, the policy lapses instead and zero is returned.
+    pub fn process_renewal(&mut self, policy_id: U256) -> Result<U256, Error> {
+        self.process_renewal_with(policy_id, |calculator, pool_id| calculator.provider_state(pool_id))
+    }
+
+    /// Moves a live policy to a new holder
+    pub fn transfer_policy(&mut self, policy_id: U256, new_holder: Address) -> Result<(), Error> {
+        let old_holder = self.policies.get(policy_id).holder.get();
+        if old_holder != msg::sender() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        if new_holder == Address::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if !self.is_policy_live(policy_id) {
+            return Err(Error::PolicyNotActive(PolicyNotActive { policy_id }));
+        }
+        self.remove_holder_policy(old_holder, policy_id);
+        self.add_holder_policy(new_holder, policy_id);
+        self.policies.setter(policy_id).holder.set(new_holder);
+        evm::log(PolicyTransferred { policy_id, old_holder, new_holder });
+        Ok(())
+    }
+
+    /// Returns the ids of every policy an address holds
+    pub fn get_holder_policies(&self, holder: Address) -> Vec<U256> {
+        let policies = self.holder_policies.get(holder);
+        (0..policies.len()).filter_map(|i| policies.get(i)).collect()
+    }
+
+    /// Cancels an active policy, crediting the unused premium (less the penalty) for withdrawal
+    ///
+    /// The refund is `premium * remaining / duration` minus `cancellation_penalty_bps` of that
+    /// amount, and is zero in the final 10% of the policy's life. It is drawn from the policy's
+    /// unearned premium only, and whatever of that it leaves is recognized into reserves.
+    pub fn cancel_policy(&mut self, policy_id: U256) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        let holder = policy.holder.get();
+        if holder != msg::sender() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        if !self.is_policy_live(policy_id) {
+            return Err(Error::PolicyNotActive(PolicyNotActive { policy_id }));
+        }
+        let pool_id = policy.pool_id.get();
+        let notional = policy.notional.get();
+        let refund = self.calculate_cancellation_refund(policy_id)?;
+
+        self.set_policy_status(policy_id, POLICY_CANCELLED);
+        self.release_coverage(pool_id, notional);
+        let unearned = self.unwind_deferred_premium(policy_id, pool_id)?;
+        self.recognize_premium(pool_id, unearned - refund)?;
+        if refund > U256::ZERO {
+            let balance = self.refund_balance.get(holder)
+                .checked_add(refund)
+                .ok_or(Error::CalculationError(CalculationError{}))?;
+            self.refund_balance.insert(holder, balance);
+        }
+        evm::log(PolicyCancelled { policy_id, holder, refund });
+        Ok(refund)
+    }
+
+    /// Computes what cancelling a policy now would refund, never more than its unearned premium
+    pub fn calculate_cancellation_refund(&self, policy_id: U256) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        let start = policy.start.get();
+        let expiry = policy.expiry.get();
+        let now = block_time();
+        if expiry <= now {
+            return Ok(U256::ZERO);
+        }
+        let duration = expiry - start;
+        let remaining = expiry - now.max(start);
+        // remaining / duration < 10% means the policy is in its final stretch
+        if remaining * U256::from(BPS_DENOMINATOR) < duration * U256::from(CANCELLATION_CUTOFF_BPS) {
+            return Ok(U256::ZERO);
+        }
+        let refundable = math::mul_div(policy.premium.get(), remaining, duration)?;
+        let penalty = math::mul_div(refundable, self.cancellation_penalty_bps.get(), U256::from(BPS_DENOMINATOR))?;
+        Ok((refundable - penalty).min(self.policy_unearned_premium(policy_id)))
+    }
+
+    /// Sets the share of each cancellation refund withheld as a penalty, in basis points
+    pub fn set_cancellation_penalty_bps(&mut self, bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.cancellation_penalty_bps.set(bps);
+        evm::log(CancellationPenaltySet { bps });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the cancellation penalty in basis points
+    pub fn cancellation_penalty_bps(&self) -> U256 {
+        self.cancellation_penalty_bps.get()
+    }
+
+    /// Returns the cancellation refunds an address can withdraw
+    pub fn get_refund_balance(&self, holder: Address) -> U256 {
+        self.refund_balance.get(holder)
+    }
+
+    /// Withdraws the caller's accumulated cancellation refunds
+    pub fn withdraw_refund(&mut self) -> Result<U256, Error> {
+        let holder = msg::sender();
+        let amount = self.refund_balance.get(holder);
+        if amount == U256::ZERO {
+            return Err(Error::InsufficientBalance(InsufficientBalance { available: amount, requested: amount }));
+        }
+        self.refund_balance.insert(holder, U256::ZERO);
+        transfer_eth(holder, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(RefundWithdrawn { holder, amount });
+        Ok(amount)
+    }
+
+    /// Returns a policy's (holder, pool_id, notional, premium, start, expiry, status, deductible,
+    /// coinsurance_bps) record
+    pub fn get_policy(&self, policy_id: U256) -> (Address, FixedBytes<32>, U256, U256, U256, U256, u8, U256, U256) {
+        let policy = self.policies.get(policy_id);
+        (
+            policy.holder.get(),
+            policy.pool_id.get(),
+            policy.notional.get(),
+            policy.premium.get(),
+            policy.start.get(),
+            policy.expiry.get(),
+            policy.status.get().to::<u8>(),
+            policy.deductible.get(),
+            policy.coinsurance_bps.get(),
+        )
+    }
+
+    /// Returns the insurance product a policy was bought under
+    pub fn get_policy_product(&self, policy_id: U256) -> u8 {
+        self.policies.get(policy_id).product_id.get().to::<u8>()
+    }
+
+    /// Returns the share of its position a policy covers (WAD)
+    pub fn get_policy_coverage_fraction(&self, policy_id: U256) -> U256 {
+        self.policies.get(policy_id).coverage_fraction.get()
+    }
+
+    /// Returns the notional of a pool's active policies
+    pub fn get_outstanding_coverage(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.outstanding_coverage.get(pool_id)
+    }
+
+    /// Sets the WAD weight `w` of the open-interest multiplier
+    /// `1 + w * min(outstanding_coverage / total_liquidity, 1)` (zero disables it)
+    pub fn set_open_interest_weight(&mut self, weight: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if weight > U256::from(MAX_RISK_WEIGHT) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.open_interest_weight.set(weight);
+        evm::log(OpenInterestWeightSet { weight });
+        Ok(())
+    }
+
+    /// Returns the WAD weight of the open-interest multiplier
+    pub fn open_interest_weight(&self) -> U256 {
+        self.open_interest_weight.get()
+    }
+
+    /// Returns the notional of active policies across every pool
+    pub fn get_total_outstanding_coverage(&self) -> U256 {
+        self.total_outstanding_coverage.get()
+    }
+
+    /// Configures the systemic multiplier on every insurance quote: 1.0 while total open
+    /// coverage is at or below `soft_threshold`, rising linearly to `max_multiplier` (WAD) at
+    /// `hard_threshold` and flat beyond; a zero `hard_threshold` disables it
+    pub fn set_systemic_risk_params(
+        &mut self,
+        soft_threshold: U256,
+        hard_threshold: U256,
+        max_multiplier: U256,
+    ) -> Result<(), Error> {
+        self.only_owner()?;
+        if hard_threshold != U256::ZERO
+            && (soft_threshold >= hard_threshold
+                || max_multiplier < U256::from(WAD)
+                || max_multiplier > U256::from(MAX_SURGE_MULTIPLIER))
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.systemic_soft_threshold.set(soft_threshold);
+        self.systemic_hard_threshold.set(hard_threshold);
+        self.systemic_max_multiplier.set(max_multiplier);
+        evm::log(SystemicRiskParamsSet { soft_threshold, hard_threshold, max_multiplier });
+        Ok(())
+    }
+
+    /// Returns the (soft_threshold, hard_threshold, max_multiplier) systemic risk parameters
+    pub fn get_systemic_risk_params(&self) -> (U256, U256, U256) {
+        (
+            self.systemic_soft_threshold.get(),
+            self.systemic_hard_threshold.get(),
+            self.systemic_max_multiplier.get(),
+        )
+    }
+
+    /// Returns the systemic multiplier (WAD) total open coverage currently puts on every quote;
+    /// lapsed policies count until `expire_policy` releases them
+    pub fn get_systemic_multiplier(&self) -> Result<U256, Error> {
+        let hard = self.systemic_hard_threshold.get();
+        let soft = self.systemic_soft_threshold.get();
+        let total = self.total_outstanding_coverage.get();
+        if hard == U256::ZERO || total <= soft {
+            return Ok(U256::from(WAD));
+        }
+        let max_multiplier = self.systemic_max_multiplier.get();
+        if total >= hard {
+            return Ok(max_multiplier);
+        }
+        let ramp = math::mul_div(max_multiplier - U256::from(WAD), total - soft, hard - soft)?;
+        Ok(U256::from(WAD) + ramp)
+    }
+
+    /// Returns a pool's open coverage as a share of `total_liquidity` (WAD, capped at 1.0)
+    ///
+    /// Lapsed policies count until `expire_policy` releases them.
+    pub fn get_open_interest_ratio(&self, pool_id: FixedBytes<32>, total_liquidity: U256) -> Result<U256, Error> {
+        let open_interest = self.outstanding_coverage.get(pool_id);
+        if open_interest == U256::ZERO {
+            return Ok(U256::ZERO);
+        }
+        if total_liquidity == U256::ZERO {
+            return Ok(U256::from(WAD));
+        }
+        Ok(math::mul_div(open_interest, U256::from(WAD), total_liquidity)?.min(U256::from(WAD)))
+    }
+
+    /// Caps the outstanding coverage a pool may carry (zero removes the cap)
+    pub fn set_max_exposure(&mut self, pool_id: FixedBytes<32>, cap: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.max_exposure.insert(pool_id, cap);
+        evm::log(MaxExposureSet { pool_id, cap });
+        Ok(())
+    }
+
+    /// Returns a pool's exposure cap (zero if uncapped)
+    pub fn get_max_exposure(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.max_exposure.get(pool_id)
+    }
+
+    /// Returns the notional still available for new coverage on a pool
+    ///
+    /// Uncapped pools report `U256::MAX`; a cap lowered below current exposure reports zero.
+    pub fn get_remaining_capacity(&self, pool_id: FixedBytes<32>) -> U256 {
+        let cap = self.max_exposure.get(pool_id);
+        if cap == U256::ZERO {
+            return U256::MAX;
+        }
+        cap.saturating_sub(self.outstanding_coverage.get(pool_id))
+    }
+
+    /// Sets the reserves required per unit of outstanding coverage (WAD, zero restores 1.0)
+    pub fn set_target_reserve_ratio(&mut self, ratio: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.target_reserve_ratio.set(ratio);
+        evm::log(TargetReserveRatioSet { ratio });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the reserves required per unit of outstanding coverage
+    pub fn target_reserve_ratio(&self) -> U256 {
+        let ratio = self.target_reserve_ratio.get();
+        if ratio == U256::ZERO {
+            U256::from(WAD)
+        } else {
+            ratio
+        }
+    }
+
+    /// Sets the share of each skim paid to the caller, in basis points
+    pub fn set_skim_incentive_bps(&mut self, bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if bps > U256::from(MAX_SKIM_INCENTIVE_BPS) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.skim_incentive_bps.set(bps);
+        evm::log(SkimIncentiveSet { bps });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the share of each skim paid to the caller, in basis points
+    pub fn skim_incentive_bps(&self) -> U256 {
+        self.skim_incentive_bps.get()
+    }
+
+    /// Sets the share of a lapsed policy's premium paid to whoever expires it, in basis points
+    pub fn set_expiry_incentive_bps(&mut self, bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if bps > U256::from(MAX_EXPIRY_INCENTIVE_BPS) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.expiry_incentive_bps.set(bps);
+        evm::log(ExpiryIncentiveSet { bps });
+        Ok(())
+    }
+
+    /// Returns the share of a lapsed policy's premium paid to whoever expires it, in basis points
+    pub fn expiry_incentive_bps(&self) -> U256 {
+        self.expiry_incentive_bps.get()
+    }
+
+    /// Sweeps reserves above the pool's coverage requirement to the treasury, callable by anyone
+    ///
+    /// The requirement is `outstanding_coverage * target_reserve_ratio`, never below the pool's
+    /// minimum reserve. The caller receives `skim_incentive_bps` of the excess (rounded down).
+    pub fn skim_excess(&mut self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        let reserves = self.pool_reserves.get(pool_id);
+        let required = self.outstanding_coverage.get(pool_id)
+            .checked_mul(self.target_reserve_ratio())
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .checked_div(U256::from(WAD))
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .max(self.min_reserve.get(pool_id));
+        if reserves <= required {
+            return Err(Error::NoExcessReserves(NoExcessReserves { reserves, required }));
+        }
+        let excess = reserves - required;
+        let incentive = excess
+            .checked_mul(self.skim_incentive_bps.get())
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .checked_div(U256::from(BPS_DENOMINATOR))
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+
+        self.debit_reserves(pool_id, excess)?;
+        let treasury_balance = self.treasury_balance.get()
+            .checked_add(excess - incentive)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.treasury_balance.set(treasury_balance);
+        if incentive > U256::ZERO {
+            transfer_eth(msg::sender(), incentive).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        }
+        evm::log(ReservesSkimmed { pool_id, caller: msg::sender(), excess, incentive });
+        Ok(excess)
+    }
+
+    /// Estimates the cost of hedging a position's IL with an at-the-money straddle
+    ///
+    /// Uses the `0.4 * sigma * sqrt(T) * notional` approximation with sigma annualized (WAD)
+    /// and T in years. Zero volatility or duration costs nothing.
+    pub fn estimate_hedging_cost(
+        &self,
+        notional: U256,
+        volatility_wad: U256,
+        duration_seconds: U256,
+    ) -> Result<U256, Error> {
+        if volatility_wad == U256::ZERO || duration_seconds == U256::ZERO {
+            return Ok(U256::ZERO);
+        }
+        let years = math::mul_div(duration_seconds, U256::from(WAD), U256::from(SECONDS_PER_YEAR))?;
+        let sqrt_years = math::wad_sqrt(years)?;
+        let factor = math::mul_div(U256::from(STRADDLE_FACTOR), volatility_wad, U256::from(WAD))?;
+        let factor = math::mul_div(factor, sqrt_years, U256::from(WAD))?;
+        math::mul_div(notional, factor, U256::from(WAD))
+    }
+
+    /// Computes the IL (WAD) of a concentrated-liquidity position in `[lower_price, upper_price]`
+    /// opened at `entry_price`, as the price moves to `current_price`
+    ///
+    /// Token amounts follow the range formula: `x = L * (1/sqrt(P) - 1/sqrt(upper))` and
+    /// `y = L * (sqrt(P) - sqrt(lower))` with P clamped into the range, so the position is all
+    /// token0 below it and all token1 above it. IL is `1 - V(current) / H(current)`, where H
+    /// values the entry amounts at the current price; L cancels out. Prices are WAD.
+    pub fn compute_range_il(
+        &self,
+        entry_price: U256,
+        current_price: U256,
+        lower_price: U256,
+        upper_price: U256,
+    ) -> Result<U256, Error> {
+        if entry_price == U256::ZERO
+            || current_price == U256::ZERO
+            || lower_price == U256::ZERO
+            || lower_price >= upper_price
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let (entry_x, entry_y) = Self::range_amounts(entry_price, lower_price, upper_price)?;
+        let (current_x, current_y) = Self::range_amounts(current_price, lower_price, upper_price)?;
+        let held = math::mul_div(entry_x, current_price, U256::from(WAD))?
+            .checked_add(entry_y)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let position = math::mul_div(current_x, current_price, U256::from(WAD))?
+            .checked_add(current_y)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        // A non-degenerate range always holds some of one token, so the hold value is positive
+        let retained = math::mul_div(position, U256::from(WAD), held)?;
+        Ok(U256::from(WAD).saturating_sub(retained))
+    }
+
+    /// Enables or disables the hedging-cost premium floor for a pool
+    ///
+    /// `volatility` is an annualized WAD fraction, at most 500%.
+    pub fn set_hedging_fee_mode(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        enabled: bool,
+        volatility: U256,
+    ) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if volatility > U256::from(MAX_HEDGING_VOLATILITY) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.hedging_fee_mode.insert(pool_id, enabled);
+        self.hedging_volatility.insert(pool_id, volatility);
+        evm::log(HedgingFeeModeSet { pool_id, enabled, volatility });
+        Ok(())
+    }
+
+    /// Returns a pool's (enabled, volatility) hedging fee mode
+    pub fn get_hedging_fee_mode(&self, pool_id: FixedBytes<32>) -> (bool, U256) {
+        (self.hedging_fee_mode.get(pool_id), self.hedging_volatility.get(pool_id))
+    }
+
+    /// Grants or revokes IL updater rights on pools that have no operator assigned
+    pub fn set_updater(&mut self, updater: Address, authorized: bool) -> Result<(), Error> {
+        self.only_owner()?;
+        self.authorized_updaters.insert(updater, authorized);
+        evm::log(UpdaterSet { updater, authorized });
+        Ok(())
+    }
+
+    /// Returns whether an address may push IL data
+    pub fn is_updater(&self, updater: Address) -> bool {
+        self.authorized_updaters.get(updater)
+    }
+
+    /// Stores a pool's historical IL (WAD fraction) and clears any unrated flag
+    ///
+    /// `sequence` must exceed the pool's last accepted sequence number.
+    pub fn update_historical_il(&mut self, pool_id: FixedBytes<32>, il: U256, sequence: U256) -> Result<(), Error> {
+        self.only_updater(pool_id)?;
+        if il > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.guarded_write(pool_id, il, sequence, msg::sender())
+    }
+
+    /// Like `update_historical_il`, but a deviation guard trip is counted against the caller
+    /// and reported as `false` instead of reverting, so it stays on record
+    pub fn try_update_historical_il(&mut self, pool_id: FixedBytes<32>, il: U256, sequence: U256) -> Result<bool, Error> {
+        self.only_updater(pool_id)?;
+        if il > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let updater = msg::sender();
+        match self.guarded_write(pool_id, il, sequence, updater) {
+            Ok(()) => Ok(true),
+            Err(Error::DeviationTooLarge(DeviationTooLarge { old_value, proposed })) => {
+                let guarded = self.updater_stats.get(updater).guarded.get() + U256::from(1);
+                self.updater_stats.setter(updater).guarded.set(guarded);
+                evm::log(IlUpdateGuarded { pool_id, updater, old_value, proposed });
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Sets the bond authorized updaters must hold to write IL (zero disables the requirement)
+    /// and how long it stays locked after their last write (zero restores the default)
+    pub fn set_updater_bond_params(&mut self, min_bond: U256, cooldown: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.min_updater_bond.set(min_bond);
+        self.updater_bond_cooldown.set(cooldown);
+        evm::log(UpdaterBondParamsSet { min_bond, cooldown });
+        Ok(())
+    }
+
+    /// Returns the (min_bond, cooldown) updater bond parameters
+    pub fn get_updater_bond_params(&self) -> (U256, U256) {
+        (self.min_updater_bond.get(), self.updater_bond_cooldown())
+    }
+
+    /// Returns how long an updater's bond stays locked after its last IL write, in seconds
+    pub fn updater_bond_cooldown(&self) -> U256 {
+        let cooldown = self.updater_bond_cooldown.get();
+        if cooldown == U256::ZERO {
+            U256::from(DEFAULT_BOND_COOLDOWN)
+        } else {
+            cooldown
+        }
+    }
+
+    /// Adds the attached ETH to the caller's updater bond, returning the new bond
+    #[payable]
+    pub fn post_updater_bond(&mut self) -> Result<U256, Error> {
+        let updater = msg::sender();
+        let amount = msg::value();
+        if amount == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let bond = self.updater_bond.get(updater)
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.updater_bond.insert(updater, bond);
+        evm::log(UpdaterBondPosted { updater, amount, bond });
+        Ok(bond)
+    }
+
+    /// Withdraws the caller's whole bond once the cooldown since its last IL write has passed
+    /// and none of its staged IL is still inside the dispute window
+    pub fn withdraw_updater_bond(&mut self) -> Result<U256, Error> {
+        let updater = msg::sender();
+        let amount = self.updater_bond.get(updater);
+        if amount == U256::ZERO {
+            return Err(Error::InsufficientBalance(InsufficientBalance { available: amount, requested: amount }));
+        }
+        let last_update = self.updater_stats.get(updater).last_update.get();
+        let unlocks_at = if last_update == U256::ZERO {
+            U256::ZERO
+        } else {
+            last_update.saturating_add(self.updater_bond_cooldown())
+        };
+        let unlocks_at = unlocks_at.max(self.updater_pending_until.get(updater));
+        if block_time() < unlocks_at {
+            return Err(Error::BondLocked(BondLocked { unlocks_at }));
+        }
+        self.updater_bond.insert(updater, U256::ZERO);
+        transfer_eth(updater, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(UpdaterBondWithdrawn { updater, amount });
+        Ok(amount)
+    }
+
+    /// Seizes `amount` of an updater's bond for provable misbehavior, paying it to `recipient`
+    pub fn slash_updater(&mut self, updater: Address, amount: U256, recipient: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        let available = self.updater_bond.get(updater);
+        if amount == U256::ZERO || amount > available {
+            return Err(Error::InsufficientBalance(InsufficientBalance { available, requested: amount }));
+        }
+        if recipient == Address::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.updater_bond.insert(updater, available - amount);
+        transfer_eth(recipient, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(UpdaterSlashed { updater, recipient, amount });
+        Ok(())
+    }
+
+    /// Returns an updater's bonded ETH
+    pub fn get_updater_bond(&self, updater: Address) -> U256 {
+        self.updater_bond.get(updater)
+    }
+
+    /// Returns an updater's (accepted, guarded, last_update, pools_touched) statistics
+    ///
+    /// Reverted calls leave nothing on chain, so guard trips are only counted through
+    /// `try_update_historical_il`.
+    pub fn get_updater_stats(&self, updater: Address) -> (U256, U256, U256, U256) {
+        let stats = self.updater_stats.get(updater);
+        (stats.accepted.get(), stats.guarded.get(), stats.last_update.get(), stats.pools_touched.get())
+    }
+
+    /// Stores a pool's historical IL without the deviation guard, callable only by the owner
+    ///
+    /// Takes effect at once and discards any update still inside its dispute window.
+    pub fn force_update_historical_il(&mut self, pool_id: FixedBytes<32>, il: U256, sequence: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if il > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.promote_pending(pool_id);
+        self.pending_il_effective_at.insert(pool_id, U256::ZERO);
+        self.write_historical_il(pool_id, il, sequence, msg::sender())
+    }
+
+    /// Sets how long non-owner IL updates wait before taking effect, in seconds (zero applies
+    /// them at once)
+    pub fn set_il_dispute_window(&mut self, window: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.il_dispute_window.set(window);
+        evm::log(IlDisputeWindowSet { window });
+        Ok(())
+    }
+
+    /// Returns the IL dispute window in seconds
+    pub fn il_dispute_window(&self) -> U256 {
+        self.il_dispute_window.get()
+    }
+
+    /// Returns a pool's (il, effective_at, updater) update waiting out its dispute window;
+    /// zero `effective_at` means none is pending
+    pub fn get_pending_il(&self, pool_id: FixedBytes<32>) -> (U256, U256, Address) {
+        (
+            self.pending_il.get(pool_id),
+            self.pending_il_effective_at.get(pool_id),
+            self.pending_il_updater.get(pool_id),
+        )
+    }
+
+    /// Discards a pool's pending IL update before its window ends, callable by the owner or
+    /// the emergency admin
+    pub fn reject_pending_il(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        let guardian = msg::sender();
+        let admin = self.emergency_admin.get();
+        if guardian != self.owner.get() && (admin == Address::ZERO || guardian != admin) {
+            return Err(Error::Unauthorized(Unauthorized { caller: guardian }));
+        }
+        let effective_at = self.pending_il_effective_at.get(pool_id);
+        if effective_at == U256::ZERO || block_time() >= effective_at {
+            return Err(Error::NoPendingIl(NoPendingIl { pool_id }));
+        }
+        let il = self.pending_il.get(pool_id);
+        self.pending_il_effective_at.insert(pool_id, U256::ZERO);
+        evm::log(PendingIlRejected { pool_id, il, guardian });
+        Ok(())
+    }
+
+    /// Writes a pool's pending IL into storage once its window has passed, callable by anyone;
+    /// returns whether anything was promoted. Reads already see a matured value without this
+    pub fn promote_pending_il(&mut self, pool_id: FixedBytes<32>) -> bool {
+        self.promote_pending(pool_id)
+    }
+
+    /// Sets the IL source slots per pool, the fresh sources a median needs, and how long an
+    /// answer stays fresh in seconds (zero restores each default)
+    pub fn set_il_source_params(&mut self, max_sources: U256, quorum: U256, heartbeat: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        let effective_max = if max_sources == U256::ZERO { U256::from(DEFAULT_MAX_IL_SOURCES) } else { max_sources };
+        let effective_quorum = if quorum == U256::ZERO { U256::from(DEFAULT_IL_SOURCE_QUORUM) } else { quorum };
+        if effective_max > U256::from(MAX_IL_SOURCES) || effective_quorum > effective_max {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.max_il_sources.set(max_sources);
+        self.il_source_quorum.set(quorum);
+        self.il_source_heartbeat.set(heartbeat);
+        evm::log(IlSourceParamsSet { max_sources, quorum, heartbeat });
+        Ok(())
+    }
+
+    /// Returns the effective (max_sources, quorum, heartbeat) for IL source aggregation
+    pub fn get_il_source_params(&self) -> (U256, U256, U256) {
+        (self.effective_max_il_sources(), self.effective_il_source_quorum(), self.effective_il_source_heartbeat())
+    }
+
+    /// Registers an address whose IL answers feed a pool's median, callable only by the owner
+    pub fn add_il_source(&mut self, pool_id: FixedBytes<32>, source: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        if source == Address::ZERO
+            || self.is_il_source.get(pool_id).get(source)
+            || U256::from(self.il_sources.get(pool_id).len()) >= self.effective_max_il_sources()
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.il_sources.setter(pool_id).push(source);
+        self.is_il_source.setter(pool_id).insert(source, true);
+        evm::log(IlSourceAdded { pool_id, source });
+        Ok(())
+    }
+
+    /// Deregisters one of a pool's IL sources and drops its answer, callable only by the owner
+    pub fn remove_il_source(&mut self, pool_id: FixedBytes<32>, source: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        if !self.is_il_source.get(pool_id).get(source) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let remaining: Vec<Address> = self.get_il_sources(pool_id).into_iter().filter(|s| *s != source).collect();
+        let mut sources = self.il_sources.setter(pool_id);
+        sources.truncate(0);
+        for s in remaining {
+            sources.push(s);
+        }
+        self.is_il_source.setter(pool_id).insert(source, false);
+        self.il_source_value.setter(pool_id).insert(source, U256::ZERO);
+        self.il_source_updated_at.setter(pool_id).insert(source, U256::ZERO);
+        evm::log(IlSourceRemoved { pool_id, source });
+        Ok(())
+    }
+
+    /// Returns a pool's registered IL sources
+    pub fn get_il_sources(&self, pool_id: FixedBytes<32>) -> Vec<Address> {
+        let sources = self.il_sources.get(pool_id);
+        (0..sources.len()).map(|i| sources.get(i).unwrap_or_default()).collect()
+    }
+
+    /// Records the caller's latest IL answer for a pool it is registered as a source for
+    pub fn submit_il_source(&mut self, pool_id: FixedBytes<32>, il: U256) -> Result<(), Error> {
+        let source = msg::sender();
+        if !self.is_il_source.get(pool_id).get(source) {
+            return Err(Error::Unauthorized(Unauthorized { caller: source }));
+        }
+        if il > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.il_source_value.setter(pool_id).insert(source, il);
+        self.il_source_updated_at.setter(pool_id).insert(source, block_time());
+        evm::log(IlSourceSubmitted { pool_id, source, il });
+        Ok(())
+    }
+
+    /// Returns a source's latest (il, timestamp) answer for a pool
+    pub fn get_il_source(&self, pool_id: FixedBytes<32>, source: Address) -> (U256, U256) {
+        (self.il_source_value.get(pool_id).get(source), self.il_source_updated_at.get(pool_id).get(source))
+    }
+
+    /// Returns the (il, fresh_sources) a pool's sources aggregate to; below quorum the IL is
+    /// the unrated default
+    pub fn get_aggregated_il(&self, pool_id: FixedBytes<32>) -> (U256, U256) {
+        let mut fresh = self.fresh_il_answers(pool_id);
+        let count = U256::from(fresh.len());
+        if fresh.is_empty() || count < self.effective_il_source_quorum() {
+            return (self.unrated_default_il.get(), count);
+        }
+        (math::median(&mut fresh), count)
+    }
+
+    /// Stores an IL update signed by an updater, submitted by any relayer
+    ///
+    /// `signature` is the 65-byte `r || s || v` signature over `il_update_digest`. The signer
+    /// must be allowed to update the pool, and the update passes the same deviation and
+    /// sequence checks as a direct write.
+    pub fn submit_signed_il_update(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        il: U256,
+        sequence: U256,
+        signature: Bytes,
+    ) -> Result<(), Error> {
+        if il > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let digest = self.il_update_digest(pool_id, il, sequence);
+        let signer = Self::recover_signer(digest, &signature).ok_or(Error::InvalidInput(InvalidInput{}))?;
+        if !self.is_updater_for(pool_id, signer) {
+            return Err(Error::UnauthorizedForPool(UnauthorizedForPool { caller: signer, pool_id }));
+        }
+        self.guarded_write(pool_id, il, sequence, signer)
+    }
+
+    /// Returns the digest an updater signs to authorize an IL update
+    ///
+    /// `keccak256("\x19Ethereum Signed Message:\n32" ++ keccak256(abi.encode(address(this),
+    /// block.chainid, pool_id, il, sequence)))`, i.e. a standard signed message over the
+    /// inner hash, so the sequence number and deployment are bound into every signature.
+    pub fn il_update_digest(&self, pool_id: FixedBytes<32>, il: U256, sequence: U256) -> FixedBytes<32> {
+        let inner = keccak((contract::address(), U256::from(block::chainid()), pool_id, il, sequence).abi_encode());
+        let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+        message.extend_from_slice(inner.as_slice());
+        keccak(message)
+    }
+
+    /// Returns the sequence number of a pool's latest IL write
+    pub fn get_update_sequence(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.update_sequence.get(pool_id)
+    }
+
+    /// Sets the relative band a single IL update must stay within, in basis points
+    /// (zero restores the default)
+    pub fn set_max_il_deviation_bps(&mut self, bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.max_il_deviation_bps.set(bps);
+        evm::log(MaxIlDeviationSet { bps });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the relative band a single IL update must stay within, in basis points
+    pub fn max_il_deviation_bps(&self) -> U256 {
+        let bps = self.max_il_deviation_bps.get();
+        if bps == U256::ZERO {
+            U256::from(DEFAULT_MAX_IL_DEVIATION_BPS)
+        } else {
+            bps
+        }
+    }
+
+    /// Sets the ceiling on the IL quotes price with (WAD, at most 1.0; zero restores 1.0)
+    ///
+    /// Applied when quoting rather than when writing, so even a corrupted stored value cannot
+    /// push the IL multiplier past `1 + il_weight * cap`.
+    pub fn set_max_effective_il(&mut self, cap: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if cap > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.max_effective_il.set(cap);
+        evm::log(MaxEffectiveIlSet { cap });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the ceiling on the IL quotes price with
+    pub fn max_effective_il(&self) -> U256 {
+        let cap = self.max_effective_il.get();
+        if cap == U256::ZERO {
+            U256::from(WAD)
+        } else {
+            cap
+        }
+    }
+
+    /// Returns whether a pool's quotes are currently clamping its IL to the ceiling
+    pub fn is_il_clamped(&self, pool_id: FixedBytes<32>) -> bool {
+        self.effective_il(pool_id) > self.max_effective_il()
+    }
+
+    /// Sets the seconds that must pass between a pool's accepted IL writes (zero disables)
+    ///
+    /// Owner writes, including forced updates, are never rate limited.
+    pub fn set_min_il_update_interval(&mut self, interval: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.min_il_update_interval.set(interval);
+        evm::log(MinIlUpdateIntervalSet { interval });
+        Ok(())
+    }
+
+    /// Returns the seconds that must pass between a pool's accepted IL writes
+    pub fn min_il_update_interval(&self) -> U256 {
+        self.min_il_update_interval.get()
+    }
+
+    /// Returns the timestamp of a pool's last IL update, counting a matured pending update
+    pub fn get_last_il_update(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.il_updated_at(pool_id)
+    }
+
+    /// Returns how many IL writes a pool's history log retains (at most 128)
+    pub fn get_il_history_length(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.il_history_count.get(pool_id).min(U256::from(IL_HISTORY_CAPACITY))
+    }
+
+    /// Returns up to `limit` retained IL writes as (values, timestamps, updaters), newest first,
+    /// skipping the `offset` most recent
+    pub fn get_il_history(
+        &self,
+        pool_id: FixedBytes<32>,
+        offset: U256,
+        limit: U256,
+    ) -> (Vec<U256>, Vec<U256>, Vec<Address>) {
+        let length = self.get_il_history_length(pool_id);
+        let start = offset.min(length);
+        let end = start.saturating_add(limit).min(length);
+        let count = self.il_history_count.get(pool_id);
+        let history = self.il_history.get(pool_id);
+        let page = (end - start).to::<usize>();
+        let mut values = Vec::with_capacity(page);
+        let mut timestamps = Vec::with_capacity(page);
+        let mut updaters = Vec::with_capacity(page);
+        for age in start.to::<u64>()..end.to::<u64>() {
+            // The `age`-th newest write; within the retained length it has not been overwritten
+            let slot = (count - U256::from(1) - U256::from(age)) % U256::from(IL_HISTORY_CAPACITY);
+            let entry = history.get(slot);
+            values.push(entry.value.get());
+            timestamps.push(entry.timestamp.get());
+            updaters.push(entry.updater.get());
+        }
+        (values, timestamps, updaters)
+    }
+
+    /// Hashes per-pool oracle state so a migrated deployment can be checked against its source
+    ///
+    /// Starting from `bytes32(0)`, each pool in the given order folds in as
+    /// `acc = keccak256(abi.encode(acc, pool_id, historical_il, default_flash_fee_multiplier,
+    /// last_il_update))`, all values as stored (unset reads as zero). An empty list returns
+    /// `bytes32(0)`; reordering or repeating ids changes the result.
+    pub fn compute_state_commitment(&self, pool_ids: Vec<FixedBytes<32>>) -> FixedBytes<32> {
+        pool_ids.iter().fold(FixedBytes::ZERO, |acc, pool_id| {
+            keccak((
+                acc,
+                *pool_id,
+                self.historical_il.get(*pool_id),
+                self.default_flash_fee_multiplier.get(*pool_id),
+                self.last_il_update.get(*pool_id),
+            ).abi_encode())
+        })
+    }
+
+    /// Sets how long IL data stays fresh, in seconds (zero disables staleness flagging)
+    pub fn set_stale_threshold(&mut self, threshold: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.stale_threshold.set(threshold);
+        evm::log(StaleThresholdSet { threshold });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the IL staleness threshold in seconds
+    pub fn stale_threshold(&self) -> U256 {
+        self.stale_threshold.get()
+    }
+
+    /// Sets the conservative IL (WAD fraction) quoted for unrated pools
+    pub fn set_unrated_default_il(&mut self, il: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if il > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.unrated_default_il.set(il);
+        evm::log(UnratedDefaultIlSet { il });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the conservative IL quoted for unrated pools
+    pub fn unrated_default_il(&self) -> U256 {
+        self.unrated_default_il.get()
+    }
+
+    /// Flags a pool whose IL data has gone stale, callable by anyone
+    pub fn mark_pool_unrated(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        self.promote_pending(pool_id);
+        let last_update = self.last_il_update.get(pool_id);
+        let stale_threshold = self.stale_threshold.get();
+        let now = block_time();
+        // Strictly older than the threshold; a zero threshold means the feature is off
+        let is_stale = stale_threshold > U256::ZERO
+            && now.saturating_sub(last_update) > stale_threshold;
+        if !is_stale || self.unrated.get(pool_id) {
+            return Err(Error::PoolNotStale(PoolNotStale { last_update, stale_threshold }));
+        }
+        self.unrated.insert(pool_id, true);
+        evm::log(PoolMarkedUnrated { pool_id, caller: msg::sender(), last_update });
+        Ok(())
+    }
+
+    /// Sets the oldest IL or price observation a pool quotes from, in seconds (zero defers to
+    /// the global default)
+    pub fn set_max_data_age(&mut self, pool_id: FixedBytes<32>, max_age: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        self.max_data_age.insert(pool_id, max_age);
+        evm::log(MaxDataAgeSet { pool_id, max_age });
+        Ok(())
+    }
+
+    /// Sets the data age pools without their own heartbeat accept (zero imposes none)
+    pub fn set_default_max_data_age(&mut self, max_age: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.default_max_data_age.set(max_age);
+        evm::log(DefaultMaxDataAgeSet { max_age });
+        Ok(())
+    }
+
+    /// Returns the global data age default, in seconds
+    pub fn default_max_data_age(&self) -> U256 {
+        self.default_max_data_age.get()
+    }
+
+    /// Returns the oldest data a pool quotes from, in seconds (zero means no requirement)
+    pub fn get_max_data_age(&self, pool_id: FixedBytes<32>) -> U256 {
+        let max_age = self.max_data_age.get(pool_id);
+        if max_age == U256::ZERO {
+            self.default_max_data_age.get()
+        } else {
+            max_age
+        }
+    }
+
+    /// Returns whether a pool is quoting off the unrated default IL
+    pub fn is_pool_unrated(&self, pool_id: FixedBytes<32>) -> bool {
+        self.unrated.get(pool_id)
+    }
+
+    /// Sets how long issued quotes stay valid, in seconds (zero restores the default)
+    pub fn set_quote_validity(&mut self, validity: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.quote_validity.set(validity);
+        evm::log(QuoteValiditySet { validity });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns how long issued quotes stay valid, in seconds
+    pub fn quote_validity(&self) -> U256 {
+        let validity = self.quote_validity.get();
+        if validity == U256::ZERO {
+            U256::from(DEFAULT_QUOTE_VALIDITY)
+        } else {
+            validity
+        }
+    }
+
+    /// Quotes an insurance fee and locks it in for the caller, returning (quote_id, fee)
+    pub fn issue_quote(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<(FixedBytes<32>, U256), Error> {
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+
+        let issuer = msg::sender();
+        let nonce = self.quote_nonce.get();
+        self.quote_nonce.set(nonce + U256::from(1));
+        let params_hash = keccak((pool_id, amount, total_liquidity, total_volume, fee).abi_encode());
+        let quote_id = keccak((params_hash, issuer, nonce).abi_encode());
+        let expiry = block_time()
+            .checked_add(self.quote_validity())
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+
+        let mut quote = self.quotes.setter(quote_id);
+        quote.params_hash.set(params_hash);
+        quote.issuer.set(issuer);
+        quote.fee.set(fee);
+        quote.expiry.set(expiry);
+        evm::log(QuoteIssued { quote_id, pool_id, issuer, fee, expiry, epoch: self.parameter_epoch.get() });
+        Ok((quote_id, fee))
+    }
+
+    /// Consumes a live quote issued to the caller, returning its locked fee
+    pub fn settle_quote(&mut self, quote_id: FixedBytes<32>) -> Result<U256, Error> {
+        let quote = self.quotes.get(quote_id);
+        let issuer = quote.issuer.get();
+        // Quotes are bound to their issuer so nobody else can burn them
+        if issuer == Address::ZERO || issuer != msg::sender() {
+            return Err(Error::QuoteNotFound(QuoteNotFound { quote_id }));
+        }
+        if quote.settled.get() {
+            return Err(Error::QuoteAlreadySettled(QuoteAlreadySettled { quote_id }));
+        }
+        let expiry = quote.expiry.get();
+        if block_time() > expiry {
+            return Err(Error::QuoteExpired(QuoteExpired { quote_id, expiry }));
+        }
+        let fee = quote.fee.get();
+        self.quotes.setter(quote_id).settled.set(true);
+        evm::log(QuoteSettled { quote_id, issuer, fee });
+        Ok(fee)
+    }
+
+    /// Returns a quote's (params_hash, issuer, fee, expiry, settled) record
+    pub fn get_quote(&self, quote_id: FixedBytes<32>) -> (FixedBytes<32>, Address, U256, U256, bool) {
+        let quote = self.quotes.get(quote_id);
+        (quote.params_hash.get(), quote.issuer.get(), quote.fee.get(), quote.expiry.get(), quote.settled.get())
+    }
+
+    /// Sets how many blocks a commitment stays revealable (zero restores the default)
+    pub fn set_reveal_window(&mut self, blocks: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.reveal_window.set(blocks);
+        evm::log(RevealWindowSet { blocks });
+        Ok(())
+    }
+
+    /// Returns how many blocks a commitment stays revealable
+    pub fn reveal_window(&self) -> U256 {
+        let window = self.reveal_window.get();
+        if window == U256::ZERO {
+            U256::from(DEFAULT_REVEAL_WINDOW)
+        } else {
+            window
+        }
+    }
+
+    /// Commits to a quote request without revealing its size
+    ///
+    /// The commitment is `keccak256(abi.encode(pool_id, amount, salt, msg.sender))`.
+    pub fn commit_quote(&mut self, commitment: FixedBytes<32>) -> Result<(), Error> {
+        if commitment == FixedBytes::ZERO || self.commitment_block.get(commitment) != U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let commit_block = U256::from(block::number());
+        self.commitment_block.insert(commitment, commit_block);
+        evm::log(QuoteCommitted { commitment, committer: msg::sender(), commit_block });
+        Ok(())
+    }
+
+    /// Reveals a committed quote request and prices it against current pool state
+    pub fn reveal_and_quote(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        salt: FixedBytes<32>,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        let commitment = keccak((pool_id, amount, salt, msg::sender()).abi_encode());
+        let commit_block = self.commitment_block.get(commitment);
+        if commit_block == U256::ZERO {
+            return Err(Error::CommitmentMismatch(CommitmentMismatch { commitment }));
+        }
+        let current_block = U256::from(block::number());
+        if current_block <= commit_block {
+            return Err(Error::RevealTooEarly(RevealTooEarly { commit_block, current_block }));
+        }
+        if current_block - commit_block > self.reveal_window() {
+            return Err(Error::CommitmentExpired(CommitmentExpired { commit_block, current_block }));
+        }
+
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+
+        // Each commitment is single-use
+        self.commitment_block.delete(commitment);
+        evm::log(QuoteRevealed { commitment, pool_id, amount, fee, epoch: self.parameter_epoch.get() });
+        Ok(fee)
+    }
+
+    /// Replaces the loyalty tiers; thresholds must be strictly increasing
+    pub fn set_loyalty_tiers(
+        &mut self,
+        thresholds: Vec<U256>,
+        discounts_bps: Vec<U256>,
+    ) -> Result<(), Error> {
+        self.only_owner()?;
+        if thresholds.len() != discounts_bps.len() || thresholds.len() > MAX_LOYALTY_TIERS {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if thresholds.windows(2).any(|pair| pair[0] >= pair[1])
+            || discounts_bps.iter().any(|bps| *bps > U256::from(BPS_DENOMINATOR))
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.loyalty_thresholds.truncate(0);
+        self.loyalty_discounts_bps.truncate(0);
+        for (threshold, bps) in thresholds.iter().zip(discounts_bps.iter()) {
+            self.loyalty_thresholds.push(*threshold);
+            self.loyalty_discounts_bps.push(*bps);
+        }
+        evm::log(LoyaltyTiersUpdated { thresholds, discounts_bps });
+        Ok(())
+    }
+
+    /// Returns the configured (thresholds, discounts_bps) loyalty tiers
+    pub fn get_loyalty_tiers(&self) -> (Vec<U256>, Vec<U256>) {
+        let count = self.loyalty_thresholds.len();
+        let mut thresholds = Vec::with_capacity(count);
+        let mut discounts_bps = Vec::with_capacity(count);
+        for i in 0..count {
+            thresholds.push(self.loyalty_thresholds.get(i).unwrap_or_default());
+            discounts_bps.push(self.loyalty_discounts_bps.get(i).unwrap_or_default());
+        }
+        (thresholds, discounts_bps)
+    }
+
+    /// Returns an address's (cumulative_volume, current_discount_bps)
+    pub fn get_loyalty_status(&self, addr: Address) -> (U256, U256) {
+        let volume = self.insured_volume.get(addr);
+        (volume, self.loyalty_discount_bps(volume))
+    }
+
+    /// Adds or removes an address from the flash fee exemption list
+    pub fn set_flash_fee_exempt(&mut self, account: Address, exempt: bool) -> Result<(), Error> {
+        self.only_owner()?;
+        self.flash_fee_exempt.insert(account, exempt);
+        evm::log(FlashFeeExemptSet { account, exempt });
+        Ok(())
+    }
+
+    /// Returns whether an address flash-borrows without fees
+    pub fn is_flash_fee_exempt(&self, account: Address) -> bool {
+        self.flash_fee_exempt.get(account)
+    }
+
+    /// Waives insurance fees on a pool until `until`; a past timestamp clears the holiday
+    pub fn set_fee_holiday(&mut self, pool_id: FixedBytes<32>, until: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        let until = if until <= block_time() { U256::ZERO } else { until };
+        self.fee_holiday_until.insert(pool_id, until);
+        evm::log(FeeHolidaySet { pool_id, until });
+        Ok(())
+    }
+
+    /// Returns when a pool's fee holiday ends (zero if none is scheduled)
+    pub fn get_fee_holiday_until(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.fee_holiday_until.get(pool_id)
+    }
+
+    /// Returns whether a pool is currently in a fee holiday
+    pub fn is_fee_holiday(&self, pool_id: FixedBytes<32>) -> bool {
+        block_time() < self.fee_holiday_until.get(pool_id)
+    }
+
+    /// Waives insurance on a pool's trades below `threshold` (WAD, zero disables the waiver)
+    ///
+    /// Trade amounts are normalized from the pool's token0 decimals before the comparison.
+    pub fn set_dust_threshold(&mut self, pool_id: FixedBytes<32>, threshold: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.dust_threshold.insert(pool_id, threshold);
+        evm::log(DustThresholdSet { pool_id, threshold });
+        Ok(())
+    }
+
+    /// Returns the amount below which a pool's insurance is waived
+    pub fn get_dust_threshold(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.dust_threshold.get(pool_id)
+    }
+
+    /// Installs a pool's size curve: up to 8 (size_fraction, multiplier) WAD breakpoints with
+    /// strictly increasing size fractions, linearly interpolated and flat outside the table
+    pub fn set_fee_curve(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        size_fractions: Vec<U256>,
+        multipliers: Vec<U256>,
+    ) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if size_fractions.is_empty()
+            || size_fractions.len() != multipliers.len()
+            || size_fractions.len() > MAX_CURVE_POINTS
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if size_fractions.windows(2).any(|pair| pair[0] >= pair[1])
+            || multipliers.iter().any(|multiplier| *multiplier > U256::from(MAX_RISK_WEIGHT))
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let mut xs = self.fee_curve_x.setter(pool_id);
+        xs.truncate(0);
+        for x in size_fractions.iter() {
+            xs.push(*x);
+        }
+        let mut ys = self.fee_curve_y.setter(pool_id);
+        ys.truncate(0);
+        for y in multipliers.iter() {
+            ys.push(*y);
+        }
+        evm::log(FeeCurveSet { pool_id, size_fractions, multipliers });
+        Ok(())
+    }
+
+    /// Removes a pool's size curve, restoring the parametric size multiplier
+    pub fn clear_fee_curve(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        self.fee_curve_x.setter(pool_id).truncate(0);
+        self.fee_curve_y.setter(pool_id).truncate(0);
+        evm::log(FeeCurveCleared { pool_id });
+        Ok(())
+    }
+
+    /// Returns a pool's size curve as (size_fractions, multipliers); empty when none is installed
+    pub fn get_fee_curve(&self, pool_id: FixedBytes<32>) -> (Vec<U256>, Vec<U256>) {
+        let xs = self.fee_curve_x.get(pool_id);
+        let ys = self.fee_curve_y.get(pool_id);
+        let mut size_fractions = Vec::with_capacity(xs.len());
+        let mut multipliers = Vec::with_capacity(xs.len());
+        for i in 0..xs.len() {
+            size_fractions.push(xs.get(i).unwrap_or_default());
+            multipliers.push(ys.get(i).unwrap_or_default());
+        }
+        (size_fractions, multipliers)
+    }
+
+    /// Configures the new-pool grace period (at most 365 days) and its fee factor (WAD, at most 1.0)
+    pub fn set_grace_period(&mut self, duration: U256, factor: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if factor > U256::from(WAD) || duration > U256::from(MAX_GRACE_DURATION) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.grace_duration.set(duration);
+        self.grace_factor.set(factor);
+        evm::log(GracePeriodSet { duration, factor });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the configured (duration, factor) grace period
+    pub fn get_grace_period(&self) -> (U256, U256) {
+        (self.grace_duration.get(), self.grace_factor.get())
+    }
+
+    /// Returns the grace multiplier currently applied to a pool's insurance fee (WAD)
+    ///
+    /// Holds at the grace factor for the first three quarters of the window, then ramps
+    /// linearly back to 1.0 by its end.
+    pub fn get_grace_multiplier(&self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        let pool = self.pools.get(pool_id);
+        let duration = self.grace_duration.get();
+        if !pool.registered.get() || duration == U256::ZERO {
+            return Ok(U256::from(WAD));
+        }
+        let elapsed = block_time().saturating_sub(pool.registered_at.get());
+        if elapsed >= duration {
+            return Ok(U256::from(WAD));
+        }
+        let factor = self.grace_factor.get();
+        let phase_out_start = math::mul_div(duration, U256::from(3), U256::from(4))?;
+        if elapsed < phase_out_start {
+            return Ok(factor);
+        }
+        let recovered = math::mul_div(
+            U256::from(WAD) - factor,
+            elapsed - phase_out_start,
+            duration - phase_out_start,
+        )?;
+        Ok(factor + recovered)
+    }
+
+    /// Sets the WAD premium `k` a pool with no IL observations is priced with; the fee
+    /// multiplier `1 + k / (observations + 1)` decays toward 1.0 as observations accrue
+    pub fn set_uncertainty_premium(&mut self, premium: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if premium > U256::from(MAX_RISK_WEIGHT) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.uncertainty_premium.set(premium);
+        evm::log(UncertaintyPremiumSet { premium });
+        Ok(())
+    }
+
+    /// Returns the WAD uncertainty premium
+    pub fn uncertainty_premium(&self) -> U256 {
+        self.uncertainty_premium.get()
+    }
+
+    /// Returns the number of IL observations behind a pool's rating
+    pub fn get_il_observations(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.il_observations.get(pool_id)
+    }
+
+    /// Returns the fee multiplier (WAD) for a pool's data confidence, `1 + k / (observations + 1)`
+    pub fn get_uncertainty_multiplier(&self, pool_id: FixedBytes<32>) -> U256 {
+        // observations + 1 cannot overflow: the counter only moves by one per IL write
+        let loading = self.uncertainty_premium.get() / (self.il_observations.get(pool_id) + U256::from(1));
+        U256::from(WAD) + loading
+    }
+
+    /// Clears a pool's IL observation count, returning it to full uncertainty pricing
+    pub fn reset_pool_observations(&mut self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        self.only_owner()?;
+        let observations = self.il_observations.get(pool_id);
+        self.il_observations.insert(pool_id, U256::ZERO);
+        evm::log(PoolObservationsReset { pool_id, observations });
+        Ok(())
+    }
+
+    /// Configures a pool's flash fee surge; a zero multiplier disables it
+    ///
+    /// The threshold must sit below 100% utilization and the multiplier between 1x and 10x.
+    pub fn set_surge_params(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        threshold: U256,
+        multiplier: U256,
+    ) -> Result<(), Error> {
+        self.only_owner()?;
+        Self::check_surge_params(threshold, multiplier)?;
+        self.surge_threshold.insert(pool_id, threshold);
+        self.surge_multiplier.insert(pool_id, multiplier);
+        evm::log(SurgeParamsSet { pool_id, threshold, multiplier });
+        Ok(())
+    }
+
+    /// Returns a pool's (threshold, multiplier) surge configuration
+    pub fn get_surge_params(&self, pool_id: FixedBytes<32>) -> (U256, U256) {
+        (self.surge_threshold.get(pool_id), self.surge_multiplier.get(pool_id))
+    }
+
+    /// Records a swap for analytics and loyalty, returning the insurance fee it was quoted
+    ///
+    /// The fee is debited from the caller's prepaid coverage budget for the pool and collected
+    /// as premium. Dust swaps below the pool's threshold quote zero and leave every counter
+    /// and the budget untouched.
+    pub fn record_swap_volume(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        trader: Address,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.only_updater(pool_id)?;
+        if self.is_dust(pool_id, amount) {
+            return Ok(U256::ZERO);
+        }
+        let (raw_fee, formula_failed) = self.price_insurance_fee_traced(
+            pool_id,
+            IL_PRODUCT,
+            Address::ZERO,
+            None,
+            amount,
+            total_liquidity,
+            total_volume,
+        )?;
+        if formula_failed {
+            evm::log(FeeFormulaFallback { pool_id, formula: self.fee_formula.get(pool_id) });
+        }
+        let (fee, rate) = self.smooth_fee(pool_id, raw_fee, amount)?;
+        self.last_recorded_fee_rate.insert(pool_id, rate);
+        self.last_recorded_fee_block.insert(pool_id, U256::from(block::number()));
+        let fee = self.apply_loyalty_discount(trader, fee)?;
+        self.debit_coverage_budget(pool_id, msg::sender(), fee)?;
+
+        let volume = self.recorded_volume.get(pool_id)
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let fees = self.recorded_fees.get(pool_id)
+            .checked_add(fee)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let count = self.recorded_swap_count.get(pool_id) + U256::from(1);
+        self.recorded_volume.insert(pool_id, volume);
+        self.recorded_fees.insert(pool_id, fees);
+        self.recorded_swap_count.insert(pool_id, count);
+        if fee != U256::ZERO {
+            self.add_insured_volume(trader, amount)?;
+        }
+        self.record_recent_fee(pool_id, fee, amount);
+
+        evm::log(SwapRecorded { pool_id, trader, amount, fee, epoch: self.parameter_epoch.get() });
+        Ok(fee)
+    }
+
+    /// Sets how far a pool's swap fee rate may move per block from the last recorded rate, in
+    /// basis points (zero disables smoothing)
+    pub fn set_fee_smoothing(&mut self, pool_id: FixedBytes<32>, max_change_bps: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if max_change_bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.fee_smoothing_bps.insert(pool_id, max_change_bps);
+        evm::log(FeeSmoothingSet { pool_id, max_change_bps });
+        Ok(())
+    }
+
+    /// Returns a pool's (max_change_bps, last_recorded_fee_rate, last_recorded_block) smoothing
+    /// state, the rate in WAD fee per unit swapped
+    pub fn get_fee_smoothing(&self, pool_id: FixedBytes<32>) -> (U256, U256, U256) {
+        (
+            self.fee_smoothing_bps.get(pool_id),
+            self.last_recorded_fee_rate.get(pool_id),
+            self.last_recorded_fee_block.get(pool_id),
+        )
+    }
+
+    /// Quotes a swap's insurance fee as (raw, smoothed), where smoothed is what
+    /// `record_swap_volume` would charge the caller as trader in this block
+    pub fn calculate_smoothed_insurance_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<(U256, U256), Error> {
+        let raw_fee =
+            self.quote_insurance_fee_for(pool_id, IL_PRODUCT, Address::ZERO, amount, total_liquidity, total_volume)?;
+        let (fee, _) = self.smooth_fee(pool_id, raw_fee, amount)?;
+        Ok((
+            self.apply_loyalty_discount(msg::sender(), raw_fee)?,
+            self.apply_loyalty_discount(msg::sender(), fee)?,
+        ))
+    }
+
+    /// Returns up to `count` of a pool's most recently recorded swap and flash fees as
+    /// (fees, amounts, timestamps), newest first
+    ///
+    /// Only the last 32 are retained; asking for more returns what is there.
+    pub fn get_recent_fees(&self, pool_id: FixedBytes<32>, count: U256) -> (Vec<U256>, Vec<U256>, Vec<U256>) {
+        let recorded = self.recent_fee_count.get(pool_id);
+        let length = recorded.min(U256::from(RECENT_FEES_CAPACITY)).min(count).to::<u64>();
+        let records = self.recent_fees.get(pool_id);
+        let mut fees = Vec::with_capacity(length as usize);
+        let mut amounts = Vec::with_capacity(length as usize);
+        let mut timestamps = Vec::with_capacity(length as usize);
+        for age in 0..length {
+            let record = records.get((recorded - U256::from(1) - U256::from(age)) % U256::from(RECENT_FEES_CAPACITY));
+            fees.push(record.fee.get());
+            amounts.push(record.amount.get());
+            timestamps.push(record.timestamp.get());
+        }
+        (fees, amounts, timestamps)
+    }
+
+    /// Records a swap like `record_swap_volume` unless the caller already used `key`, in which
+    /// case the first call's fee is returned and nothing is recorded again
+    pub fn record_swap_volume_idempotent(
+        &mut self,
+        key: FixedBytes<32>,
+        pool_id: FixedBytes<32>,
+        trader: Address,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        if let Some((fee, _)) = self.idempotent_replay(key) {
+            return Ok(fee);
+        }
+        let fee = self.record_swap_volume(pool_id, trader, amount, total_liquidity, total_volume)?;
+        self.consume_idempotency_key(key, fee, U256::ZERO);
+        Ok(fee)
+    }
+
+    /// Returns whether the caller has consumed an idempotency key
+    pub fn is_idempotency_key_used(&self, caller: Address, key: FixedBytes<32>) -> bool {
+        self.idempotent_results.get(caller).get(key).consumed.get()
+    }
+
+    /// Prepays ETH toward the insurance fees of the swaps the caller records on a pool
+    #[payable]
+    pub fn deposit_coverage_budget(&mut self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        let amount = msg::value();
+        if amount == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let payer = msg::sender();
+        let budget = self.coverage_budget.get(pool_id).get(payer)
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.coverage_budget.setter(pool_id).insert(payer, budget);
+        evm::log(CoverageBudgetDeposited { pool_id, payer, amount });
+        Ok(budget)
+    }
+
+    /// Withdraws unused coverage budget back to the caller
+    pub fn withdraw_coverage_budget(&mut self, pool_id: FixedBytes<32>, amount: U256) -> Result<(), Error> {
+        let payer = msg::sender();
+        let available = self.coverage_budget.get(pool_id).get(payer);
+        if amount == U256::ZERO || amount > available {
+            return Err(Error::InsufficientBalance(InsufficientBalance { available, requested: amount }));
+        }
+        self.coverage_budget.setter(pool_id).insert(payer, available - amount);
+        transfer_eth(payer, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(CoverageBudgetWithdrawn { pool_id, payer, amount });
+        Ok(())
+    }
+
+    /// Returns a payer's remaining coverage budget on a pool
+    pub fn get_coverage_budget(&self, pool_id: FixedBytes<32>, payer: Address) -> U256 {
+        self.coverage_budget.get(pool_id).get(payer)
+    }
+
+    /// Assigns the swap hook allowed to push a pool's realized figures, callable only by the owner
+    ///
+    /// The hook's IL writes bypass the updater guards, so it is never left to pool operators.
+    pub fn set_pool_hook(&mut self, pool_id: FixedBytes<32>, hook: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        self.pool_hook.insert(pool_id, hook);
+        evm::log(PoolHookSet { pool_id, hook });
+        Ok(())
+    }
+
+    /// Returns a pool's swap hook
+    pub fn get_pool_hook(&self, pool_id: FixedBytes<32>) -> Address {
+        self.pool_hook.get(pool_id)
+    }
+
+    /// Records a swap pushed by the pool's hook and folds its price into the smoothed IL
+    ///
+    /// The price joins a ring of the last 16 observations. Once the ring is full, the IL of
+    /// holding from the oldest price to `price_after`, `1 - 2 * sqrt(r) / (1 + r)`, is blended
+    /// into the stored IL with a 10% weight, and each swap moves it no further than the pool's
+    /// IL deviation band lets an updater.
+    pub fn on_after_swap(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        swap_volume: U256,
+        price_after: U256,
+    ) -> Result<U256, Error> {
+        let hook = self.pool_hook.get(pool_id);
+        if hook == Address::ZERO || msg::sender() != hook {
+            return Err(Error::UnauthorizedForPool(UnauthorizedForPool { caller: msg::sender(), pool_id }));
+        }
+        if price_after == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+
+        let count = self.observation_count.get(pool_id);
+        let slot = count % U256::from(OBSERVATION_WINDOW);
+        // The slot being overwritten holds the oldest price in the window
+        let reference = self.price_observations.get(pool_id).get(slot);
+        self.price_observations.setter(pool_id).insert(slot, price_after);
+        self.observation_count.insert(pool_id, count + U256::from(1));
+        self.last_price_observation.insert(pool_id, block_time());
+
+        let volume = self.recorded_volume.get(pool_id)
+            .checked_add(swap_volume)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.recorded_volume.insert(pool_id, volume);
+        self.recorded_swap_count.insert(pool_id, self.recorded_swap_count.get(pool_id) + U256::from(1));
+
+        self.promote_pending(pool_id);
+        let mut il = self.historical_il.get(pool_id);
+        if reference != U256::ZERO {
+            let measured = Self::price_path_il(reference, price_after)?;
+            let smoothed = math::mul_div(il, U256::from(BPS_DENOMINATOR - IL_SMOOTHING_BPS), U256::from(BPS_DENOMINATOR))?
+                + math::mul_div(measured, U256::from(IL_SMOOTHING_BPS), U256::from(BPS_DENOMINATOR))?;
+            il = self.clamp_il_deviation(pool_id, smoothed);
+            self.store_historical_il(pool_id, il, hook);
+        }
+        evm::log(SwapPushed { pool_id, volume: swap_volume, price_after, il });
+        Ok(il)
+    }
+
+    /// Sets how far a price-aware quote's price may stray from the pool's reference, in basis
+    /// points (zero disables the breaker)
+    pub fn set_circuit_breaker_bps(&mut self, pool_id: FixedBytes<32>, max_deviation_bps: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        self.circuit_breaker_bps.insert(pool_id, max_deviation_bps);
+        evm::log(CircuitBreakerSet { pool_id, max_deviation_bps });
+        Ok(())
+    }
+
+    /// Skips or restores a pool's circuit breaker while its reference recovers
+    pub fn set_circuit_breaker_bypass(&mut self, pool_id: FixedBytes<32>, bypassed: bool) -> Result<(), Error> {
+        self.only_owner()?;
+        self.circuit_breaker_bypassed.insert(pool_id, bypassed);
+        evm::log(CircuitBreakerBypassSet { pool_id, bypassed });
+        Ok(())
+    }
+
+    /// Returns a pool's (max_deviation_bps, bypassed) circuit breaker configuration
+    pub fn get_circuit_breaker(&self, pool_id: FixedBytes<32>) -> (U256, bool) {
+        (self.circuit_breaker_bps.get(pool_id), self.circuit_breaker_bypassed.get(pool_id))
+    }
+
+    /// Returns the mean of a pool's retained price observations (zero before the first)
+    pub fn get_reference_price(&self, pool_id: FixedBytes<32>) -> U256 {
+        let retained = self.observation_count.get(pool_id).min(U256::from(OBSERVATION_WINDOW));
+        if retained == U256::ZERO {
+            return U256::ZERO;
+        }
+        let observations = self.price_observations.get(pool_id);
+        let mut sum = U256::ZERO;
+        for slot in 0..retained.to::<u64>() {
+            sum = sum.saturating_add(observations.get(U256::from(slot)));
+        }
+        sum / retained
+    }
+
+    /// Returns a pool's recorded (volume, swap_count, fees)
+    pub fn get_swap_stats(&self, pool_id: FixedBytes<32>) -> (U256, U256, U256) {
+        (
+            self.recorded_volume.get(pool_id),
+            self.recorded_swap_count.get(pool_id),
+            self.recorded_fees.get(pool_id),
+        )
+    }
+
+    /// Computes an LP's pro-rata share of a period's fees, rounded down
+    pub fn calculate_lp_share(
+        &self,
+        pool_id: FixedBytes<32>,
+        lp_liquidity: U256,
+        total_liquidity: U256,
+        period_fees: U256,
+    ) -> Result<U256, Error> {
+        self.check_registration(pool_id)?;
+        if total_liquidity == U256::ZERO || lp_liquidity > total_liquidity {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        math::mul_div(period_fees, lp_liquidity, total_liquidity)
+    }
+
+    /// Records fees collected for a pool's LPs outside the contract, along with the pool's
+    /// current total liquidity, and advances its fee growth accumulator
+    ///
+    /// Premiums credited to reserves and the LP share of flash fees advance the accumulator
+    /// as they are collected, spread over the liquidity last recorded here or by a lender; a
+    /// zero `fees` only updates that liquidity.
+    pub fn record_lp_fees(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        fees: U256,
+        total_liquidity: U256,
+    ) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if total_liquidity == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.fee_growth_liquidity.insert(pool_id, total_liquidity);
+        self.accrue_fee_growth(pool_id, fees)
+    }
+
+    /// Returns a pool's cumulative fees per unit of liquidity, WAD-scaled
+    pub fn get_fee_growth_global(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.fee_growth_global.get(pool_id)
+    }
+
+    /// Computes the fees owed to an LP since its fee growth checkpoint, rounded down
+    pub fn claimable_fees(
+        &self,
+        pool_id: FixedBytes<32>,
+        lp_liquidity: U256,
+        fee_growth_checkpoint: U256,
+    ) -> Result<U256, Error> {
+        let growth = self.fee_growth_global.get(pool_id)
+            .checked_sub(fee_growth_checkpoint)
+            .ok_or(Error::InvalidInput(InvalidInput{}))?; // Checkpoint from the future
+        math::mul_div(lp_liquidity, growth, U256::from(WAD))
+    }
+
+    /// Returns the liquidity a pool's collected fees are currently spread over (zero until
+    /// its admin or a lender reports it)
+    pub fn get_fee_growth_liquidity(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.fee_growth_liquidity.get(pool_id)
+    }
+
+    /// Calculates insurance fee for a trade
+    ///
+    /// `timestamp` must be within 15 seconds of the block time; pricing always uses the block
+    /// time itself.
+    pub fn calculate_insurance_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        current_price: U256,
+        timestamp: U256,
+    ) -> Result<U256, Error> {
+        let now = block_time();
+        let drift = math::abs(math::signed_sub(timestamp, now).map_err(|_| Error::InvalidInput(InvalidInput{}))?);
+        if drift > U256::from(TIMESTAMP_TOLERANCE) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.calculate_insurance_fee_now(pool_id, amount, total_liquidity, total_volume, current_price)
+    }
+
+    /// Derives a pool id as `keccak256(abi.encode(token0, token1, fee_tier))` with the two
+    /// tokens sorted ascending, so either order gives the same id
+    ///
+    /// This is the Uniswap v3 pool salt (`PoolAddress.computeAddress` in v3-periphery), whose
+    /// `uint24` fee encodes to the same 32-byte word; off-chain it is
+    /// `keccak256(AbiCoder.defaultAbiCoder().encode(["address", "address", "uint256"], [token0, token1, feeTier]))`
+    /// in ethers v6 with the tokens sorted first. Identical tokens or a zero address revert
+    /// with `InvalidInput`.
+    pub fn compute_pool_id(&self, token0: Address, token1: Address, fee_tier: U256) -> Result<FixedBytes<32>, Error> {
+        if token0 == Address::ZERO || token1 == Address::ZERO || token0 == token1 {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let (token0, token1) = if token0 < token1 { (token0, token1) } else { (token1, token0) };
+        Ok(keccak((token0, token1, fee_tier).abi_encode()))
+    }
+
+    /// Calculates insurance fee for a trade on the pool identified by its tokens and fee tier,
+    /// as `calculate_insurance_fee` on `compute_pool_id`
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_insurance_fee_for_tokens(
+        &self,
+        token0: Address,
+        token1: Address,
+        fee_tier: U256,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        current_price: U256,
+        timestamp: U256,
+    ) -> Result<U256, Error> {
+        let pool_id = self.compute_pool_id(token0, token1, fee_tier)?;
+        self.calculate_insurance_fee(pool_id, amount, total_liquidity, total_volume, current_price, timestamp)
+    }
+
+    /// Calculates the insurance fee for an exact-output trade, priced on the input it implies
+    ///
+    /// `current_price` is output per unit of input (WAD). The spot input `amount_out / price`
+    /// is grossed up for constant-product impact against `total_liquidity` as the input-side
+    /// reserve, `spot * liquidity / (liquidity - spot)`, rounding up, and the result is quoted
+    /// like `calculate_insurance_fee_now`. The quote therefore matches the exact-in quote for
+    /// that implied input exactly, and the exact-in quote for the spot input to within the
+    /// impact, a relative `spot / (liquidity - spot)` more input. A zero price, or an implied
+    /// input reaching the pool's liquidity, reverts with `InvalidInput`.
+    pub fn calculate_insurance_fee_exact_out(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount_out: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        current_price: U256,
+    ) -> Result<U256, Error> {
+        if current_price == U256::ZERO || amount_out == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let spot_in = math::mul_div_up(amount_out, U256::from(WAD), current_price)?;
+        if spot_in >= total_liquidity {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let amount_in = math::mul_div_up(spot_in, total_liquidity, total_liquidity - spot_in)?;
+        if amount_in > total_liquidity {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.calculate_insurance_fee_now(pool_id, amount_in, total_liquidity, total_volume, current_price)
+    }
+
+    /// Calculates the premium to cover an LP withdrawing `lp_notional` from a pool
+    ///
+    /// The IL realized as the price moved from `entry_price` to `current_price` replaces the
+    /// pool's stored IL, and the withdrawal's share of `total_liquidity` drives the size term,
+    /// with the pool's other risk multipliers applied as for a trade and volume left neutral.
+    /// An exit at the entry price therefore prices close to the base fee. Zero prices or
+    /// notional, or a withdrawal above the pool's liquidity, revert with `InvalidInput`.
+    pub fn calculate_exit_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        lp_notional: U256,
+        total_liquidity: U256,
+        entry_price: U256,
+        current_price: U256,
+    ) -> Result<U256, Error> {
+        if lp_notional == U256::ZERO
+            || lp_notional > total_liquidity
+            || entry_price == U256::ZERO
+            || current_price == U256::ZERO
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let realized_il = Self::price_path_il(entry_price, current_price)?;
+        self.price_insurance_fee(
+            pool_id,
+            IL_PRODUCT,
+            msg::sender(),
+            Some(realized_il),
+            lp_notional,
+            total_liquidity,
+            U256::ZERO,
+        )
+    }
+
+    /// Estimates a trade's insurance fee without ever reverting, returning (fee, saturated)
+    ///
+    /// Returns the live quote whenever it succeeds. Otherwise the internal formula is rerun
+    /// with every step saturating at `U256::MAX` instead of overflowing, skipping the quote's
+    /// gates (registration, data age) and any external formula, and `saturated` is set to flag
+    /// the result as an estimate. For dashboards and estimators only: payment paths always
+    /// price with the checked quote.
+    pub fn calculate_insurance_fee_saturating(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> (U256, bool) {
+        match self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume) {
+            Ok(fee) => (fee, false),
+            Err(_) => (self.saturating_insurance_fee(pool_id, amount, total_liquidity, total_volume).0, true),
+        }
+    }
+
+    /// Calculates insurance fee for a trade at the current block time
+    ///
+    /// Reverts with `PriceCircuitBreaker` when `current_price` trips the pool's breaker.
+    pub fn calculate_insurance_fee_now(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        current_price: U256,
+    ) -> Result<U256, Error> {
+        self.check_circuit_breaker(pool_id, current_price)?;
+        self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)
+    }
+
+    /// Calculates insurance fee for a trade in token units of the pool's token0 or token1
+    ///
+    /// The fee is computed in token0 (WAD) and, with `denominate_in_token1`, converted at
+    /// `current_price` (token1 per token0, WAD). Either way it is rescaled to that token's
+    /// configured decimals, every step rounding up. A zero price reverts in both modes.
+    pub fn calculate_insurance_fee_denominated(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        current_price: U256,
+        denominate_in_token1: bool,
+    ) -> Result<U256, Error> {
+        if current_price == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let fee = self.calculate_insurance_fee_now(pool_id, amount, total_liquidity, total_volume, current_price)?;
+        let (decimals0, decimals1) = self.get_token_decimals(pool_id);
+        if !denominate_in_token1 {
+            return math::from_wad_up(fee, decimals0);
+        }
+        math::from_wad_up(math::mul_div_up(fee, current_price, U256::from(WAD))?, decimals1)
+    }
+
+    /// Quotes the insurance fee a trade would pay if the pool's IL were `il_override`
+    ///
+    /// With `use_override` unset this is exactly the live quote; either way nothing is written.
+    pub fn simulate_insurance_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        il_override: U256,
+        use_override: bool,
+    ) -> Result<U256, Error> {
+        if use_override && il_override > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.price_insurance_fee(
+            pool_id,
+            IL_PRODUCT,
+            msg::sender(),
+            use_override.then_some(il_override),
+            amount,
+            total_liquidity,
+            total_volume,
+        )
+    }
+
+    /// Quotes a swap's insurance fee with a surcharge for the price impact its expected output
+    /// implies
+    ///
+    /// The execution price is compared with the pool's token1-per-token0 reference price:
+    /// `expected_amount_out / amount_in` (WAD) for a token0-in swap (`zero_for_one`), inverted
+    /// to `amount_in / expected_amount_out` for a token1-in swap. The shortfall against the
+    /// reference, in the direction that hurts the trader, as a fraction of the reference adds
+    /// `fee * 5 * impact`, with impact capped at 20%. Execution at or better than the
+    /// reference, or a pool without a reference price, quotes the standard fee.
+    pub fn calculate_insurance_fee_with_slippage(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount_in: U256,
+        expected_amount_out: U256,
+        zero_for_one: bool,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        if amount_in == U256::ZERO || expected_amount_out == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let fee = self.quote_insurance_fee(pool_id, amount_in, total_liquidity, total_volume)?;
+        let reference = self.get_reference_price(pool_id);
+        if reference == U256::ZERO {
+            return Ok(fee);
+        }
+        // Selling token0 hurts below the reference price; selling token1 hurts above it
+        let shortfall = if zero_for_one {
+            let execution = math::mul_div(expected_amount_out, U256::from(WAD), amount_in)?;
+            reference.saturating_sub(execution)
+        } else {
+            let execution = math::mul_div(amount_in, U256::from(WAD), expected_amount_out)?;
+            execution.saturating_sub(reference)
+        };
+        if shortfall == U256::ZERO {
+            return Ok(fee);
+        }
+        let impact = math::mul_div(shortfall, U256::from(WAD), reference)?
+            .min(U256::from(MAX_SLIPPAGE_IMPACT));
+        let surcharge = math::mul_div(
+            fee,
+            math::mul_div(impact, U256::from(SLIPPAGE_IMPACT_WEIGHT), U256::from(WAD))?,
+            U256::from(WAD),
+        )?;
+        fee.checked_add(surcharge).ok_or(Error::CalculationError(CalculationError{}))
+    }
+
+    /// Stores a named stress scenario: an IL shock multiplier (WAD, zero leaves IL live), a
+    /// utilization override for flash quotes (WAD, zero leaves it live), and a haircut on
+    /// volume in basis points
+    pub fn set_stress_scenario(
+        &mut self,
+        name: FixedBytes<32>,
+        il_shock: U256,
+        utilization: U256,
+        volume_haircut_bps: U256,
+    ) -> Result<(), Error> {
+        self.only_owner()?;
+        if il_shock > U256::from(MAX_RISK_WEIGHT)
+            || utilization > U256::from(WAD)
+            || volume_haircut_bps > U256::from(BPS_DENOMINATOR)
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let mut scenario = self.stress_scenarios.setter(name);
+        scenario.il_shock.set(il_shock);
+        scenario.utilization.set(utilization);
+        scenario.volume_haircut_bps.set(volume_haircut_bps);
+        evm::log(StressScenarioSet { name, il_shock, utilization, volume_haircut_bps });
+        Ok(())
+    }
+
+    /// Returns a stress scenario's (il_shock, utilization, volume_haircut_bps)
+    pub fn get_stress_scenario(&self, name: FixedBytes<32>) -> (U256, U256, U256) {
+        let scenario = self.stress_scenarios.get(name);
+        (scenario.il_shock.get(), scenario.utilization.get(), scenario.volume_haircut_bps.get())
+    }
+
+    /// Quotes a trade's insurance fee under a stress scenario applied to live data
+    ///
+    /// The pool's IL is multiplied by the shock (capped at 100%) and the volume cut by the
+    /// haircut. An unset scenario quotes exactly the live fee; nothing is written either way.
+    pub fn calculate_insurance_fee_stressed(
+        &self,
+        pool_id: FixedBytes<32>,
+        scenario: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        let (il_shock, _, volume_haircut_bps) = self.get_stress_scenario(scenario);
+        let il_override = if il_shock == U256::ZERO {
+            None
+        } else {
+            Some(math::mul_div(self.effective_il(pool_id), il_shock, U256::from(WAD))?.min(U256::from(WAD)))
+        };
+        let total_volume = math::mul_div(
+            total_volume,
+            U256::from(BPS_DENOMINATOR) - volume_haircut_bps,
+            U256::from(BPS_DENOMINATOR),
+        )?;
+        self.price_insurance_fee(
+            pool_id,
+            IL_PRODUCT,
+            msg::sender(),
+            il_override,
+            amount,
+            total_liquidity,
+            total_volume,
+        )
+    }
+
+    /// Quotes the insurance fee for a trade together with the address that should receive it
+    pub fn quote_with_recipient(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<(U256, Address), Error> {
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+        Ok((fee, self.get_fee_recipient(pool_id)?))
+    }
+
+    /// Sets the address a pool's fees are routed to; zero falls back to the global default
+    pub fn set_fee_recipient(&mut self, pool_id: FixedBytes<32>, recipient: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        self.fee_recipient.insert(pool_id, recipient);
+        evm::log(FeeRecipientSet { pool_id, recipient });
+        Ok(())
+    }
+
+    /// Sets the address fees are routed to for pools without their own recipient
+    pub fn set_default_fee_recipient(&mut self, recipient: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        let old_recipient = self.default_fee_recipient.get();
+        self.default_fee_recipient.set(recipient);
+        evm::log(DefaultFeeRecipientSet { old_recipient, new_recipient: recipient });
+        Ok(())
+    }
+
+    /// Returns the global default fee recipient (zero if unset)
+    pub fn default_fee_recipient(&self) -> Address {
+        self.default_fee_recipient.get()
+    }
+
+    /// Returns the address a pool's fees should be sent to, reverting if none is configured
+    pub fn get_fee_recipient(&self, pool_id: FixedBytes<32>) -> Result<Address, Error> {
+        let recipient = self.fee_recipient.get(pool_id);
+        if recipient != Address::ZERO {
+            return Ok(recipient);
+        }
+        let recipient = self.default_fee_recipient.get();
+        if recipient == Address::ZERO {
+            return Err(Error::NoFeeRecipient(NoFeeRecipient { pool_id }));
+        }
+        Ok(recipient)
+    }
+
+    /// Converts a WAD fee into settlement currency units with `target_decimals` decimals
+    ///
+    /// `conversion_rate_wad` is the settlement amount one unit of the fee's token is worth and
+    /// must lie within [1e-12, 1e12]. Both the conversion and the rescale round up, so the
+    /// settlement amount never undercharges the fee.
+    pub fn convert_fee(&self, fee: U256, conversion_rate_wad: U256, target_decimals: u8) -> Result<U256, Error> {
+        if conversion_rate_wad < U256::from(MIN_CONVERSION_RATE) || conversion_rate_wad > U256::from(MAX_CONVERSION_RATE) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let converted = math::mul_div_up(fee, conversion_rate_wad, U256::from(WAD))?;
+        math::from_wad_up(converted, target_decimals)
+    }
+
+    /// Calculates the insurance fee for a trade in settlement currency units, via `convert_fee`
+    pub fn calculate_insurance_fee_converted(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        conversion_rate_wad: U256,
+        target_decimals: u8,
+    ) -> Result<U256, Error> {
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+        self.convert_fee(fee, conversion_rate_wad, target_decimals)
+    }
+
+    /// Calculates the insurance fee for a trade in USD (WAD) at the pool's price feed
+    ///
+    /// The feed's answer is normalized from its own decimals to WAD, checked against the pool's
+    /// circuit breaker like any other price-aware quote, and applied as in `convert_fee`,
+    /// rounding up.
+    pub fn calculate_insurance_fee_usd(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        let price = self.oracle_price(pool_id)?;
+        self.check_circuit_breaker(pool_id, price)?;
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+        self.convert_fee(fee, price, 18)
+    }
+
+    /// Sets the USD price feed for a pool's token (zero removes it)
+    pub fn set_price_feed(&mut self, pool_id: FixedBytes<32>, feed: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        self.price_feed.insert(pool_id, feed);
+        evm::log(PriceFeedSet { pool_id, feed });
+        Ok(())
+    }
+
+    /// Returns a pool's USD price feed
+    pub fn get_price_feed(&self, pool_id: FixedBytes<32>) -> Address {
+        self.price_feed.get(pool_id)
+    }
+
+    /// Sets the oldest price feed answer accepted, in seconds (zero restores the default)
+    pub fn set_oracle_max_age(&mut self, max_age: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.oracle_max_age.set(max_age);
+        evm::log(OracleMaxAgeSet { max_age });
+        Ok(())
+    }
+
+    /// Returns the oldest price feed answer accepted, in seconds
+    pub fn oracle_max_age(&self) -> U256 {
+        let max_age = self.oracle_max_age.get();
+        if max_age == U256::ZERO {
+            U256::from(DEFAULT_ORACLE_MAX_AGE)
+        } else {
+            max_age
+        }
+    }
+
+    /// Calculates insurance fee for a trade against liquidity and volume read from the pool's
+    /// data provider
+    pub fn calculate_insurance_fee_from_provider(&self, pool_id: FixedBytes<32>, amount: U256) -> Result<U256, Error> {
+        let (total_liquidity, total_volume) = self.provider_state(pool_id)?;
+        self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)
+    }
+
+    /// Calculates flash loan fee for a borrowing from a pool whose liquidity is read from its
+    /// data provider
+    pub fn calculate_pool_flash_loan_fee_from_provider(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<U256, Error> {
+        let provider = self.data_provider(pool_id)?;
+        let total_liquidity = Self::provider_liquidity(&provider, pool_id)?;
+        self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history)
+    }
+
+    /// Registers the adapter a pool's liquidity and volume are read from (zero removes it)
+    pub fn set_data_provider(&mut self, pool_id: FixedBytes<32>, provider: Address) -> Result<(), Error> {
+        self.only_owner()?;
+        self.data_provider.insert(pool_id, provider);
+        evm::log(DataProviderSet { pool_id, provider });
+        Ok(())
+    }
+
+    /// Returns a pool's data provider
+    pub fn get_data_provider(&self, pool_id: FixedBytes<32>) -> Address {
+        self.data_provider.get(pool_id)
+    }
+
+    /// Calculates the fee for insuring a trade under a product
+    pub fn calculate_product_insurance_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.quote_insurance_fee_for(pool_id, product_id, msg::sender(), amount, total_liquidity, total_volume)
+    }
+
+    /// Calculates insurance fee for a trade whose inputs are in token units rather than WAD
+    ///
+    /// `amount`, `total_liquidity` and `total_volume` carry `amount_decimals` decimals and
+    /// `current_price` carries `price_decimals`; both must be at most 36. Everything is
+    /// rescaled to WAD (rounding down) and priced as `calculate_insurance_fee_now`. The fee is
+    /// a WAD rate like every other quote, so it is returned as is rather than in token units.
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_insurance_fee_units(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        current_price: U256,
+        amount_decimals: u8,
+        price_decimals: u8,
+    ) -> Result<U256, Error> {
+        self.calculate_insurance_fee_now(
+            pool_id,
+            math::to_wad(amount, amount_decimals)?,
+            math::to_wad(total_liquidity, amount_decimals)?,
+            math::to_wad(total_volume, amount_decimals)?,
+            math::to_wad(current_price, price_decimals)?,
+        )
+    }
+
+    /// Returns the largest trade whose insurance fee fits within `fee_budget`
+    ///
+    /// The fee only grows with amount, so this binary searches the full `U256` range in a
+    /// fixed 256 steps: the result's fee is within budget and one wei more would exceed it
+    /// (or overflow). Returns zero for a zero budget or when even a zero-size trade exceeds it,
+    /// and `U256::MAX` when the fee never does.
+    pub fn max_amount_for_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        fee_budget: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        if fee_budget == U256::ZERO
+            || self.quote_insurance_fee(pool_id, U256::ZERO, total_liquidity, total_volume)? > fee_budget
+        {
+            return Ok(U256::ZERO);
+        }
+        let mut low = U256::ZERO;
+        let mut high = U256::MAX;
+        for _ in 0..MAX_AMOUNT_SEARCH_STEPS {
+            if low == high {
+                break;
+            }
+            // Round the midpoint up so the search always makes progress
+            let mid = low + ((high - low) >> 1) + U256::from(1);
+            match self.quote_insurance_fee(pool_id, mid, total_liquidity, total_volume) {
+                Ok(fee) if fee <= fee_budget => low = mid,
+                _ => high = mid - U256::from(1),
+            }
+        }
+        Ok(low)
+    }
+
+    /// Returns the marginal insurance fee per unit of amount at `amount` (WAD)
+    ///
+    /// Computed as the discrete slope `(fee(amount + step) - fee(amount)) * 1e18 / step` with
+    /// `step = amount / 1000` (0.1%), never below 1 wei, so amounts under 1000 wei step by one.
+    /// When `amount + step` overflows or cannot be priced, the slope is taken backwards over
+    /// `[amount - step, amount]` instead.
+    pub fn get_fee_sensitivity(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        let step = (amount / U256::from(SENSITIVITY_STEP_DIVISOR)).max(U256::from(1));
+        let fee = self.quote_insurance_fee(pool_id, amount, total_liquidity, total_volume)?;
+        let forward = amount
+            .checked_add(step)
+            .and_then(|upper| self.quote_insurance_fee(pool_id, upper, total_liquidity, total_volume).ok());
+        let (low_fee, high_fee) = match forward {
+            Some(upper_fee) => (fee, upper_fee),
+            None => {
+                // step never exceeds a non-zero amount, and a zero amount cannot overflow forwards
+                let lower_fee = self.quote_insurance_fee(pool_id, amount - step, total_liquidity, total_volume)?;
+                (lower_fee, fee)
+            }
+        };
+        // The fee never falls as amount grows; saturate rather than revert if that ever breaks
+        math::mul_div(high_fee.saturating_sub(low_fee), U256::from(WAD), step)
+    }
+
+    /// Prices insurance across several correlated pools with a diversification discount
+    ///
+    /// Blends linearly between the root-sum-of-squares of the per-pool fees at zero
+    /// correlation and their plain sum at full correlation (1e18).
+    pub fn calculate_portfolio_fee(
+        &self,
+        pool_ids: Vec<FixedBytes<32>>,
+        notionals: Vec<U256>,
+        correlation_wad: U256,
+        total_liquidity: Vec<U256>,
+        total_volume: Vec<U256>,
+    ) -> Result<U256, Error> {
+        let legs = pool_ids.len();
+        if legs == 0
+            || notionals.len() != legs
+            || total_liquidity.len() != legs
+            || total_volume.len() != legs
+            || correlation_wad > U256::from(WAD)
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+
+        let mut fees = Vec::with_capacity(legs);
+        let mut sum = U256::ZERO;
+        for i in 0..legs {
+            let fee = self.quote_insurance_fee(pool_ids[i], notionals[i], total_liquidity[i], total_volume[i])?;
+            sum = sum
+                .checked_add(fee)
+                .ok_or(Error::CalculationError(CalculationError{}))?;
+            fees.push(fee);
+        }
+        if sum == U256::ZERO {
+            return Ok(U256::ZERO);
+        }
+
+        // Squares are taken of each fee's WAD share of the sum, so they stay within `legs` WAD
+        // however large the fees are: rss = sum * sqrt(sum of share²)
+        let mut sum_of_squares = U256::ZERO;
+        for fee in fees {
+            let share = math::mul_div(fee, U256::from(WAD), sum)?;
+            sum_of_squares += math::mul_div(share, share, U256::from(WAD))?;
+        }
+        // sqrt(sum of squares) never exceeds the sum, so the blend stays within [rss, sum]
+        let root_sum_of_squares = math::mul_div(sum, math::wad_sqrt(sum_of_squares)?, U256::from(WAD))?.min(sum);
+        let correlated_part = math::mul_div(sum - root_sum_of_squares, correlation_wad, U256::from(WAD))?;
+        Ok(root_sum_of_squares + correlated_part)
+    }
+
+    /// Prices a trade split across several pools, returning (total_fee, per-leg fees)
+    ///
+    /// Each leg is quoted exactly as a standalone trade on its own pool, and the total is the
+    /// plain sum of the legs. At most 16 legs are accepted.
+    pub fn calculate_split_insurance_fee(
+        &self,
+        pool_ids: Vec<FixedBytes<32>>,
+        leg_amounts: Vec<U256>,
+        leg_liquidity: Vec<U256>,
+        total_volume: Vec<U256>,
+    ) -> Result<(U256, Vec<U256>), Error> {
+        let legs = pool_ids.len();
+        if legs == 0
+            || legs > MAX_ROUTE_LEGS
+            || leg_amounts.len() != legs
+            || leg_liquidity.len() != legs
+            || total_volume.len() != legs
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+
+        let mut total = U256::ZERO;
+        let mut fees = Vec::with_capacity(legs);
+        for i in 0..legs {
+            let fee = self.quote_insurance_fee(pool_ids[i], leg_amounts[i], leg_liquidity[i], total_volume[i])?;
+            total = total
+                .checked_add(fee)
+                .ok_or(Error::CalculationError(CalculationError{}))?;
+            fees.push(fee);
+        }
+        Ok((total, fees))
+    }
+
+    /// Calculates flash loan fee for a borrowing
+    ///
+    /// `default_history` above the configured ceiling is priced at the ceiling.
+    pub fn calculate_flash_loan_fee(
+        &self,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<U256, Error> {
+        // Utilization is a WAD fraction; nothing past 100% is meaningful
+        if amount == U256::ZERO || utilization_rate > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        // Protocol keepers borrow for free once their request is known to be well-formed
+        if self.flash_fee_exempt.get(msg::sender()) {
+            return Ok(U256::ZERO);
+        }
+
+        let base_fee = U256::from(FLASH_BASE_FEE);
+
+        // Utilization multiplier: scales up fee when pool usage is high, kept in WAD (1.0 to 3.0)
+        let utilization_multiplier = utilization_rate
+            .checked_mul(U256::from(2))
+            .ok_or(Error::CalculationError(CalculationError{}))? // Amplify by 2x
+            .checked_add(U256::from(1_000_000_000_000_000_000u64))
+            .ok_or(Error::CalculationError(CalculationError{}))?; // Add baseline multiplier
+
+        // Liquidity multiplier: reduces fee when liquidity is high
+        let liquidity_multiplier = if total_liquidity > U256::ZERO {
+            U256::from(1_000_000_000_000_000_000u64)
+                .checked_div(total_liquidity.checked_add(U256::from(1_000_000_000_000_000_000u64))
+                .ok_or(Error::CalculationError(CalculationError{}))?)
+                .ok_or(Error::CalculationError(CalculationError{}))? // Adjust by available liquidity
+                .checked_add(U256::from(1_000_000_000_000_000_000u64))
+                .ok_or(Error::CalculationError(CalculationError{}))? // Baseline multiplier
+        } else {
+            U256::from(2_000_000_000_000_000_000u64) // Default multiplier 2.0 * 1e18
+        };
+
+        // Historical multiplier: default adjustment for past performance, clamped to the ceiling
+        let historical_multiplier = U256::from(1_000_000_000_000_000_000u64)
+            .checked_add(default_history.min(self.max_default_history()))
+            .ok_or(Error::CalculationError(CalculationError{}))?; // Add historical adjustment
+
+        // Final fee = base * utilization * liquidity * historical, scaled down for precision
+        let fee = base_fee
+            .checked_mul(utilization_multiplier)
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .checked_mul(liquidity_multiplier)
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .checked_mul(historical_multiplier)
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .checked_div(U256::from(1_000_000_000_000_000_000u64).pow(U256::from(3)))
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+
+        // Scale by the loan amount
+        let final_fee = fee
+            .checked_mul(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .checked_div(U256::from(1_000_000_000_000_000_000u64))
+            .ok_or(Error::CalculationError(CalculationError{}))?; // Scale fee by amount
+
+        Ok(final_fee)
+    }
+
+    /// Calculates flash loan fee for a borrowing from the pool identified by its tokens and fee
+    /// tier, as `calculate_pool_flash_loan_fee` on `compute_pool_id`
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_pool_flash_loan_fee_for_tokens(
+        &self,
+        token0: Address,
+        token1: Address,
+        fee_tier: U256,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<U256, Error> {
+        let pool_id = self.compute_pool_id(token0, token1, fee_tier)?;
+        self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history)
+    }
+
+    /// Estimates a flash loan's fee without ever reverting, returning (fee, saturated)
+    ///
+    /// Returns `calculate_flash_loan_fee` whenever it succeeds. Otherwise utilization is
+    /// clamped to 100% and each multiplication saturates at `U256::MAX`, with `saturated`
+    /// reporting whether anything was clamped. For dashboards and estimators only: payment
+    /// paths always price with the checked calculators.
+    pub fn calculate_flash_loan_fee_saturating(
+        &self,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> (U256, bool) {
+        if let Ok(fee) = self.calculate_flash_loan_fee(amount, total_liquidity, utilization_rate, default_history) {
+            return (fee, false);
+        }
+        if amount == U256::ZERO || self.flash_fee_exempt.get(msg::sender()) {
+            return (U256::ZERO, false);
+        }
+        let wad = U256::from(WAD);
+        let mut saturated = utilization_rate > wad;
+        let utilization_multiplier = utilization_rate.min(wad).saturating_mul(U256::from(2)).saturating_add(wad);
+        // Zero liquidity takes the checked calculator's default 2.0
+        let liquidity_multiplier = if total_liquidity > U256::ZERO {
+            wad / total_liquidity.saturating_add(wad) + wad
+        } else {
+            U256::from(2) * wad
+        };
+        let historical_multiplier = wad.saturating_add(default_history.min(self.max_default_history()));
+        let mut fee = U256::from(FLASH_BASE_FEE);
+        for (factor, denominator) in [
+            (utilization_multiplier, wad),
+            (liquidity_multiplier, wad),
+            (historical_multiplier, wad),
+            (amount, wad),
+        ] {
+            let (value, clamped) = math::saturating_mul_div(fee, factor, denominator);
+            fee = value;
+            saturated |= clamped;
+        }
+        (fee, saturated)
+    }
+
+    /// Calculates flash loan fee for a borrowing from a specific pool, applying its multiplier
+    ///
+    /// Flash amounts already recorded against the pool in this block count towards utilization
+    /// on top of `utilization_rate`.
+    pub fn calculate_pool_flash_loan_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<U256, Error> {
+        let already_borrowed = self.get_flash_borrowed_this_block(pool_id);
+        self.pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history, already_borrowed, false)
+    }
+
+    /// Returns what a borrower repays for a pool flash loan: `amount` plus the fee rounded up
+    ///
+    /// The fee part is never below `calculate_pool_flash_loan_fee` for the same inputs and at
+    /// most 1 wei above it.
+    pub fn flash_loan_repayment_amount(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<U256, Error> {
+        let already_borrowed = self.get_flash_borrowed_this_block(pool_id);
+        let fee = self.pool_flash_loan_fee(
+            pool_id,
+            amount,
+            total_liquidity,
+            utilization_rate,
+            default_history,
+            already_borrowed,
+            true,
+        )?;
+        amount.checked_add(fee).ok_or(Error::CalculationError(CalculationError{}))
+    }
+
+    /// Calculates a pool's flash loan fee as if `already_borrowed_this_block` had been recorded
+    /// against it earlier in the block, for simulating a sequence of loans
+    pub fn simulate_pool_flash_loan_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+        already_borrowed_this_block: U256,
+    ) -> Result<U256, Error> {
+        self.pool_flash_loan_fee(
+            pool_id,
+            amount,
+            total_liquidity,
+            utilization_rate,
+            default_history,
+            already_borrowed_this_block,
+            false,
+        )
+    }
+
+    /// Quotes a pool flash loan's fee under a stress scenario, pricing at the scenario's
+    /// utilization in place of `utilization_rate` and this block's earlier loans
+    ///
+    /// A scenario without a utilization override quotes exactly the live fee.
+    pub fn calculate_pool_flash_loan_fee_stressed(
+        &self,
+        pool_id: FixedBytes<32>,
+        scenario: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<U256, Error> {
+        let (_, utilization, _) = self.get_stress_scenario(scenario);
+        if utilization == U256::ZERO {
+            return self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history);
+        }
+        self.pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization, default_history, U256::ZERO, false)
+    }
+
+    /// Returns the flash amount recorded against a pool so far in the current block
+    pub fn get_flash_borrowed_this_block(&self, pool_id: FixedBytes<32>) -> U256 {
+        if self.flash_block.get(pool_id) == U256::from(block::number()) {
+            self.flash_borrowed_in_block.get(pool_id)
+        } else {
+            U256::ZERO
+        }
+    }
+
+    /// Calculates a pool's flash loan fee using its tracked default score as the default history
+    pub fn calculate_tracked_flash_loan_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+    ) -> Result<U256, Error> {
+        let default_history = self.default_score.get(pool_id);
+        self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history)
+    }
+
+    /// Calculates a pool's flash loan fee in settlement currency units, via `convert_fee`
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_pool_flash_loan_fee_converted(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+        conversion_rate_wad: U256,
+        target_decimals: u8,
+    ) -> Result<U256, Error> {
+        let fee = self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history)?;
+        self.convert_fee(fee, conversion_rate_wad, target_decimals)
+    }
+
+    /// Calculates a pool's flash loan fee split into its (lp_portion, insurance_portion)
+    ///
+    /// The insurance portion is the pool's insurance share of the fee rounded up, and the LP
+    /// portion is the remainder, so the two always sum to the full fee.
+    pub fn calculate_pool_flash_loan_fee_split(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<(U256, U256), Error> {
+        let fee = self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history)?;
+        self.split_flash_fee(pool_id, fee)
+    }
+
+    /// Records a flash loan's ETH fee, crediting the insurance portion to the pool's fund and
+    /// holding the LP portion for the pool's admin, returning (lp_portion, insurance_portion)
+    ///
+    /// Callable only by lender contracts. Any value above the fee is refunded.
+    #[payable]
+    pub fn record_flash_loan(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<(U256, U256), Error> {
+        self.only_lender()?;
+        let fee = self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization_rate, default_history)?;
+        Self::check_payment(fee)?;
+        let borrowed = self.get_flash_borrowed_this_block(pool_id)
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.flash_block.insert(pool_id, U256::from(block::number()));
+        self.flash_borrowed_in_block.insert(pool_id, borrowed);
+        let (lp_portion, insurance_portion) = self.split_flash_fee(pool_id, fee)?;
+        let cap = self.max_default_history();
+        if default_history > cap {
+            evm::log(DefaultHistoryClamped { pool_id, lender: msg::sender(), provided: default_history, cap });
+        }
+
+        let lp_fees = self.flash_lp_fees.get(pool_id)
+            .checked_add(lp_portion)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.flash_lp_fees.insert(pool_id, lp_fees);
+        if total_liquidity != U256::ZERO {
+            self.fee_growth_liquidity.insert(pool_id, total_liquidity);
+        }
+        self.accrue_fee_growth(pool_id, lp_portion)?;
+        self.credit_reserves(pool_id, insurance_portion)?;
+        self.record_recent_fee(pool_id, fee, amount);
+        Self::refund_excess(fee)?;
+        evm::log(FlashLoanRecorded {
+            pool_id,
+            lender: msg::sender(),
+            amount,
+            lp_portion,
+            insurance_portion,
+            epoch: self.parameter_epoch.get(),
+        });
+        Ok((lp_portion, insurance_portion))
+    }
+
+    /// Records a flash loan like `record_flash_loan` unless the caller already used `key`, in
+    /// which case the first call's split is returned, any attached value is refunded, and
+    /// nothing is recorded again
+    #[payable]
+    pub fn record_flash_loan_idempotent(
+        &mut self,
+        key: FixedBytes<32>,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<(U256, U256), Error> {
+        if let Some(split) = self.idempotent_replay(key) {
+            Self::refund_excess(U256::ZERO)?;
+            return Ok(split);
+        }
+        let (lp_portion, insurance_portion) =
+            self.record_flash_loan(pool_id, amount, total_liquidity, utilization_rate, default_history)?;
+        self.consume_idempotency_key(key, lp_portion, insurance_portion);
+        Ok((lp_portion, insurance_portion))
+    }
+
+    /// Sets the share of a pool's flash fees routed to its insurance fund, in basis points
+    pub fn set_insurance_share_bps(&mut self, pool_id: FixedBytes<32>, share_bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if share_bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.insurance_share_bps.insert(pool_id, share_bps);
+        evm::log(InsuranceShareSet { pool_id, share_bps });
+        Ok(())
+    }
+
+    /// Returns the share of a pool's flash fees routed to its insurance fund, in basis points
+    pub fn get_insurance_share_bps(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.insurance_share_bps.get(pool_id)
+    }
+
+    /// Returns the LP share of a pool's recorded flash fees awaiting withdrawal
+    pub fn get_flash_lp_fees(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.flash_lp_fees.get(pool_id)
+    }
+
+    /// Withdraws the LP share of a pool's recorded flash fees, callable by the pool's admin
+    pub fn withdraw_flash_lp_fees(&mut self, pool_id: FixedBytes<32>, to: Address, amount: U256) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if to == Address::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let available = self.flash_lp_fees.get(pool_id);
+        if amount > available {
+            return Err(Error::InsufficientBalance(InsufficientBalance { available, requested: amount }));
+        }
+        self.flash_lp_fees.insert(pool_id, available - amount);
+        transfer_eth(to, amount).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        evm::log(FlashLpFeesWithdrawn { pool_id, to, amount });
+        Ok(())
+    }
+
+    /// Calculates a pool's flash loan fee at spot utilization blended with its time-weighted
+    /// utilization over the configured window
+    ///
+    /// The blend is `twu * weight_bps / 10_000 + spot * (10_000 - weight_bps) / 10_000`. Pools
+    /// without checkpoints price at spot.
+    pub fn calculate_time_weighted_flash_loan_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+    ) -> Result<U256, Error> {
+        if utilization_rate > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let utilization = match self.time_weighted_utilization(pool_id, self.effective_twu_window()) {
+            Some(twu) => {
+                let weight = self.effective_twu_weight_bps();
+                math::mul_div(twu, weight, U256::from(BPS_DENOMINATOR))?
+                    + math::mul_div(utilization_rate, U256::from(BPS_DENOMINATOR) - weight, U256::from(BPS_DENOMINATOR))?
+            }
+            None => utilization_rate,
+        };
+        self.calculate_pool_flash_loan_fee(pool_id, amount, total_liquidity, utilization, default_history)
+    }
+
+    /// Records a pool's current utilization (WAD fraction) and advances its running total
+    pub fn checkpoint_utilization(&mut self, pool_id: FixedBytes<32>, utilization: U256) -> Result<(), Error> {
+        self.only_lender()?;
+        if utilization > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let now = block_time();
+        let count = self.utilization_checkpoint_count.get(pool_id);
+        let cumulative = if count == U256::ZERO {
+            U256::ZERO
+        } else {
+            self.cumulative_utilization_at(pool_id, count - U256::from(1), now)?
+        };
+
+        let slot = count % U256::from(UTILIZATION_CHECKPOINTS);
+        let mut checkpoints = self.utilization_checkpoints.setter(pool_id);
+        let mut checkpoint = checkpoints.setter(slot);
+        checkpoint.timestamp.set(now);
+        checkpoint.utilization.set(utilization);
+        checkpoint.cumulative.set(cumulative);
+        self.utilization_checkpoint_count.insert(pool_id, count + U256::from(1));
+        evm::log(UtilizationCheckpointed { pool_id, lender: msg::sender(), utilization, cumulative });
+        Ok(())
+    }
+
+    /// Returns a pool's average utilization over the last `window` seconds (WAD)
+    ///
+    /// A window reaching past the oldest retained checkpoint averages from that checkpoint
+    /// instead. Reverts for a zero window or a pool with no checkpoints.
+    pub fn get_time_weighted_utilization(&self, pool_id: FixedBytes<32>, window: U256) -> Result<U256, Error> {
+        if window == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.time_weighted_utilization(pool_id, window).ok_or(Error::InvalidInput(InvalidInput{}))
+    }
+
+    /// Configures the time-weighted utilization window in seconds (zero restores the default)
+    /// and its blend weight against spot in basis points (zero prices off spot alone)
+    pub fn set_twu_params(&mut self, window: U256, weight_bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if weight_bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.twu_window.set(window);
+        self.twu_weight_bps.set(weight_bps);
+        self.twu_weight_set.set(true);
+        evm::log(TwuParamsSet { window, weight_bps });
+        Ok(())
+    }
+
+    /// Returns the effective (window, weight_bps) time-weighted utilization parameters
+    pub fn get_twu_params(&self) -> (U256, U256) {
+        (self.effective_twu_window(), self.effective_twu_weight_bps())
+    }
+
+    /// Sets the ceiling on the default history flash fees are priced with (WAD, zero restores
+    /// the default)
+    pub fn set_max_default_history(&mut self, cap: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        self.max_default_history.set(cap);
+        evm::log(MaxDefaultHistorySet { cap });
+        self.snapshot_parameters();
+        Ok(())
+    }
+
+    /// Returns the ceiling on the default history flash fees are priced with
+    pub fn max_default_history(&self) -> U256 {
+        let cap = self.max_default_history.get();
+        if cap == U256::ZERO {
+            U256::from(DEFAULT_MAX_DEFAULT_HISTORY)
+        } else {
+            cap
+        }
+    }
+
+    /// Grants or revokes a lender contract's right to report flash loan outcomes
+    pub fn set_lender(&mut self, lender: Address, authorized: bool) -> Result<(), Error> {
+        self.only_owner()?;
+        self.lenders.insert(lender, authorized);
+        evm::log(LenderSet { lender, authorized });
+        Ok(())
+    }
+
+    /// Returns whether an address may report flash loan outcomes
+    pub fn is_lender(&self, lender: Address) -> bool {
+        self.lenders.get(lender)
+    }
+
+    /// Configures the default score ceiling (WAD) and the share each clean repayment decays it
+    /// by, in basis points; zero restores either default
+    pub fn set_default_score_params(&mut self, max_score: U256, decay_bps: U256) -> Result<(), Error> {
+        self.only_owner()?;
+        if decay_bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        self.max_default_score.set(max_score);
+        self.repayment_decay_bps.set(decay_bps);
+        evm::log(DefaultScoreParamsSet { max_score, decay_bps });
+        Ok(())
+    }
+
+    /// Returns the effective (max_score, decay_bps) default score parameters
+    pub fn get_default_score_params(&self) -> (U256, U256) {
+        (self.effective_max_default_score(), self.effective_repayment_decay_bps())
+    }
+
+    /// Returns a pool's tracked default score (WAD)
+    pub fn get_default_score(&self, pool_id: FixedBytes<32>) -> U256 {
+        self.default_score.get(pool_id)
+    }
+
+    /// Records a flash loan default on a pool, raising its score by `severity` up to the ceiling
+    pub fn record_default(&mut self, pool_id: FixedBytes<32>, severity: U256) -> Result<U256, Error> {
+        self.only_lender()?;
+        if severity == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let score = self.default_score.get(pool_id)
+            .saturating_add(severity)
+            .min(self.effective_max_default_score());
+        self.default_score.insert(pool_id, score);
+        evm::log(DefaultRecorded { pool_id, lender: msg::sender(), severity, score });
+        Ok(score)
+    }
+
+    /// Records a clean flash loan repayment on a pool, decaying its score
+    ///
+    /// The remaining score is rounded down, so repeated repayments always reach zero.
+    pub fn record_repayment(&mut self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        self.only_lender()?;
+        let kept_bps = U256::from(BPS_DENOMINATOR) - self.effective_repayment_decay_bps();
+        let score = math::mul_div(self.default_score.get(pool_id), kept_bps, U256::from(BPS_DENOMINATOR))?;
+        self.default_score.insert(pool_id, score);
+        evm::log(RepaymentRecorded { pool_id, lender: msg::sender(), score });
+        Ok(score)
+    }
+}
+
+impl InsuranceCalculator {
+    /// Reverts unless the caller is the owner
+    fn only_owner(&self) -> Result<(), Error> {
+        if msg::sender() != self.owner.get() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        Ok(())
+    }
+
+    /// Opens a new parameter epoch, appending each bundle parameter whose value differs from
+    /// its last recorded one to that parameter's history
+    ///
+    /// Single-parameter setters log their own event first; `ParametersUpdated` then carries
+    /// the whole bundle.
+    fn snapshot_parameters(&mut self) -> U256 {
+        let (
+            protocol_fee_bps,
+            cancellation_penalty_bps,
+            target_reserve_ratio,
+            skim_incentive_bps,
+            stale_threshold,
+            unrated_default_il,
+            max_il_deviation_bps,
+            quote_validity,
+            max_effective_il,
+            max_default_history,
+            grace_duration,
+            grace_factor,
+        ) = self.get_parameters();
+        let epoch = self.parameter_epoch.get() + U256::from(1);
+        self.parameter_epoch.set(epoch);
+        self.parameter_epoch_started_at.insert(epoch, block_time());
+
+        let values = [
+            protocol_fee_bps,
+            cancellation_penalty_bps,
+            target_reserve_ratio,
+            skim_incentive_bps,
+            stale_threshold,
+            unrated_default_il,
+            max_il_deviation_bps,
+            quote_validity,
+            max_effective_il,
+            max_default_history,
+            grace_duration,
+            grace_factor,
+        ];
+        for (index, value) in values.into_iter().enumerate() {
+            let mut history = self.parameter_history.setter(U256::from(index));
+            // Every parameter reads zero before its first recorded change
+            let last = history.values.len()
+                .checked_sub(1)
+                .and_then(|latest| history.values.get(latest))
+                .unwrap_or_default();
+            if value != last {
+                history.epochs.push(epoch);
+                history.values.push(value);
+            }
+        }
+
+        evm::log(ParametersUpdated {
+            epoch,
+            protocol_fee_bps,
+            cancellation_penalty_bps,
+            target_reserve_ratio,
+            skim_incentive_bps,
+            stale_threshold,
+            unrated_default_il,
+            max_il_deviation_bps,
+            quote_validity,
+            max_effective_il,
+            max_default_history,
+            grace_duration,
+            grace_factor,
+        });
+        epoch
+    }
+
+    /// Returns the value bundle parameter `index` held during `epoch`: its last recorded change
+    /// at or before the epoch, found by binary search, or zero if it had none yet
+    fn parameter_at_epoch(&self, index: usize, epoch: U256) -> U256 {
+        let history = self.parameter_history.get(U256::from(index));
+        let mut low = 0;
+        let mut high = history.epochs.len();
+        while low < high {
+            let mid = (low + high) / 2;
+            if history.epochs.get(mid).unwrap_or_default() <= epoch {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low.checked_sub(1)
+            .and_then(|latest| history.values.get(latest))
+            .unwrap_or_default()
+    }
+
+    /// Reverts unless the caller is the emergency admin
+    fn only_emergency_admin(&self) -> Result<(), Error> {
+        let admin = self.emergency_admin.get();
+        if admin == Address::ZERO || msg::sender() != admin {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        Ok(())
+    }
+
+    /// Reverts unless the caller is the owner or the pool's operator
+    fn only_pool_admin(&self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        let caller = msg::sender();
+        if caller != self.owner.get() && !self.is_pool_operator(pool_id, caller) {
+            return Err(Error::UnauthorizedForPool(UnauthorizedForPool { caller, pool_id }));
+        }
+        Ok(())
+    }
+
+    /// Reverts unless the caller is the owner, the pool's operator or an authorized updater
+    fn only_updater(&self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        let caller = msg::sender();
+        if !self.is_updater_for(pool_id, caller) {
+            return Err(Error::UnauthorizedForPool(UnauthorizedForPool { caller, pool_id }));
+        }
+        Ok(())
+    }
+
+    /// Reverts unless the caller is a registered lender contract
+    fn only_lender(&self) -> Result<(), Error> {
+        if !self.lenders.get(msg::sender()) {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        Ok(())
+    }
+
+    /// Returns the default score ceiling, or the default when unset
+    fn effective_max_default_score(&self) -> U256 {
+        let max_score = self.max_default_score.get();
+        if max_score == U256::ZERO {
+            U256::from(DEFAULT_MAX_DEFAULT_SCORE)
+        } else {
+            max_score
+        }
+    }
+
+    /// Returns the per-repayment score decay, or the default when unset
+    fn effective_repayment_decay_bps(&self) -> U256 {
+        let bps = self.repayment_decay_bps.get();
+        if bps == U256::ZERO {
+            U256::from(DEFAULT_REPAYMENT_DECAY_BPS)
+        } else {
+            bps
+        }
+    }
+
+    /// Prices a pool flash loan with `already_borrowed` added to the supplied utilization,
+    /// rounding only the final surge scaling up when `round_up` is set
+    #[allow(clippy::too_many_arguments)]
+    fn pool_flash_loan_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        utilization_rate: U256,
+        default_history: U256,
+        already_borrowed: U256,
+        round_up: bool,
+    ) -> Result<U256, Error> {
+        self.check_registration(pool_id)?;
+        if total_liquidity == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let cap = self.max_flash_amount.get(pool_id);
+        if cap != U256::ZERO && amount > cap {
+            return Err(Error::FlashLoanCapExceeded(FlashLoanCapExceeded { cap, requested: amount }));
+        }
+        // Earlier loans this block are drawn but not yet reflected in the caller's figure
+        let utilization_rate = utilization_rate
+            .checked_add(math::mul_div(already_borrowed, U256::from(WAD), total_liquidity)?)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        // Utilization once this loan is drawn; nothing past 100% can be lent
+        let post_loan_utilization = utilization_rate
+            .checked_add(math::mul_div(amount, U256::from(WAD), total_liquidity)?)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        if post_loan_utilization > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+
+        let mut fee = self.calculate_flash_loan_fee(amount, total_liquidity, utilization_rate, default_history)?;
+        let multiplier = self.default_flash_fee_multiplier.get(pool_id);
+        invariant!(multiplier <= U256::from(MAX_FLASH_FEE_MULTIPLIER), INVARIANT_MULTIPLIER_BOUNDS);
+        if multiplier != U256::ZERO {
+            fee = math::mul_div(fee, multiplier, U256::from(WAD))?;
+        }
+        let surge = self.surge_factor(pool_id, post_loan_utilization)?;
+        invariant!(
+            surge >= U256::from(WAD) && surge <= U256::from(MAX_SURGE_MULTIPLIER),
+            INVARIANT_MULTIPLIER_BOUNDS
+        );
+        let fee = if round_up {
+            math::mul_div_up(fee, surge, U256::from(WAD))?
+        } else {
+            math::mul_div(fee, surge, U256::from(WAD))?
+        };
+        invariant!(fee <= amount, INVARIANT_FEE_EXCEEDS_AMOUNT);
+        Ok(fee)
+    }
+
+    /// Bounds a raw swap fee's rate per unit of `amount` to within `max_change_bps` per elapsed
+    /// block of the pool's last recorded rate, returning the (fee, rate) to charge and record
+    ///
+    /// Smoothing the rate rather than the fee keeps it independent of trade size, so a large
+    /// swap is never priced at a small one's fee. Pools without smoothing, or with nothing
+    /// recorded yet, charge the raw fee.
+    fn smooth_fee(&self, pool_id: FixedBytes<32>, raw_fee: U256, amount: U256) -> Result<(U256, U256), Error> {
+        if amount == U256::ZERO {
+            return Ok((raw_fee, U256::ZERO));
+        }
+        let raw_rate = math::mul_div(raw_fee, U256::from(WAD), amount)?;
+        let max_change_bps = self.fee_smoothing_bps.get(pool_id);
+        let last_block = self.last_recorded_fee_block.get(pool_id);
+        let last_rate = self.last_recorded_fee_rate.get(pool_id);
+        // A zero last rate (a fee holiday) has no scale to bound against
+        if max_change_bps == U256::ZERO || last_block == U256::ZERO || last_rate == U256::ZERO {
+            return Ok((raw_fee, raw_rate));
+        }
+        let blocks = U256::from(block::number()).saturating_sub(last_block);
+        let step = math::mul_div(last_rate, max_change_bps.saturating_mul(blocks), U256::from(BPS_DENOMINATOR))?;
+        let rate = raw_rate.clamp(last_rate.saturating_sub(step), last_rate.saturating_add(step));
+        if rate == raw_rate {
+            return Ok((raw_fee, raw_rate));
+        }
+        Ok((math::mul_div(amount, rate, U256::from(WAD))?, rate))
+    }
+
+    /// Appends a charged fee to the pool's recent-fee ring, overwriting the oldest when full
+    fn record_recent_fee(&mut self, pool_id: FixedBytes<32>, fee: U256, amount: U256) {
+        let count = self.recent_fee_count.get(pool_id);
+        let mut records = self.recent_fees.setter(pool_id);
+        let mut record = records.setter(count % U256::from(RECENT_FEES_CAPACITY));
+        record.fee.set(fee);
+        record.amount.set(amount);
+        record.timestamp.set(block_time());
+        self.recent_fee_count.insert(pool_id, count + U256::from(1));
+    }
+
+    /// Returns the stored result of the caller's earlier call under `key`, logging the replay
+    fn idempotent_replay(&self, key: FixedBytes<32>) -> Option<(U256, U256)> {
+        let caller = msg::sender();
+        let results = self.idempotent_results.get(caller);
+        let result = results.get(key);
+        if !result.consumed.get() {
+            return None;
+        }
+        evm::log(IdempotentReplay { caller, key });
+        Some((result.first.get(), result.second.get()))
+    }
+
+    /// Marks `key` consumed for the caller, keeping the call's result for replays
+    fn consume_idempotency_key(&mut self, key: FixedBytes<32>, first: U256, second: U256) {
+        let mut results = self.idempotent_results.setter(msg::sender());
+        let mut result = results.setter(key);
+        result.consumed.set(true);
+        result.first.set(first);
+        result.second.set(second);
+    }
+
+    /// Splits a flash fee into (lp_portion, insurance_portion), rounding in the fund's favour
+    fn split_flash_fee(&self, pool_id: FixedBytes<32>, fee: U256) -> Result<(U256, U256), Error> {
+        let share_bps = self.insurance_share_bps.get(pool_id);
+        invariant!(share_bps <= U256::from(BPS_DENOMINATOR), INVARIANT_MULTIPLIER_BOUNDS);
+        let lp_portion = math::mul_div(fee, U256::from(BPS_DENOMINATOR) - share_bps, U256::from(BPS_DENOMINATOR))?;
+        let insurance_portion = fee - lp_portion;
+        invariant!(lp_portion.checked_add(insurance_portion) == Some(fee), INVARIANT_SPLIT_MISMATCH);
+        Ok((lp_portion, insurance_portion))
+    }
+
+    /// Returns the time-weighted utilization window, or the default when unset
+    fn effective_twu_window(&self) -> U256 {
+        let window = self.twu_window.get();
+        if window == U256::ZERO {
+            U256::from(DEFAULT_TWU_WINDOW)
+        } else {
+            window
+        }
+    }
+
+    /// Returns the time-weighted utilization blend weight, or the default until one is set
+    fn effective_twu_weight_bps(&self) -> U256 {
+        if self.twu_weight_set.get() {
+            self.twu_weight_bps.get()
+        } else {
+            U256::from(DEFAULT_TWU_WEIGHT_BPS)
+        }
+    }
+
+    /// Returns a pool's utilization-seconds total at `at`, extrapolated from checkpoint `index`
+    fn cumulative_utilization_at(&self, pool_id: FixedBytes<32>, index: U256, at: U256) -> Result<U256, Error> {
+        let checkpoints = self.utilization_checkpoints.get(pool_id);
+        let checkpoint = checkpoints.get(index % U256::from(UTILIZATION_CHECKPOINTS));
+        let elapsed = at.saturating_sub(checkpoint.timestamp.get());
+        checkpoint.utilization.get()
+            .checked_mul(elapsed)
+            .and_then(|accrued| accrued.checked_add(checkpoint.cumulative.get()))
+            .ok_or(Error::CalculationError(CalculationError{}))
+    }
+
+    /// Returns a pool's average utilization over the last `window` seconds, or `None` without
+    /// checkpoints
+    ///
+    /// Walks the ring from newest to oldest for the checkpoint in force at `now - window`;
+    /// when history is shorter, averages from the oldest checkpoint, and when that is also
+    /// the current second, returns the latest utilization.
+    fn time_weighted_utilization(&self, pool_id: FixedBytes<32>, window: U256) -> Option<U256> {
+        let count = self.utilization_checkpoint_count.get(pool_id);
+        if count == U256::ZERO {
+            return None;
+        }
+        let now = block_time();
+        let newest = count - U256::from(1);
+        let cumulative_now = self.cumulative_utilization_at(pool_id, newest, now).ok()?;
+        let retained = count.min(U256::from(UTILIZATION_CHECKPOINTS));
+        let target = now.saturating_sub(window);
+
+        let checkpoints = self.utilization_checkpoints.get(pool_id);
+        let mut index = newest;
+        for _ in 1..retained.to::<u64>() {
+            if checkpoints.get(index % U256::from(UTILIZATION_CHECKPOINTS)).timestamp.get() <= target {
+                break;
+            }
+            index -= U256::from(1);
+        }
+        let start = checkpoints.get(index % U256::from(UTILIZATION_CHECKPOINTS)).timestamp.get().max(target);
+        if start >= now {
+            return Some(checkpoints.get(newest % U256::from(UTILIZATION_CHECKPOINTS)).utilization.get());
+        }
+        let cumulative_start = self.cumulative_utilization_at(pool_id, index, start).ok()?;
+        Some((cumulative_now - cumulative_start) / (now - start))
+    }
+
+    /// Returns whether `account` is the owner, the pool's operator or an authorized updater
+    ///
+    /// Global updaters only cover pools that have no operator yet, so assigning an operator hands
+    /// that pool's IL over to it alone.
+    fn is_updater_for(&self, pool_id: FixedBytes<32>, account: Address) -> bool {
+        if account == self.owner.get() {
+            return true;
+        }
+        if self.pool_operator.get(pool_id) != Address::ZERO {
+            return self.is_pool_operator(pool_id, account);
+        }
+        self.authorized_updaters.get(account)
+    }
+
+    /// Recovers the signer of a 65-byte `r || s || v` signature, or `None` if it is invalid
+    fn recover_signer(digest: FixedBytes<32>, signature: &[u8]) -> Option<Address> {
+        if signature.len() != 65 {
+            return None;
+        }
+        let v = match signature[64] {
+            v @ (27 | 28) => v,
+            v @ (0 | 1) => v + 27,
+            _ => return None,
+        };
+        let mut input = [0u8; 128];
+        input[..32].copy_from_slice(digest.as_slice());
+        input[63] = v;
+        input[64..].copy_from_slice(&signature[..64]);
+        let output = RawCall::new_static().call(ECRECOVER, &input).ok()?;
+        // The precompile returns nothing for an unrecoverable signature
+        if output.len() != 32 {
+            return None;
+        }
+        let signer = Address::from_slice(&output[12..]);
+        (signer != Address::ZERO).then_some(signer)
+    }
+
+    /// Returns whether `caller` is the assigned operator of the pool
+    fn is_pool_operator(&self, pool_id: FixedBytes<32>, caller: Address) -> bool {
+        let operator = self.pool_operator.get(pool_id);
+        operator != Address::ZERO && operator == caller
+    }
+
+    /// Appends a pool to the enumerable index the first time it is registered
+    fn list_pool(&mut self, pool_id: FixedBytes<32>) {
+        if !self.pool_listed.get(pool_id) {
+            self.pool_listed.insert(pool_id, true);
+            self.all_pools.push(pool_id);
+        }
+    }
+
+    /// Reverts unless a surge configuration is disabled (zero multiplier) or has its threshold
+    /// below 100% and its multiplier between 1x and 10x
+    fn check_surge_params(threshold: U256, multiplier: U256) -> Result<(), Error> {
+        let disabled = multiplier == U256::ZERO;
+        if !disabled
+            && (threshold >= U256::from(WAD)
+                || multiplier < U256::from(WAD)
+                || multiplier > U256::from(MAX_SURGE_MULTIPLIER))
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        Ok(())
+    }
+
+    /// Returns the surge factor (WAD) for a utilization, 1.0 at or below the threshold
+    fn surge_factor(&self, pool_id: FixedBytes<32>, utilization: U256) -> Result<U256, Error> {
+        let multiplier = self.surge_multiplier.get(pool_id);
+        let threshold = self.surge_threshold.get(pool_id);
+        if multiplier == U256::ZERO || utilization <= threshold {
+            return Ok(U256::from(WAD));
+        }
+        // Linear ramp from 1x at the threshold to the full multiplier at 100% utilization
+        let ramp = math::mul_div(
+            multiplier - U256::from(WAD),
+            utilization - threshold,
+            U256::from(WAD) - threshold,
+        )?;
+        Ok(U256::from(WAD) + ramp)
+    }
+
+    /// Reverts for unregistered pools when registration is required, and for deactivated pools
+    fn check_registration(&self, pool_id: FixedBytes<32>) -> Result<(), Error> {
+        if self.require_registration.get() && !self.pools.get(pool_id).registered.get() {
+            return Err(Error::PoolNotRegistered(PoolNotRegistered { pool_id }));
+        }
+        if self.pool_deactivated.get(pool_id) {
+            return Err(Error::PoolInactive(PoolInactive { pool_id }));
+        }
+        Ok(())
+    }
+
+    /// Flips a registered pool's active flag on behalf of its admin
+    fn set_pool_active(&mut self, pool_id: FixedBytes<32>, active: bool) -> Result<(), Error> {
+        self.only_pool_admin(pool_id)?;
+        if !self.pools.get(pool_id).registered.get() {
+            return Err(Error::PoolNotRegistered(PoolNotRegistered { pool_id }));
+        }
+        self.pool_deactivated.insert(pool_id, !active);
+        evm::log(PoolActiveSet { pool_id, active });
+        Ok(())
+    }
+
+    /// Reverts if `current_price` deviates from the pool's reference by more than its breaker
+    /// allows; pools without a breaker, a bypass or any observations always pass
+    fn check_circuit_breaker(&self, pool_id: FixedBytes<32>, current_price: U256) -> Result<(), Error> {
+        let max_deviation_bps = self.circuit_breaker_bps.get(pool_id);
+        if max_deviation_bps == U256::ZERO || self.circuit_breaker_bypassed.get(pool_id) {
+            return Ok(());
+        }
+        let reference_price = self.get_reference_price(pool_id);
+        if reference_price == U256::ZERO {
+            return Ok(());
+        }
+        let deviation = math::abs(math::signed_sub(current_price, reference_price).map_err(|_| Error::InvalidInput(InvalidInput{}))?);
+        if math::mul_div(deviation, U256::from(BPS_DENOMINATOR), reference_price)? > max_deviation_bps {
+            return Err(Error::PriceCircuitBreaker(PriceCircuitBreaker { current_price, reference_price }));
+        }
+        Ok(())
+    }
+
+    /// Returns the pool's base fee override, or the default when unset
+    fn effective_base_fee(&self, pool_id: FixedBytes<32>) -> U256 {
+        let base_fee = self.base_fee.get(pool_id);
+        if base_fee == U256::ZERO {
+            U256::from(DEFAULT_INSURANCE_BASE_FEE)
+        } else {
+            base_fee
+        }
+    }
+
+    /// Returns the pool's IL weight override, or the default when unset
+    fn effective_il_weight(&self, pool_id: FixedBytes<32>) -> U256 {
+        let il_weight = self.il_weight.get(pool_id);
+        if il_weight == U256::ZERO {
+            U256::from(DEFAULT_IL_WEIGHT)
+        } else {
+            il_weight
+        }
+    }
+
+    /// Reverts with the seconds remaining if a non-owner write lands before the pool's minimum
+    /// update interval has passed since its last updater write; a pool's first such write is exempt
+    fn check_update_interval(&self, pool_id: FixedBytes<32>, updater: Address) -> Result<(), Error> {
+        let last_update = self.last_updater_write.get(pool_id);
+        if updater == self.owner.get() || last_update == U256::ZERO {
+            return Ok(());
+        }
+        let elapsed = block_time().saturating_sub(last_update);
+        let interval = self.min_il_update_interval.get();
+        if elapsed < interval {
+            return Err(Error::TooFrequent(TooFrequent { remaining: interval - elapsed }));
+        }
+        Ok(())
+    }
+
+    /// Reverts if `proposed` moves a pool's stored IL by more than the deviation band
+    ///
+    /// A pool's first write is exempt. Once written, a zero IL can only move via the owner's
+    /// forced update, since any change from zero is an unbounded relative move.
+    fn check_il_deviation(&self, pool_id: FixedBytes<32>, proposed: U256) -> Result<(), Error> {
+        if self.last_il_update.get(pool_id) == U256::ZERO {
+            return Ok(());
+        }
+        let old_value = self.historical_il.get(pool_id);
+        // Rises and falls share one path: only the size of the move matters
+        let delta = math::abs(math::signed_sub(proposed, old_value)?);
+        // IL is bounded by WAD; only an extreme configured band can saturate the right side
+        if delta * U256::from(BPS_DENOMINATOR) > old_value.saturating_mul(self.max_il_deviation_bps()) {
+            return Err(Error::DeviationTooLarge(DeviationTooLarge { old_value, proposed }));
+        }
+        Ok(())
+    }
+
+    /// Pulls `proposed` back inside the deviation band around a pool's stored IL, the most a
+    /// write that passes `check_il_deviation` could move it
+    fn clamp_il_deviation(&self, pool_id: FixedBytes<32>, proposed: U256) -> U256 {
+        if self.last_il_update.get(pool_id) == U256::ZERO {
+            return proposed;
+        }
+        let old_value = self.historical_il.get(pool_id);
+        let band = old_value.saturating_mul(self.max_il_deviation_bps()) / U256::from(BPS_DENOMINATOR);
+        proposed.clamp(old_value.saturating_sub(band), old_value.saturating_add(band))
+    }
+
+    /// Writes an updater's IL after the interval and deviation guards, crediting its stats
+    ///
+    /// Updates from anyone but the owner are staged behind the dispute window when one is set,
+    /// replacing any update already pending but keeping its clock, so repeated staging cannot
+    /// hold off maturity. Staged writes count toward the pool's minimum update interval.
+    fn guarded_write(&mut self, pool_id: FixedBytes<32>, il: U256, sequence: U256, updater: Address) -> Result<(), Error> {
+        self.promote_pending(pool_id);
+        self.check_updater_bond(updater)?;
+        self.check_update_interval(pool_id, updater)?;
+        self.check_il_deviation(pool_id, il)?;
+        self.last_updater_write.insert(pool_id, block_time());
+        let window = self.il_dispute_window.get();
+        if window == U256::ZERO || updater == self.owner.get() {
+            self.write_historical_il(pool_id, il, sequence, updater)?;
+            self.count_il_observation(pool_id);
+        } else {
+            self.stage_historical_il(pool_id, il, sequence, updater, window)?;
+        }
+
+        let stats = self.updater_stats.get(updater);
+        let accepted = stats.accepted.get() + U256::from(1);
+        let mut pools_touched = stats.pools_touched.get();
+        if !self.updater_pools.get(updater).get(pool_id) {
+            self.updater_pools.setter(updater).insert(pool_id, true);
+            pools_touched += U256::from(1);
+        }
+        let mut stats = self.updater_stats.setter(updater);
+        stats.accepted.set(accepted);
+        stats.last_update.set(block_time());
+        stats.pools_touched.set(pools_touched);
+        Ok(())
+    }
+
+    /// Reverts unless an authorized updater's bond meets the minimum; only the owner writes
+    /// unbonded
+    fn check_updater_bond(&self, updater: Address) -> Result<(), Error> {
+        if updater == self.owner.get() {
+            return Ok(());
+        }
+        let bond = self.updater_bond.get(updater);
+        let required = self.min_updater_bond.get();
+        if bond < required {
+            return Err(Error::InsufficientBond(InsufficientBond { bond, required }));
+        }
+        Ok(())
+    }
+
+    /// Holds a sequenced IL update as the pool's pending value until `window` seconds from now,
+    /// or until the replaced pending value was due
+    fn stage_historical_il(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        il: U256,
+        sequence: U256,
+        updater: Address,
+        window: U256,
+    ) -> Result<(), Error> {
+        let stored = self.update_sequence.get(pool_id);
+        if sequence <= stored {
+            return Err(Error::StaleSequence(StaleSequence { stored, provided: sequence }));
+        }
+        let pending_at = self.pending_il_effective_at.get(pool_id);
+        let effective_at = if pending_at != U256::ZERO {
+            pending_at
+        } else {
+            block_time()
+                .checked_add(window)
+                .ok_or(Error::CalculationError(CalculationError{}))?
+        };
+        self.update_sequence.insert(pool_id, sequence);
+        self.pending_il.insert(pool_id, il);
+        self.pending_il_effective_at.insert(pool_id, effective_at);
+        self.pending_il_updater.insert(pool_id, updater);
+        if effective_at > self.updater_pending_until.get(updater) {
+            self.updater_pending_until.insert(updater, effective_at);
+        }
+        evm::log(IlUpdatePending { pool_id, il, effective_at, updater });
+        Ok(())
+    }
+
+    /// Writes a sequenced IL update on behalf of `updater`
+    ///
+    /// Reverts unless `sequence` is strictly above the pool's last accepted one.
+    fn write_historical_il(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        il: U256,
+        sequence: U256,
+        updater: Address,
+    ) -> Result<(), Error> {
+        let stored = self.update_sequence.get(pool_id);
+        if sequence <= stored {
+            return Err(Error::StaleSequence(StaleSequence { stored, provided: sequence }));
+        }
+        self.update_sequence.insert(pool_id, sequence);
+        self.store_historical_il(pool_id, il, updater);
+        Ok(())
+    }
+
+    /// Stores a pool's IL, refreshes its update time, clears the unrated flag and appends the
+    /// write to the pool's history log
+    fn store_historical_il(&mut self, pool_id: FixedBytes<32>, il: U256, updater: Address) {
+        let old_il = self.historical_il.get(pool_id);
+        let now = block_time();
+        self.historical_il.insert(pool_id, il);
+        self.last_il_update.insert(pool_id, now);
+        self.unrated.insert(pool_id, false);
+
+        let count = self.il_history_count.get(pool_id);
+        let mut history = self.il_history.setter(pool_id);
+        let mut entry = history.setter(count % U256::from(IL_HISTORY_CAPACITY));
+        entry.value.set(il);
+        entry.timestamp.set(now);
+        entry.updater.set(updater);
+        self.il_history_count.insert(pool_id, count + U256::from(1));
+        evm::log(HistoricalIlUpdated { pool_id, old_il, new_il: il, updater });
+    }
+
+    /// Returns the IL (WAD) of an LP position as the price moves from `start` to `end`
+    fn price_path_il(start: U256, end: U256) -> Result<U256, Error> {
+        let ratio = math::mul_div(end, U256::from(WAD), start)?;
+        let sqrt_ratio = math::wad_sqrt(ratio)?;
+        let denominator = ratio
+            .checked_add(U256::from(WAD))
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        // 2 * sqrt(r) <= 1 + r, so the held value never exceeds 1.0
+        let held = math::mul_div(sqrt_ratio * U256::from(2), U256::from(WAD), denominator)?;
+        Ok(U256::from(WAD).saturating_sub(held))
+    }
+
+    /// Reads a pool's price feed, returning its latest answer normalized to WAD
+    fn oracle_price(&self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        let feed = self.price_feed.get(pool_id);
+        if feed == Address::ZERO {
+            return Err(Error::NoOracle(NoOracle { pool_id }));
+        }
+        let invalid = || Error::InvalidOracleResponse(InvalidOracleResponse { feed });
+        let oracle = IPriceFeed::new(feed);
+        let decimals = oracle.decimals(Call::new()).map_err(|_| invalid())?;
+        let (_, answer, _, updated_at, _) = oracle.latest_round_data(Call::new()).map_err(|_| invalid())?;
+        if answer <= I256::ZERO {
+            return Err(invalid());
+        }
+        let age = block_time().saturating_sub(updated_at);
+        let max_age = self.oracle_max_age();
+        if age > max_age {
+            return Err(Error::StaleData(StaleData { age, max_age }));
+        }
+        math::to_wad(math::to_unsigned(answer)?, decimals).map_err(|_| invalid())
+    }
+
+    /// Returns the pool's registered data provider
+    fn data_provider(&self, pool_id: FixedBytes<32>) -> Result<IPoolDataProvider, Error> {
+        let provider = self.data_provider.get(pool_id);
+        if provider == Address::ZERO {
+            return Err(Error::NoDataProvider(NoDataProvider { pool_id }));
+        }
+        Ok(IPoolDataProvider::new(provider))
+    }
+
+    /// Reads a pool's (total_liquidity, total_volume) from its provider
+    fn provider_state(&self, pool_id: FixedBytes<32>) -> Result<(U256, U256), Error> {
+        let provider = self.data_provider(pool_id)?;
+        let total_liquidity = Self::provider_liquidity(&provider, pool_id)?;
+        let total_volume = provider
+            .get_volume(Call::new(), pool_id)
+            .map_err(|_| Error::DataProviderFailed(DataProviderFailed { provider: provider.address }))?;
+        Ok((total_liquidity, total_volume))
+    }
+
+    /// Reads a pool's total liquidity from its provider
+    fn provider_liquidity(provider: &IPoolDataProvider, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        provider
+            .get_liquidity(Call::new(), pool_id)
+            .map_err(|_| Error::DataProviderFailed(DataProviderFailed { provider: provider.address }))
+    }
+
+    /// Returns the (token0, token1) amounts, in WAD, of one unit of liquidity in
+    /// `[lower, upper]` at `price`
+    fn range_amounts(price: U256, lower: U256, upper: U256) -> Result<(U256, U256), Error> {
+        let sqrt_price = math::wad_sqrt(price.clamp(lower, upper))?;
+        let sqrt_lower = math::wad_sqrt(lower)?;
+        let sqrt_upper = math::wad_sqrt(upper)?;
+        let wad_squared = U256::from(WAD) * U256::from(WAD);
+        // sqrt_price <= sqrt_upper, so the inverse at the price is never the smaller one
+        let x = wad_squared / sqrt_price - wad_squared / sqrt_upper;
+        Ok((x, sqrt_price - sqrt_lower))
+    }
+
+    /// Returns the IL used for pricing: the source median when the pool has sources, otherwise
+    /// the stored value, falling back to the default for unrated pools
+    fn effective_il(&self, pool_id: FixedBytes<32>) -> U256 {
+        if !self.il_sources.get(pool_id).is_empty() {
+            return self.get_aggregated_il(pool_id).0;
+        }
+        // A matured pending update clears the unrated flag once promoted, so it wins here too
+        if let Some(il) = self.matured_pending_il(pool_id) {
+            return il;
+        }
+        if self.unrated.get(pool_id) {
+            self.unrated_default_il.get()
+        } else {
+            self.historical_il.get(pool_id)
+        }
+    }
+
+    /// Returns when a pool's current IL took effect
+    fn il_updated_at(&self, pool_id: FixedBytes<32>) -> U256 {
+        if self.matured_pending_il(pool_id).is_some() {
+            self.pending_il_effective_at.get(pool_id)
+        } else {
+            self.last_il_update.get(pool_id)
+        }
+    }
+
+    /// Returns a pool's pending IL if its dispute window has passed
+    fn matured_pending_il(&self, pool_id: FixedBytes<32>) -> Option<U256> {
+        let effective_at = self.pending_il_effective_at.get(pool_id);
+        if effective_at == U256::ZERO || block_time() < effective_at {
+            return None;
+        }
+        Some(self.pending_il.get(pool_id))
+    }
+
+    /// Returns the answers of a pool's sources that reported within the heartbeat
+    fn fresh_il_answers(&self, pool_id: FixedBytes<32>) -> Vec<U256> {
+        let now = block_time();
+        let heartbeat = self.effective_il_source_heartbeat();
+        let values = self.il_source_value.get(pool_id);
+        let updated_at = self.il_source_updated_at.get(pool_id);
+        self.get_il_sources(pool_id)
+            .into_iter()
+            .filter(|source| {
+                let at = updated_at.get(*source);
+                at != U256::ZERO && now.saturating_sub(at) <= heartbeat
+            })
+            .map(|source| values.get(source))
+            .collect()
+    }
+
+    /// Returns the IL source slots per pool, defaulting when unset
+    fn effective_max_il_sources(&self) -> U256 {
+        let max_sources = self.max_il_sources.get();
+        if max_sources == U256::ZERO {
+            U256::from(DEFAULT_MAX_IL_SOURCES)
+        } else {
+            max_sources
+        }
+    }
+
+    /// Returns the fresh sources a median needs, defaulting when unset
+    fn effective_il_source_quorum(&self) -> U256 {
+        let quorum = self.il_source_quorum.get();
+        if quorum == U256::ZERO {
+            U256::from(DEFAULT_IL_SOURCE_QUORUM)
+        } else {
+            quorum
+        }
+    }
+
+    /// Returns how long an IL source's answer stays fresh, defaulting when unset
+    fn effective_il_source_heartbeat(&self) -> U256 {
+        let heartbeat = self.il_source_heartbeat.get();
+        if heartbeat == U256::ZERO {
+            U256::from(DEFAULT_IL_SOURCE_HEARTBEAT)
+        } else {
+            heartbeat
+        }
+    }
+
+    /// Stores a matured pending IL as the pool's value, returning whether there was one
+    fn promote_pending(&mut self, pool_id: FixedBytes<32>) -> bool {
+        let Some(il) = self.matured_pending_il(pool_id) else {
+            return false;
+        };
+        let updater = self.pending_il_updater.get(pool_id);
+        self.pending_il_effective_at.insert(pool_id, U256::ZERO);
+        self.store_historical_il(pool_id, il, updater);
+        self.count_il_observation(pool_id);
+        true
+    }
+
+    /// Counts an updater's IL write that cleared the guards toward the pool's observations;
+    /// hook and forced writes never count, so they cannot buy down the uncertainty premium
+    fn count_il_observation(&mut self, pool_id: FixedBytes<32>) {
+        self.il_observations.insert(pool_id, self.il_observations.get(pool_id) + U256::from(1));
+    }
+
+    /// Prices insurance against the pool's live state
+    fn quote_insurance_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.quote_insurance_fee_for(pool_id, IL_PRODUCT, msg::sender(), amount, total_liquidity, total_volume)
+    }
+
+    /// Prices insurance under a product against the pool's live state on behalf of `trader`
+    fn quote_insurance_fee_for(
+        &self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        trader: Address,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.price_insurance_fee(pool_id, product_id, trader, None, amount, total_liquidity, total_volume)
+    }
+
+    /// Prices coverage like `quote_product_coverage_premium` with `trader`'s loyalty discount
+    #[allow(clippy::too_many_arguments)]
+    fn price_coverage_premium(
+        &self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        trader: Address,
+        notional: U256,
+        coverage_fraction: U256,
+        duration: U256,
+        deductible_wad: U256,
+        coinsurance_bps: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        if notional == U256::ZERO
+            || duration < U256::from(MIN_POLICY_DURATION)
+            || duration > U256::from(MAX_POLICY_DURATION)
+        {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if !self.is_deductible_allowed(deductible_wad) || coinsurance_bps > U256::from(MAX_COINSURANCE_BPS) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if coverage_fraction == U256::ZERO || coverage_fraction > U256::from(WAD) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let mut premium = self
+            .quote_insurance_fee_for(pool_id, product_id, trader, notional, total_liquidity, total_volume)?
+            .checked_mul(duration)
+            .ok_or(Error::CalculationError(CalculationError{}))?
+            .checked_div(U256::from(POLICY_TERM))
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+
+        // In hedging mode the premium never undercuts what it would cost to hedge the position
+        if self.hedging_fee_mode.get(pool_id) {
+            let hedging_cost = self.estimate_hedging_cost(notional, self.hedging_volatility.get(pool_id), duration)?;
+            premium = premium.max(hedging_cost);
+        }
+
+        let discount_denominator = deductible_wad
+            .checked_mul(U256::from(DEDUCTIBLE_DISCOUNT_SLOPE))
+            .and_then(|slope| slope.checked_add(U256::from(WAD)))
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let premium = math::mul_div(premium, U256::from(WAD), discount_denominator)?;
+        let premium = math::mul_div(premium, U256::from(BPS_DENOMINATOR) - coinsurance_bps, U256::from(BPS_DENOMINATOR))?;
+        math::mul_div(premium, coverage_fraction, U256::from(WAD))
+    }
+
+    /// Runs the full quote pipeline, pricing off `il_override` instead of the pool's IL when given
+    #[allow(clippy::too_many_arguments)]
+    fn price_insurance_fee(
+        &self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        trader: Address,
+        il_override: Option<U256>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<U256, Error> {
+        self.price_insurance_fee_traced(pool_id, product_id, trader, il_override, amount, total_liquidity, total_volume)
+            .map(|(fee, _)| fee)
+    }
+
+    /// Runs the quote pipeline like `price_insurance_fee`, also returning whether the pool's
+    /// external formula failed and the internal one priced instead
+    #[allow(clippy::too_many_arguments)]
+    fn price_insurance_fee_traced(
+        &self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        trader: Address,
+        il_override: Option<U256>,
+        amount: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+    ) -> Result<(U256, bool), Error> {
+        self.check_registration(pool_id)?;
+        let (base_fee, il_weight, size_weight) = self.product_params(pool_id, product_id)?;
+        if self.is_fee_holiday(pool_id) || self.is_dust(pool_id, amount) {
+            return Ok((U256::ZERO, false));
+        }
+        self.check_data_age(pool_id, il_override.is_none())?;
+        let composite = if product_id == IL_PRODUCT && il_override.is_none() {
+            self.pool_composite_multiplier(pool_id)?
+        } else {
+            let historical_il = il_override
+                .unwrap_or_else(|| self.effective_il(pool_id))
+                .min(self.max_effective_il());
+            self.composite_multiplier(base_fee, self.pool_il_weight(pool_id, il_weight)?, historical_il)?
+        };
+        let depeg = self.pool_depeg(pool_id)?;
+        let curve_multiplier = self.curve_size_multiplier(pool_id, amount, total_liquidity)?;
+        let concentration = self.get_open_interest_ratio(pool_id, total_liquidity)?;
+        let fee = self.compute_insurance_fee(
+            composite,
+            size_weight,
+            depeg,
+            concentration,
+            curve_multiplier,
+            amount,
+            total_liquidity,
+            total_volume,
+        )?;
+        let formula = self.fee_formula.get(pool_id);
+        let (fee, formula_failed) = if formula == Address::ZERO {
+            (fee, false)
+        } else {
+            let historical_il = il_override
+                .unwrap_or_else(|| self.effective_il(pool_id))
+                .min(self.max_effective_il());
+            let il_weight = self.pool_il_weight(pool_id, il_weight)?;
+            match IFeeFormula::new(formula).calculate_fee(
+                Call::new(),
+                pool_id,
+                amount,
+                total_liquidity,
+                total_volume,
+                historical_il,
+                base_fee,
+                il_weight,
+                size_weight,
+            ) {
+                Ok(external) => (self.clamp_formula_fee(pool_id, external, fee)?, false),
+                Err(_) => (fee, true),
+            }
+        };
+        let grace = self.get_grace_multiplier(pool_id)?;
+        let uncertainty = self.get_uncertainty_multiplier(pool_id);
+        let systemic = self.get_systemic_multiplier()?;
+        invariant!(grace <= U256::from(WAD), INVARIANT_MULTIPLIER_BOUNDS);
+        invariant!(
+            uncertainty >= U256::from(WAD) && uncertainty <= U256::from(WAD) + U256::from(MAX_RISK_WEIGHT),
+            INVARIANT_MULTIPLIER_BOUNDS
+        );
+        invariant!(
+            systemic >= U256::from(WAD) && systemic <= self.systemic_max_multiplier.get().max(U256::from(WAD)),
+            INVARIANT_MULTIPLIER_BOUNDS
+        );
+        let fee = math::mul_div(fee, grace, U256::from(WAD))?;
+        let fee = math::mul_div(fee, uncertainty, U256::from(WAD))?;
+        let fee = math::mul_div(fee, systemic, U256::from(WAD))?;
+        let fee = self.apply_loyalty_discount(trader, fee)?;
+        invariant!(fee <= amount, INVARIANT_FEE_EXCEEDS_AMOUNT);
+        Ok((fee, formula_failed))
+    }
+
+    /// Clamps an external formula's fee to the pool's band around the internal fee, so no
+    /// formula can price below the floor or past the cap at any trade size
+    fn clamp_formula_fee(&self, pool_id: FixedBytes<32>, external: U256, internal: U256) -> Result<U256, Error> {
+        let (min_bps, max_bps) = self.get_fee_formula_bounds(pool_id);
+        let min_fee = math::mul_div(internal, min_bps, U256::from(BPS_DENOMINATOR))?;
+        let max_fee = math::mul_div(internal, max_bps, U256::from(BPS_DENOMINATOR))?;
+        Ok(external.max(min_fee).min(max_fee))
+    }
 
-#[derive(SolidityError, Debug)]
-pub enum Error {
-    /// Math calculation error
-    CalculationError(CalculationError),
-    /// Invalid input parameters 
-    InvalidInput(InvalidInput)
-}
+    /// Returns the IL weight a pool's type prices with: stable pools dampen it
+    fn pool_il_weight(&self, pool_id: FixedBytes<32>, il_weight: U256) -> Result<U256, Error> {
+        if self.get_pool_type(pool_id) != POOL_STABLE {
+            return Ok(il_weight);
+        }
+        math::mul_div(il_weight, U256::from(STABLE_IL_WEIGHT_BPS), U256::from(BPS_DENOMINATOR))
+    }
 
-sol_storage! {
-    #[entrypoint]
-    pub struct InsuranceCalculator {
-        mapping(bytes32 => uint256) historical_il;
-        mapping(bytes32 => uint256) default_flash_fee_multiplier;
+    /// Returns a stable pool's depeg deviation (WAD), its reference price's distance from 1.0
+    fn pool_depeg(&self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        if self.get_pool_type(pool_id) != POOL_STABLE {
+            return Ok(U256::ZERO);
+        }
+        let reference = self.get_reference_price(pool_id);
+        if reference == U256::ZERO {
+            return Ok(U256::ZERO);
+        }
+        Ok(math::abs(math::signed_sub(reference, U256::from(WAD))?))
     }
-}
 
-#[public]
-impl InsuranceCalculator {
-    /// Calculates insurance fee for a trade
-    pub fn calculate_insurance_fee(
+    /// Returns the base fee × IL multiplier (RAY) every IL product quote on a pool starts from
+    fn composite_multiplier(&self, base_fee: U256, il_weight: U256, historical_il: U256) -> Result<U256, Error> {
+        let ray = U256::from(RAY);
+        // Historical IL multiplier: higher IL means higher risk, thus higher fees
+        let il_multiplier = math::il_ray_multiplier(historical_il, il_weight)?;
+        invariant!(
+            il_multiplier >= ray && il_multiplier <= math::il_ray_multiplier(U256::from(WAD), il_weight)?,
+            INVARIANT_MULTIPLIER_BOUNDS
+        );
+        math::mul_div(math::wad_to_ray(base_fee)?, il_multiplier, ray)
+    }
+
+    /// Returns a pool's IL product composite multiplier from its current inputs
+    fn pool_composite_multiplier(&self, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        let il_weight = self.pool_il_weight(pool_id, self.effective_il_weight(pool_id))?;
+        let historical_il = self.effective_il(pool_id).min(self.max_effective_il());
+        self.composite_multiplier(self.effective_base_fee(pool_id), il_weight, historical_il)
+    }
+
+    /// Interpolates the pool's size curve at the trade's share of liquidity, if one is installed
+    fn curve_size_multiplier(
         &self,
         pool_id: FixedBytes<32>,
         amount: U256,
         total_liquidity: U256,
+    ) -> Result<Option<U256>, Error> {
+        let xs = self.fee_curve_x.get(pool_id);
+        let ys = self.fee_curve_y.get(pool_id);
+        let count = xs.len();
+        if count == 0 {
+            return Ok(None);
+        }
+        // Without liquidity the trade is off the end of any table
+        let last = ys.get(count - 1).unwrap_or_default();
+        if total_liquidity == U256::ZERO {
+            return Ok(Some(last));
+        }
+        let fraction = math::mul_div(amount, U256::from(WAD), total_liquidity)?;
+        if fraction <= xs.get(0).unwrap_or_default() {
+            return Ok(Some(ys.get(0).unwrap_or_default()));
+        }
+        for i in 1..count {
+            let x1 = xs.get(i).unwrap_or_default();
+            if fraction > x1 {
+                continue;
+            }
+            let x0 = xs.get(i - 1).unwrap_or_default();
+            let y0 = ys.get(i - 1).unwrap_or_default();
+            let y1 = ys.get(i).unwrap_or_default();
+            let multiplier = if y1 >= y0 {
+                y0 + math::mul_div(y1 - y0, fraction - x0, x1 - x0)?
+            } else {
+                y0 - math::mul_div(y0 - y1, fraction - x0, x1 - x0)?
+            };
+            return Ok(Some(multiplier));
+        }
+        Ok(Some(last))
+    }
+
+    /// Reverts with `StaleData` when the pool's last price observation, or its last IL write
+    /// if `check_il`, is older than its max data age
+    ///
+    /// Unrated pools already price off the conservative default rather than their IL, and
+    /// pools without a swap hook have no price observations to age.
+    fn check_data_age(&self, pool_id: FixedBytes<32>, check_il: bool) -> Result<(), Error> {
+        let max_age = self.get_max_data_age(pool_id);
+        if max_age == U256::ZERO {
+            return Ok(());
+        }
+        let now = block_time();
+        // Source-aggregated pools enforce freshness through the heartbeat instead
+        let aggregated = !self.il_sources.get(pool_id).is_empty();
+        if check_il && !aggregated && (self.matured_pending_il(pool_id).is_some() || !self.unrated.get(pool_id)) {
+            let age = now.saturating_sub(self.il_updated_at(pool_id));
+            if age > max_age {
+                return Err(Error::StaleData(StaleData { age, max_age }));
+            }
+        }
+        if self.observation_count.get(pool_id) > U256::ZERO {
+            let age = now.saturating_sub(self.last_price_observation.get(pool_id));
+            if age > max_age {
+                return Err(Error::StaleData(StaleData { age, max_age }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether `amount`, in the pool's token0 units, falls below its WAD dust threshold
+    /// once normalized to WAD; pools without configured decimals already trade in WAD
+    fn is_dust(&self, pool_id: FixedBytes<32>, amount: U256) -> bool {
+        let (decimals0, _) = self.get_token_decimals(pool_id);
+        // An amount too large to normalize is nowhere near dust
+        math::to_wad(amount, decimals0).map_or(false, |normalized| normalized < self.dust_threshold.get(pool_id))
+    }
+
+    /// Returns the discount unlocked by the highest tier `volume` has reached
+    fn loyalty_discount_bps(&self, volume: U256) -> U256 {
+        let mut discount = U256::ZERO;
+        for i in 0..self.loyalty_thresholds.len() {
+            if volume < self.loyalty_thresholds.get(i).unwrap_or_default() {
+                break;
+            }
+            discount = self.loyalty_discounts_bps.get(i).unwrap_or_default();
+        }
+        discount
+    }
+
+    /// Reduces a fee by the trader's loyalty discount, rounding the discount down; the zero
+    /// address prices undiscounted
+    fn apply_loyalty_discount(&self, trader: Address, fee: U256) -> Result<U256, Error> {
+        if trader == Address::ZERO {
+            return Ok(fee);
+        }
+        let discount_bps = self.loyalty_discount_bps(self.insured_volume.get(trader));
+        if discount_bps == U256::ZERO {
+            return Ok(fee);
+        }
+        invariant!(discount_bps <= U256::from(BPS_DENOMINATOR), INVARIANT_MULTIPLIER_BOUNDS);
+        let discount = math::mul_div(fee, discount_bps, U256::from(BPS_DENOMINATOR))?;
+        let discounted = fee - discount;
+        invariant!(discounted.checked_add(discount) == Some(fee), INVARIANT_SPLIT_MISMATCH);
+        Ok(discounted)
+    }
+
+    /// Adds insured volume to a trader's loyalty record
+    fn add_insured_volume(&mut self, trader: Address, amount: U256) -> Result<(), Error> {
+        let volume = self.insured_volume.get(trader)
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.insured_volume.insert(trader, volume);
+        Ok(())
+    }
+
+    /// Returns a product's (base_fee, il_weight, size_weight) on a pool
+    ///
+    /// The IL product always uses the pool's base fee and IL weight with the default size weight.
+    fn product_params(&self, pool_id: FixedBytes<32>, product_id: u8) -> Result<(U256, U256, U256), Error> {
+        if product_id == IL_PRODUCT {
+            return Ok((
+                self.effective_base_fee(pool_id),
+                self.effective_il_weight(pool_id),
+                U256::from(DEFAULT_SIZE_WEIGHT),
+            ));
+        }
+        let products = self.products.get(pool_id);
+        let product = products.get(U8::from(product_id));
+        if !product.enabled.get() {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        Ok((product.base_fee.get(), product.il_weight.get(), product.size_weight.get()))
+    }
+
+    /// Core insurance fee pipeline shared by every quote path, starting from the composite
+    /// base fee × IL multiplier (RAY)
+    ///
+    /// Multipliers are carried in RAY (1e27) and combined with full-width `mul_div`, so each
+    /// step rounds at 1e-27 and only the final conversion back to WAD rounds at 1e-18.
+    #[allow(clippy::too_many_arguments)]
+    fn compute_insurance_fee(
+        &self,
+        composite: U256,
+        size_weight: U256,
+        depeg: U256,
+        concentration: U256,
+        curve_multiplier: Option<U256>,
+        amount: U256,
+        total_liquidity: U256,
         total_volume: U256,
-        current_price: U256,
-        timestamp: U256,
     ) -> Result<U256, Error> {
-        // Base fee for insurance, fixed at 0.1%
-        let base_fee = U256::from(100_000_000_000_000_000u64);
+        let ray = U256::from(RAY);
 
-        // Volume multiplier: decreases fee if volume is high
+        // Volume multiplier: decreases fee if volume is high, 0.1 + 0.9 * volume / (volume + 1e18)
         let volume_multiplier = if total_volume > U256::ZERO {
-            let factor = total_volume
-                .checked_mul(U256::from(900_000_000_000_000_000u64))
-                .ok_or(Error::CalculationError(CalculationError{}))? // Ensure no overflow
-                .checked_div(total_volume.checked_add(U256::from(1_000_000_000_000_000_000u64))
-                .ok_or(Error::CalculationError(CalculationError{}))?)
-                .ok_or(Error::CalculationError(CalculationError{}))?; // Normalize by volume + 1e18
-            U256::from(100_000_000_000_000_000u64)
+            let factor = math::mul_div(
+                math::wad_to_ray(U256::from(900_000_000_000_000_000u64))?,
+                total_volume,
+                total_volume
+                    .checked_add(U256::from(WAD))
+                    .ok_or(Error::CalculationError(CalculationError{}))?,
+            )?;
+            math::wad_to_ray(U256::from(100_000_000_000_000_000u64))?
                 .checked_add(factor)
-                .ok_or(Error::CalculationError(CalculationError{}))? // Add factor
+                .ok_or(Error::CalculationError(CalculationError{}))?
         } else {
-            U256::from(1_000_000_000_000_000_000u64) // Default to 1.0 if no volume
+            ray // Default to 1.0 if no volume
         };
 
-        // Historical IL multiplier: higher IL means higher risk, thus higher fees
-        let historical_il = self.historical_il.get(pool_id);
-        let il_multiplier = historical_il
-            .checked_mul(U256::from(3_000_000_000_000_000_000u64))
-            .ok_or(Error::CalculationError(CalculationError{}))? // Amplify IL effect
-            .checked_add(U256::from(1_000_000_000_000_000_000u64))
-            .ok_or(Error::CalculationError(CalculationError{}))?; // Add baseline multiplier
+        // Depeg multiplier: stable pools pay more the further their price sits from 1.0
+        let depeg_multiplier = math::ray_multiplier(depeg, U256::from(DEPEG_WEIGHT))?;
 
-        // Size multiplier: larger trades pay proportionally higher fees
-        let size_multiplier = if total_liquidity > U256::ZERO {
-            amount
-                .checked_mul(U256::from(1_000_000_000_000_000_000u64))
-                .ok_or(Error::CalculationError(CalculationError{}))? // Scale trade size
-                .checked_div(total_liquidity)
-                .ok_or(Error::CalculationError(CalculationError{}))? // Normalize by pool liquidity
-                .checked_add(U256::from(1_000_000_000_000_000_000u64))
-                .ok_or(Error::CalculationError(CalculationError{}))? // Baseline multiplier
+        // Open-interest multiplier: the more of the pool already insured, the dearer the next policy
+        let concentration_multiplier = math::ray_multiplier(concentration, self.open_interest_weight.get())?;
+
+        // Size multiplier: larger trades pay proportionally higher fees, or whatever the pool's
+        // custom curve dictates
+        let size_multiplier = if let Some(multiplier) = curve_multiplier {
+            math::wad_to_ray(multiplier)?
+        } else if total_liquidity > U256::ZERO {
+            math::mul_div(amount, math::wad_to_ray(size_weight)?, total_liquidity)?
+                .checked_add(ray)
+                .ok_or(Error::CalculationError(CalculationError{}))?
         } else {
-            U256::from(2_000_000_000_000_000_000u64) // Default if no liquidity
+            ray * U256::from(2) // Default if no liquidity
         };
 
-        // Final fee = base * volume * IL * size, scaled down for precision
-        let fee = base_fee
-            .checked_mul(volume_multiplier)
-            .ok_or(Error::CalculationError(CalculationError{}))?
-            .checked_mul(il_multiplier)
-            .ok_or(Error::CalculationError(CalculationError{}))?
-            .checked_mul(size_multiplier)
-            .ok_or(Error::CalculationError(CalculationError{}))?
-            .checked_div(U256::from(1_000_000_000_000_000_000u64).pow(U256::from(3)))
-            .ok_or(Error::CalculationError(CalculationError{}))?;
+        invariant!(
+            volume_multiplier >= ray / U256::from(10) && volume_multiplier <= ray,
+            INVARIANT_MULTIPLIER_BOUNDS
+        );
+        invariant!(
+            concentration_multiplier >= ray
+                && concentration_multiplier <= ray + math::wad_to_ray(self.open_interest_weight.get())?,
+            INVARIANT_MULTIPLIER_BOUNDS
+        );
+        invariant!(
+            curve_multiplier.map_or(true, |multiplier| multiplier <= U256::from(MAX_RISK_WEIGHT)),
+            INVARIANT_MULTIPLIER_BOUNDS
+        );
 
-        Ok(fee)
+        // Final fee = (base * IL) * volume * depeg * open interest * size, carried in RAY and
+        // scaled back to WAD once
+        let fee = math::mul_div(composite, volume_multiplier, ray)?;
+        let fee = math::mul_div(fee, depeg_multiplier, ray)?;
+        let fee = math::mul_div(fee, concentration_multiplier, ray)?;
+        let fee = math::mul_div(fee, size_multiplier, ray)?;
+        Ok(math::ray_to_wad(fee))
     }
 
-    /// Calculates flash loan fee for a borrowing
-    pub fn calculate_flash_loan_fee(
+    /// Reruns the internal insurance formula for a pool with every step saturating instead of
+    /// overflowing, returning (fee, saturated)
+    fn saturating_insurance_fee(
         &self,
+        pool_id: FixedBytes<32>,
         amount: U256,
         total_liquidity: U256,
-        utilization_rate: U256,
-        default_history: U256,
-    ) -> Result<U256, Error> {
-        // Base fee for flash loans, fixed at 0.05%
-        let base_fee = U256::from(500_000_000_000_000u64);
-
-        // Utilization multiplier: scales up fee when pool usage is high
-        let utilization_multiplier = utilization_rate
-            .checked_mul(U256::from(2))
-            .ok_or(Error::CalculationError(CalculationError{}))? // Amplify by 2x
-            .checked_add(U256::from(1_000_000_000_000_000_000u64))
-            .ok_or(Error::CalculationError(CalculationError{}))? // Add baseline multiplier
-            .checked_div(U256::from(1_000_000_000_000_000_000u64))
-            .ok_or(Error::CalculationError(CalculationError{}))?; // Normalize
+        total_volume: U256,
+    ) -> (U256, bool) {
+        if self.is_fee_holiday(pool_id) || self.is_dust(pool_id, amount) {
+            return (U256::ZERO, false);
+        }
+        let ray = U256::from(RAY);
+        let wad = U256::from(WAD);
+        // A shared flag keeps `step` a plain `Fn`, so calls can nest
+        let saturated = core::cell::Cell::new(false);
+        let step = |a: U256, b: U256, denominator: U256| {
+            let (value, clamped) = math::saturating_mul_div(a, b, denominator);
+            saturated.set(saturated.get() || clamped);
+            value
+        };
+        // Helpers that only fail on overflow count as saturated at their extreme
+        let il_weight = self.pool_il_weight(pool_id, self.effective_il_weight(pool_id));
+        let depeg = self.pool_depeg(pool_id);
+        let concentration = self.get_open_interest_ratio(pool_id, total_liquidity);
+        let curve_multiplier = self.curve_size_multiplier(pool_id, amount, total_liquidity);
+        let grace = self.get_grace_multiplier(pool_id);
+        let systemic = self.get_systemic_multiplier();
+        let mut failed = il_weight.is_err()
+            || depeg.is_err()
+            || concentration.is_err()
+            || curve_multiplier.is_err()
+            || grace.is_err()
+            || systemic.is_err();
 
-        // Liquidity multiplier: reduces fee when liquidity is high
-        let liquidity_multiplier = if total_liquidity > U256::ZERO {
-            U256::from(1_000_000_000_000_000_000u64)
-                .checked_div(total_liquidity.checked_add(U256::from(1_000_000_000_000_000_000u64))
-                .ok_or(Error::CalculationError(CalculationError{}))?)
-                .ok_or(Error::CalculationError(CalculationError{}))? // Adjust by available liquidity
-                .checked_add(U256::from(1_000_000_000_000_000_000u64))
-                .ok_or(Error::CalculationError(CalculationError{}))? // Baseline multiplier
+        let historical_il = self.effective_il(pool_id).min(self.max_effective_il());
+        let il_multiplier = ray.saturating_add(step(
+            step(historical_il, il_weight.unwrap_or(U256::MAX), U256::from(1)),
+            ray,
+            wad,
+        ));
+        let depeg_multiplier = ray.saturating_add(step(
+            step(depeg.unwrap_or(U256::MAX), ray, wad),
+            U256::from(DEPEG_WEIGHT),
+            wad,
+        ));
+        let concentration_multiplier = ray.saturating_add(step(
+            step(concentration.unwrap_or(wad), ray, wad),
+            self.open_interest_weight.get(),
+            wad,
+        ));
+        let volume_multiplier = if total_volume > U256::ZERO {
+            ray / U256::from(10) + step(ray - ray / U256::from(10), total_volume, total_volume.saturating_add(wad))
         } else {
-            U256::from(2_000_000_000_000_000_000u64) // Default multiplier 2.0 * 1e18
+            ray
+        };
+        let size_multiplier = match curve_multiplier {
+            Ok(Some(multiplier)) => step(multiplier, ray, wad),
+            Ok(None) | Err(_) if total_liquidity > U256::ZERO => {
+                ray.saturating_add(step(amount, step(U256::from(DEFAULT_SIZE_WEIGHT), ray, wad), total_liquidity))
+            }
+            // Zero liquidity takes the checked pipeline's default 2.0
+            _ => ray * U256::from(2),
         };
 
-        // Historical multiplier: default adjustment for past performance
-        let historical_multiplier = U256::from(1_000_000_000_000_000_000u64)
-            .checked_add(default_history)
-            .ok_or(Error::CalculationError(CalculationError{}))?; // Add historical adjustment
+        let mut fee = step(self.effective_base_fee(pool_id), ray, wad);
+        for multiplier in [il_multiplier, volume_multiplier, depeg_multiplier, concentration_multiplier, size_multiplier] {
+            fee = step(fee, multiplier, ray);
+        }
+        let fee = math::ray_to_wad(fee);
+        let fee = step(fee, grace.unwrap_or(U256::MAX), wad);
+        let fee = step(fee, self.get_uncertainty_multiplier(pool_id), wad);
+        let fee = step(fee, systemic.unwrap_or(U256::MAX), wad);
+        let fee = match self.apply_loyalty_discount(msg::sender(), fee) {
+            Ok(discounted) => discounted,
+            Err(_) => {
+                failed = true;
+                fee
+            }
+        };
+        (fee, saturated.get() || failed)
+    }
 
-        // Final fee = base * utilization * liquidity * historical, scaled down for precision
-        let fee = base_fee
-            .checked_mul(utilization_multiplier)
-            .ok_or(Error::CalculationError(CalculationError{}))?
-            .checked_mul(liquidity_multiplier)
-            .ok_or(Error::CalculationError(CalculationError{}))?
-            .checked_mul(historical_multiplier)
-            .ok_or(Error::CalculationError(CalculationError{}))?
-            .checked_div(U256::from(1_000_000_000_000_000_000u64).pow(U256::from(3)))
+    /// Charges a quoted premium from the attached value, crediting the payer's insured volume
+    ///
+    /// A zero premium, as during a fee holiday, earns no loyalty volume.
+    fn settle_premium(&mut self, pool_id: FixedBytes<32>, amount: U256, fee: U256) -> Result<U256, Error> {
+        Self::check_payment(fee)?;
+        if fee != U256::ZERO {
+            self.add_insured_volume(msg::sender(), amount)?;
+            self.record_projected_il(pool_id, fee)?;
+        }
+        self.split_premium(pool_id, fee)?;
+        Self::refund_excess(fee)?;
+        evm::log(PremiumPaid { pool_id, payer: msg::sender(), fee, epoch: self.parameter_epoch.get() });
+        Ok(fee)
+    }
+
+    /// Issues a policy for `buy_product_coverage`, first holding the premium to
+    /// `(expected_fee, tolerance_bps)` when a tolerance is given
+    #[allow(clippy::too_many_arguments)]
+    fn issue_coverage(
+        &mut self,
+        pool_id: FixedBytes<32>,
+        product_id: u8,
+        notional: U256,
+        coverage_fraction: U256,
+        duration: U256,
+        deductible_wad: U256,
+        coinsurance_bps: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        tolerance: Option<(U256, U256)>,
+    ) -> Result<U256, Error> {
+        let premium = self.quote_product_coverage_premium(
+            pool_id,
+            product_id,
+            notional,
+            coverage_fraction,
+            duration,
+            deductible_wad,
+            coinsurance_bps,
+            total_liquidity,
+            total_volume,
+        )?;
+        if let Some((expected_fee, tolerance_bps)) = tolerance {
+            Self::check_fee_tolerance(premium, expected_fee, tolerance_bps)?;
+        }
+        Self::check_payment(premium)?;
+        let position = notional;
+        let notional = math::mul_div(position, coverage_fraction, U256::from(WAD))?;
+        if notional == U256::ZERO {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        let current = self.outstanding_coverage.get(pool_id);
+        let outstanding = current
+            .checked_add(notional)
             .ok_or(Error::CalculationError(CalculationError{}))?;
+        let cap = self.max_exposure.get(pool_id);
+        if cap != U256::ZERO && outstanding > cap {
+            return Err(Error::ExposureCapExceeded(ExposureCapExceeded { current, cap, requested: notional }));
+        }
 
-        // Scale by the loan amount
-        let final_fee = fee
-            .checked_mul(amount)
+        let holder = msg::sender();
+        let start = block_time();
+        let expiry = start + duration;
+        let policy_id = self.next_policy_id.get() + U256::from(1);
+        self.next_policy_id.set(policy_id);
+
+        let mut policy = self.policies.setter(policy_id);
+        policy.holder.set(holder);
+        policy.pool_id.set(pool_id);
+        policy.notional.set(notional);
+        policy.premium.set(premium);
+        policy.start.set(start);
+        policy.expiry.set(expiry);
+        policy.deductible.set(deductible_wad);
+        policy.coinsurance_bps.set(coinsurance_bps);
+        policy.product_id.set(U8::from(product_id));
+        policy.coverage_fraction.set(coverage_fraction);
+        policy.term.set(duration);
+        policy.status.set(U8::from(POLICY_ACTIVE));
+        policy.il_at_start.set(self.effective_il(pool_id));
+        self.add_holder_policy(holder, policy_id);
+
+        self.outstanding_coverage.insert(pool_id, outstanding);
+        self.total_outstanding_coverage.set(self.total_outstanding_coverage.get() + notional);
+        if premium != U256::ZERO {
+            self.add_insured_volume(holder, notional)?;
+            self.record_projected_il(pool_id, premium)?;
+        }
+        self.defer_premium(policy_id, pool_id, premium, start, expiry)?;
+        Self::refund_excess(premium)?;
+        evm::log(CoveragePurchased { policy_id, pool_id, holder, notional, premium, expiry, epoch: self.parameter_epoch.get() });
+        Ok(policy_id)
+    }
+
+    /// Renews a policy for `renew_policy`, first holding the premium to
+    /// `(expected_fee, tolerance_bps)` when a tolerance is given
+    fn extend_policy(
+        &mut self,
+        policy_id: U256,
+        duration: U256,
+        total_liquidity: U256,
+        total_volume: U256,
+        tolerance: Option<(U256, U256)>,
+    ) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        if policy.holder.get() != msg::sender() {
+            return Err(Error::Unauthorized(Unauthorized { caller: msg::sender() }));
+        }
+        if !self.is_policy_live(policy_id) {
+            return Err(Error::PolicyNotActive(PolicyNotActive { policy_id }));
+        }
+        let pool_id = policy.pool_id.get();
+        let expiry = policy.expiry.get()
+            .checked_add(duration)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        if expiry - block_time() > U256::from(MAX_POLICY_DURATION) {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        // Re-price the whole position the policy covers a fraction of
+        let coverage_fraction = policy.coverage_fraction.get();
+        let position = math::mul_div(policy.notional.get(), U256::from(WAD), coverage_fraction)?;
+        let premium = self.quote_product_coverage_premium(
+            pool_id,
+            policy.product_id.get().to::<u8>(),
+            position,
+            coverage_fraction,
+            duration,
+            policy.deductible.get(),
+            policy.coinsurance_bps.get(),
+            total_liquidity,
+            total_volume,
+        )?;
+        if let Some((expected_fee, tolerance_bps)) = tolerance {
+            Self::check_fee_tolerance(premium, expected_fee, tolerance_bps)?;
+        }
+        Self::check_payment(premium)?;
+
+        let total_premium = policy.premium.get()
+            .checked_add(premium)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let previous_expiry = policy.expiry.get();
+        let mut policy = self.policies.setter(policy_id);
+        policy.premium.set(total_premium);
+        policy.expiry.set(expiry);
+        self.record_projected_il(pool_id, premium)?;
+        self.defer_premium(policy_id, pool_id, premium, previous_expiry, expiry)?;
+        Self::refund_excess(premium)?;
+        evm::log(PolicyRenewed { policy_id, premium, expiry, epoch: self.parameter_epoch.get() });
+        Ok(premium)
+    }
+
+    /// Reverts with `FeeSlippage` if `fee` exceeds `expected_fee * (10_000 + tolerance_bps) / 10_000`
+    fn check_fee_tolerance(fee: U256, expected_fee: U256, tolerance_bps: U256) -> Result<(), Error> {
+        let ceiling = tolerance_bps
+            .checked_add(U256::from(BPS_DENOMINATOR))
+            .ok_or(Error::InvalidInput(InvalidInput{}))?;
+        if fee > expected_fee && fee > math::mul_div(expected_fee, ceiling, U256::from(BPS_DENOMINATOR))? {
+            return Err(Error::FeeSlippage(FeeSlippage { expected: expected_fee, actual: fee }));
+        }
+        Ok(())
+    }
+
+    /// Reverts unless the attached value covers `required`
+    fn check_payment(required: U256) -> Result<(), Error> {
+        let provided = msg::value();
+        if provided < required {
+            return Err(Error::InsufficientPayment(InsufficientPayment { required, provided }));
+        }
+        Ok(())
+    }
+
+    /// Returns any attached value above `charged` to the caller
+    fn refund_excess(charged: U256) -> Result<(), Error> {
+        let excess = msg::value().saturating_sub(charged);
+        if excess > U256::ZERO {
+            transfer_eth(msg::sender(), excess).map_err(|_| Error::TransferFailed(TransferFailed{}))?;
+        }
+        Ok(())
+    }
+
+    /// Adds to a pool's reserves and the global fund total
+    fn credit_reserves(&mut self, pool_id: FixedBytes<32>, amount: U256) -> Result<(), Error> {
+        let reserves = self.pool_reserves.get(pool_id)
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let total = self.total_reserves.get()
+            .checked_add(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.pool_reserves.insert(pool_id, reserves);
+        self.total_reserves.set(total);
+        Ok(())
+    }
+
+    /// Rejects permissionless credits that carry no value or target a pool that was never registered
+    fn check_fund_credit(&self, pool_id: FixedBytes<32>, amount: U256) -> Result<(), Error> {
+        if amount.is_zero() {
+            return Err(Error::InvalidInput(InvalidInput{}));
+        }
+        if !self.pools.get(pool_id).registered.get() {
+            return Err(Error::PoolNotRegistered(PoolNotRegistered { pool_id }));
+        }
+        Ok(())
+    }
+
+    /// Removes from a pool's reserves and the global fund total
+    fn debit_reserves(&mut self, pool_id: FixedBytes<32>, amount: U256) -> Result<(), Error> {
+        let available = self.pool_reserves.get(pool_id);
+        let reserves = available
+            .checked_sub(amount)
+            .ok_or(Error::InsufficientBalance(InsufficientBalance { available, requested: amount }))?;
+        let total = self.total_reserves.get()
+            .checked_sub(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.pool_reserves.insert(pool_id, reserves);
+        self.total_reserves.set(total);
+        Ok(())
+    }
+
+    /// Renews a policy like `process_renewal`, reading (total_liquidity, total_volume) from
+    /// `market` once the policy is due and its pool still active
+    fn process_renewal_with(
+        &mut self,
+        policy_id: U256,
+        market: impl FnOnce(&Self, FixedBytes<32>) -> Result<(U256, U256), Error>,
+    ) -> Result<U256, Error> {
+        let policy = self.policies.get(policy_id);
+        let now = block_time();
+        if !policy.auto_renew.get() || policy.status.get() != U8::from(POLICY_ACTIVE) || now <= policy.expiry.get() {
+            return Err(Error::PolicyNotActive(PolicyNotActive { policy_id }));
+        }
+        let pool_id = policy.pool_id.get();
+        let balance = policy.renewal_balance.get();
+        if !self.is_pool_active(pool_id) {
+            self.lapse_policy(policy_id, U256::ZERO, balance);
+            return Ok(U256::ZERO);
+        }
+        let term = policy.term.get();
+        let coverage_fraction = policy.coverage_fraction.get();
+        let position = math::mul_div(policy.notional.get(), U256::from(WAD), coverage_fraction)?;
+        let (total_liquidity, total_volume) = market(self, pool_id)?;
+        // Priced for the holder, whoever the keeper is
+        let premium = self.price_coverage_premium(
+            pool_id,
+            policy.product_id.get().to::<u8>(),
+            policy.holder.get(),
+            position,
+            coverage_fraction,
+            term,
+            policy.deductible.get(),
+            policy.coinsurance_bps.get(),
+            total_liquidity,
+            total_volume,
+        )?;
+        if premium > balance {
+            self.lapse_policy(policy_id, premium, balance);
+            return Ok(U256::ZERO);
+        }
+
+        let total_premium = policy.premium.get()
+            .checked_add(premium)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let expiry = now + term;
+        let mut policy = self.policies.setter(policy_id);
+        policy.renewal_balance.set(balance - premium);
+        policy.premium.set(total_premium);
+        policy.expiry.set(expiry);
+        self.record_projected_il(pool_id, premium)?;
+        self.defer_premium(policy_id, pool_id, premium, now, expiry)?;
+        evm::log(PolicyRenewed { policy_id, premium, expiry, epoch: self.parameter_epoch.get() });
+        Ok(premium)
+    }
+
+    /// Expires an auto-renewing policy that could not renew, keeping its renewal balance
+    /// withdrawable
+    fn lapse_policy(&mut self, policy_id: U256, premium: U256, balance: U256) {
+        let policy = self.policies.get(policy_id);
+        let pool_id = policy.pool_id.get();
+        let notional = policy.notional.get();
+        self.set_policy_status(policy_id, POLICY_EXPIRED);
+        self.release_coverage(pool_id, notional);
+        evm::log(PolicyLapsed { policy_id, premium, balance });
+    }
+
+    /// Returns whether a policy is active and not yet past its expiry
+    fn is_policy_live(&self, policy_id: U256) -> bool {
+        let policy = self.policies.get(policy_id);
+        policy.status.get() == U8::from(POLICY_ACTIVE)
+            && block_time() <= policy.expiry.get()
+    }
+
+    /// Appends a policy to its holder's list
+    fn add_holder_policy(&mut self, holder: Address, policy_id: U256) {
+        let mut policies = self.holder_policies.setter(holder);
+        let index = policies.len();
+        policies.push(policy_id);
+        self.holder_policy_index.insert(policy_id, U256::from(index));
+    }
+
+    /// Removes a policy from its holder's list by swapping in the last entry
+    fn remove_holder_policy(&mut self, holder: Address, policy_id: U256) {
+        let index = self.holder_policy_index.get(policy_id).to::<usize>();
+        let mut policies = self.holder_policies.setter(holder);
+        let last = policies.pop().unwrap_or_default();
+        if last != policy_id {
+            if let Some(mut slot) = policies.setter(index) {
+                slot.set(last);
+            }
+            self.holder_policy_index.insert(last, U256::from(index));
+        }
+        self.holder_policy_index.delete(policy_id);
+    }
+
+    /// Moves a policy to a new lifecycle state
+    fn set_policy_status(&mut self, policy_id: U256, status: u8) {
+        self.policies.setter(policy_id).status.set(U8::from(status));
+    }
+
+    /// Releases a policy's notional from its pool's outstanding coverage
+    fn release_coverage(&mut self, pool_id: FixedBytes<32>, notional: U256) {
+        let outstanding = self.outstanding_coverage.get(pool_id);
+        self.outstanding_coverage.insert(pool_id, outstanding.saturating_sub(notional));
+        let total = self.total_outstanding_coverage.get();
+        self.total_outstanding_coverage.set(total.saturating_sub(notional));
+    }
+
+    /// Adds a premium, weighted by the IL it was priced at, to the pool's open rebate epoch;
+    /// a zero premium leaves the epoch untouched
+    fn record_projected_il(&mut self, pool_id: FixedBytes<32>, premium: U256) -> Result<(), Error> {
+        if premium == U256::ZERO {
+            return Ok(());
+        }
+        let il = self.effective_il(pool_id).min(self.max_effective_il());
+        let epoch = self.rebate_epoch.get(pool_id);
+        let epochs = self.rebate_epochs.get(pool_id);
+        let record = epochs.get(epoch);
+        let premium_total = record.premium_total.get()
+            .checked_add(premium)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let premium_weighted_il = premium
+            .checked_mul(il)
+            .and_then(|weighted| weighted.checked_add(record.premium_weighted_il.get()))
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let mut epochs = self.rebate_epochs.setter(pool_id);
+        let mut record = epochs.setter(epoch);
+        record.premium_total.set(premium_total);
+        record.premium_weighted_il.set(premium_weighted_il);
+        Ok(())
+    }
+
+    /// Returns the premium-weighted IL a rebate epoch's premiums were priced at (zero if none)
+    fn projected_il(&self, pool_id: FixedBytes<32>, epoch: U256) -> U256 {
+        let epochs = self.rebate_epochs.get(pool_id);
+        let record = epochs.get(epoch);
+        record.premium_weighted_il.get()
+            .checked_div(record.premium_total.get())
+            .unwrap_or_default()
+    }
+
+    /// Splits a premium into the treasury share (floored) and pool reserves (remainder)
+    fn split_premium(&mut self, pool_id: FixedBytes<32>, amount: U256) -> Result<(), Error> {
+        let (to_reserves, to_treasury) = self.take_protocol_fee(amount)?;
+        self.credit_reserves(pool_id, to_reserves)?;
+        self.accrue_fee_growth(pool_id, to_reserves)?;
+        evm::log(PremiumCollected { pool_id, to_reserves, to_treasury });
+        Ok(())
+    }
+
+    /// Credits the treasury its share of a premium, returning (to_reserves, to_treasury)
+    fn take_protocol_fee(&mut self, amount: U256) -> Result<(U256, U256), Error> {
+        let to_treasury = amount
+            .checked_mul(self.protocol_fee_bps.get())
             .ok_or(Error::CalculationError(CalculationError{}))?
-            .checked_div(U256::from(1_000_000_000_000_000_000u64))
-            .ok_or(Error::CalculationError(CalculationError{}))?; // Scale fee by amount
+            .checked_div(U256::from(BPS_DENOMINATOR))
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let treasury_balance = self.treasury_balance.get()
+            .checked_add(to_treasury)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.treasury_balance.set(treasury_balance);
+        Ok((amount - to_treasury, to_treasury))
+    }
 
-        Ok(final_fee)
+    /// Splits a policy premium like `split_premium`, but holds the pool's share as unearned,
+    /// spread over the accrual epochs `[from, to)` touches in proportion to the seconds of
+    /// each it covers
+    fn defer_premium(
+        &mut self,
+        policy_id: U256,
+        pool_id: FixedBytes<32>,
+        amount: U256,
+        from: U256,
+        to: U256,
+    ) -> Result<(), Error> {
+        let (to_reserves, to_treasury) = self.take_protocol_fee(amount)?;
+        let length = self.accrual_epoch_length();
+        let first = from / length;
+        let last = (to - U256::from(1)) / length;
+        if self.unearned_premium.get(pool_id) == U256::ZERO {
+            // Nothing is pending, so closing can start from this premium's first epoch; the
+            // cursor may be in units of a previous epoch length, so it is reset either way
+            self.next_accrual_epoch.insert(pool_id, first);
+        }
+        let mut remaining = to_reserves;
+        let mut epoch = first;
+        while epoch <= last {
+            // The last epoch takes the rounding remainder so the shares sum exactly
+            let share = if epoch == last {
+                remaining
+            } else {
+                let covered = (epoch + U256::from(1)) * length - from.max(epoch * length);
+                math::mul_div(to_reserves, covered, to - from)?
+            };
+            remaining -= share;
+            let scheduled = self.epoch_premium.get(pool_id).get(epoch) + share;
+            self.epoch_premium.setter(pool_id).insert(epoch, scheduled);
+            let policy_share = self.policy_epoch_premium.get(policy_id).get(epoch) + share;
+            self.policy_epoch_premium.setter(policy_id).insert(epoch, policy_share);
+            epoch += U256::from(1);
+        }
+        self.unearned_premium.insert(pool_id, self.unearned_premium.get(pool_id) + to_reserves);
+        self.total_unearned_premium.set(self.total_unearned_premium.get() + to_reserves);
+        evm::log(PremiumCollected { pool_id, to_reserves, to_treasury });
+        Ok(())
+    }
+
+    /// Takes a swap's insurance fee out of the payer's budget and collects it as premium
+    fn debit_coverage_budget(&mut self, pool_id: FixedBytes<32>, payer: Address, fee: U256) -> Result<(), Error> {
+        if fee == U256::ZERO {
+            return Ok(());
+        }
+        let budget = self.coverage_budget.get(pool_id).get(payer);
+        if budget < fee {
+            return Err(Error::InsufficientBudget(InsufficientBudget { shortfall: fee - budget }));
+        }
+        self.coverage_budget.setter(pool_id).insert(payer, budget - fee);
+        self.split_premium(pool_id, fee)
+    }
+
+    /// Adds `rate * elapsed` since its last checkpoint to an open stream's owed premium,
+    /// rounding down, and returns the new total
+    fn accrue_stream(&mut self, stream_id: U256) -> Result<U256, Error> {
+        let stream = self.streams.get(stream_id);
+        if !stream.open.get() {
+            return Err(Error::StreamNotActive(StreamNotActive { stream_id }));
+        }
+        let now = block_time();
+        let elapsed = now - stream.last_checkpoint.get();
+        let accrued = math::mul_div(stream.rate.get(), elapsed, U256::from(WAD))?;
+        let owed = stream.owed.get()
+            .checked_add(accrued)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let mut stream = self.streams.setter(stream_id);
+        stream.owed.set(owed);
+        stream.last_checkpoint.set(now);
+        Ok(owed)
+    }
+
+    /// Returns the premium a policy has scheduled in its pool's epochs not yet closed
+    fn policy_unearned_premium(&self, policy_id: U256) -> U256 {
+        let (first, last) = self.policy_open_epochs(policy_id);
+        let shares = self.policy_epoch_premium.get(policy_id);
+        let mut unearned = U256::ZERO;
+        let mut epoch = first;
+        while epoch <= last {
+            unearned += shares.get(epoch);
+            epoch += U256::from(1);
+        }
+        unearned
+    }
+
+    /// Removes a policy's unclosed epoch shares from its pool's schedule and the unearned
+    /// totals, returning their sum
+    fn unwind_deferred_premium(&mut self, policy_id: U256, pool_id: FixedBytes<32>) -> Result<U256, Error> {
+        let (first, last) = self.policy_open_epochs(policy_id);
+        let mut unearned = U256::ZERO;
+        let mut epoch = first;
+        while epoch <= last {
+            let share = self.policy_epoch_premium.get(policy_id).get(epoch);
+            if share != U256::ZERO {
+                let scheduled = self.epoch_premium.get(pool_id).get(epoch)
+                    .checked_sub(share)
+                    .ok_or(Error::CalculationError(CalculationError{}))?;
+                self.epoch_premium.setter(pool_id).insert(epoch, scheduled);
+                self.policy_epoch_premium.setter(policy_id).insert(epoch, U256::ZERO);
+                unearned += share;
+            }
+            epoch += U256::from(1);
+        }
+        self.release_unearned_premium(pool_id, unearned)?;
+        Ok(unearned)
+    }
+
+    /// Takes premium out of a pool's unearned bucket and the global unearned total, erroring
+    /// rather than wrapping if the epoch schedule ever holds more than the buckets
+    fn release_unearned_premium(&mut self, pool_id: FixedBytes<32>, amount: U256) -> Result<(), Error> {
+        let unearned = self.unearned_premium.get(pool_id)
+            .checked_sub(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        let total_unearned = self.total_unearned_premium.get()
+            .checked_sub(amount)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.unearned_premium.insert(pool_id, unearned);
+        self.total_unearned_premium.set(total_unearned);
+        Ok(())
+    }
+
+    /// Returns the inclusive range of a policy's epochs its pool has not closed yet; empty
+    /// (first above last) once all are closed
+    fn policy_open_epochs(&self, policy_id: U256) -> (U256, U256) {
+        let policy = self.policies.get(policy_id);
+        let length = self.accrual_epoch_length();
+        let first = (policy.start.get() / length).max(self.next_accrual_epoch.get(policy.pool_id.get()));
+        let last = (policy.expiry.get() - U256::from(1)) / length;
+        (first, last)
+    }
+
+    /// Credits premium that has been earned to a pool's reserves
+    fn recognize_premium(&mut self, pool_id: FixedBytes<32>, amount: U256) -> Result<(), Error> {
+        if amount == U256::ZERO {
+            return Ok(());
+        }
+        self.earned_premium.insert(pool_id, self.earned_premium.get(pool_id) + amount);
+        self.credit_reserves(pool_id, amount)?;
+        self.accrue_fee_growth(pool_id, amount)
+    }
+
+    /// Spreads fees collected for a pool over its recorded liquidity, flooring the growth so
+    /// the sum of all LP claims never exceeds the fees; while no liquidity has been recorded
+    /// the fees are left unattributed
+    fn accrue_fee_growth(&mut self, pool_id: FixedBytes<32>, fees: U256) -> Result<(), Error> {
+        let liquidity = self.fee_growth_liquidity.get(pool_id);
+        if fees == U256::ZERO || liquidity == U256::ZERO {
+            return Ok(());
+        }
+        let growth_delta = math::mul_div(fees, U256::from(WAD), liquidity)?;
+        let fee_growth_global = self.fee_growth_global.get(pool_id)
+            .checked_add(growth_delta)
+            .ok_or(Error::CalculationError(CalculationError{}))?;
+        self.fee_growth_global.insert(pool_id, fee_growth_global);
+        evm::log(LpFeesRecorded { pool_id, fees, fee_growth_global });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use stylus_sdk::{
+        alloy_primitives::{address, b256, Address, FixedBytes, U256, U8},
+        msg,
+    };
+
+    use super::{
+        Error, InsuranceCalculator, DEFAULT_ACCRUAL_EPOCH_LENGTH, IL_PRODUCT, POLICY_ACTIVE, POLICY_EXPIRED,
+        POLICY_TERM, WAD,
+    };
+
+    const POOL: FixedBytes<32> = FixedBytes::new([1; 32]);
+    const RECIPIENT: Address = address!("00000000000000000000000000000000000000a1");
+    const HOLDER: Address = address!("00000000000000000000000000000000000000a2");
+
+    thread_local! {
+        // Starts on an accrual epoch boundary (the default 30-day epoch)
+        static CLOCK: Cell<u64> = const { Cell::new(600 * DEFAULT_ACCRUAL_EPOCH_LENGTH) };
+    }
+
+    /// Block time as the contract sees it under test
+    pub(super) fn block_time() -> U256 {
+        U256::from(CLOCK.with(Cell::get))
+    }
+
+    /// Moves the test clock forward
+    fn warp(seconds: u64) {
+        CLOCK.with(|clock| clock.set(clock.get() + seconds));
+    }
+
+    /// Writes an active policy on `POOL` for the caller starting now, deferring its premium
+    /// over the term the way coverage issuance does
+    fn open_policy(contract: &mut InsuranceCalculator, policy_id: U256, premium: U256, term: U256) {
+        let (start, expiry) = (block_time(), block_time() + term);
+        let mut policy = contract.policies.setter(policy_id);
+        policy.holder.set(msg::sender());
+        policy.pool_id.set(POOL);
+        policy.notional.set(U256::from(WAD));
+        policy.coverage_fraction.set(U256::from(WAD));
+        policy.premium.set(premium);
+        policy.start.set(start);
+        policy.expiry.set(expiry);
+        policy.term.set(term);
+        policy.status.set(U8::from(POLICY_ACTIVE));
+        contract.defer_premium(policy_id, POOL, premium, start, expiry).unwrap();
+    }
+
+    /// Liquidity and volume renewals price against in place of a data provider
+    fn market(_: &InsuranceCalculator, _: FixedBytes<32>) -> Result<(U256, U256), Error> {
+        Ok((U256::from(WAD) * U256::from(1_000), U256::from(WAD) * U256::from(100)))
+    }
+
+    /// Opens a policy for `HOLDER` set to renew from a prepaid `balance`
+    fn open_renewing_policy(contract: &mut InsuranceCalculator, policy_id: U256, balance: U256) {
+        open_policy(contract, policy_id, U256::from(1_000), U256::from(POLICY_TERM));
+        let mut policy = contract.policies.setter(policy_id);
+        policy.holder.set(HOLDER);
+        policy.auto_renew.set(true);
+        policy.renewal_balance.set(balance);
+    }
+
+    #[motsu::test]
+    fn treasury_takes_the_floor_of_each_split(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        contract.set_protocol_fee_bps(U256::from(333)).unwrap();
+
+        // 3.33% of 1,001 wei is 33.33: the treasury gets 33, reserves the other 968
+        let split = contract.take_protocol_fee(U256::from(1_001)).unwrap();
+        assert_eq!(split, (U256::from(968), U256::from(33)));
+        // A dust premium rounds the treasury share to nothing
+        let split = contract.take_protocol_fee(U256::from(30)).unwrap();
+        assert_eq!(split, (U256::from(30), U256::ZERO));
+
+        contract.split_premium(POOL, U256::from(1_001)).unwrap();
+        assert_eq!(contract.treasury_balance(), U256::from(66));
+        assert_eq!(contract.get_pool_reserves(POOL), U256::from(968));
+
+        // Raising the rate leaves what has already accrued alone
+        contract.set_protocol_fee_bps(U256::from(5_000)).unwrap();
+        assert_eq!(contract.treasury_balance(), U256::from(66));
+    }
+
+    #[motsu::test]
+    fn treasury_withdraws_its_balance(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        contract.set_protocol_fee_bps(U256::from(5_000)).unwrap();
+        contract.set_treasury(msg::sender()).unwrap();
+        contract.split_premium(POOL, U256::from(1_000)).unwrap();
+
+        contract.withdraw_treasury(RECIPIENT, U256::from(200)).unwrap();
+        assert_eq!(contract.treasury_balance(), U256::from(300));
+        assert!(matches!(
+            contract.withdraw_treasury(RECIPIENT, U256::from(301)),
+            Err(Error::InsufficientBalance(_))
+        ));
+        assert!(matches!(
+            contract.withdraw_treasury(Address::ZERO, U256::from(1)),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[motsu::test]
+    fn only_the_treasury_withdraws(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        contract.set_protocol_fee_bps(U256::from(5_000)).unwrap();
+        contract.set_treasury(RECIPIENT).unwrap();
+        contract.split_premium(POOL, U256::from(1_000)).unwrap();
+
+        assert!(matches!(
+            contract.withdraw_treasury(RECIPIENT, U256::from(1)),
+            Err(Error::Unauthorized(_))
+        ));
+        assert_eq!(contract.treasury_balance(), U256::from(500));
+    }
+
+    #[motsu::test]
+    fn lp_claims_never_exceed_collected_fees(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        // 100 wei across LPs of 1, 2 and 4 units: exact shares are 14.29, 28.57 and 57.14
+        let lps = [1, 2, 4].map(U256::from);
+        let total = U256::from(7);
+        let fees = U256::from(100);
+        contract.record_lp_fees(POOL, fees, total).unwrap();
+
+        let claims = lps.map(|lp| contract.claimable_fees(POOL, lp, U256::ZERO).unwrap());
+        assert_eq!(claims, [14, 28, 57].map(U256::from));
+        let shares = lps.map(|lp| contract.calculate_lp_share(POOL, lp, total, fees).unwrap());
+        assert_eq!(shares, claims);
+        assert!(claims.into_iter().sum::<U256>() <= fees);
+    }
+
+    #[motsu::test]
+    fn collected_premiums_advance_fee_growth(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        // Before any liquidity is recorded a premium is left unattributed
+        contract.split_premium(POOL, U256::from(1_000)).unwrap();
+        assert_eq!(contract.get_fee_growth_global(POOL), U256::ZERO);
+
+        contract.record_lp_fees(POOL, U256::ZERO, U256::from(3)).unwrap();
+        contract.split_premium(POOL, U256::from(1_000)).unwrap();
+        // Each unit's growth is floored, so the whole pool can claim 999 of the 1,000 wei
+        assert_eq!(contract.claimable_fees(POOL, U256::from(3), U256::ZERO).unwrap(), U256::from(999));
+
+        let checkpoint = contract.get_fee_growth_global(POOL);
+        contract.recognize_premium(POOL, U256::from(3)).unwrap();
+        assert_eq!(contract.claimable_fees(POOL, U256::from(1), checkpoint).unwrap(), U256::from(1));
+    }
+
+    #[motsu::test]
+    fn pool_ids_match_uniswap_v3_salts(contract: InsuranceCalculator) {
+        let usdc = address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let weth = address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2");
+        let dai = address!("6B175474E89094C44Da98b954EedeAC495271d0F");
+        // Each salt was computed off-chain with the ethers expression in `compute_pool_id`'s doc
+        // and checked by deriving the mainnet pool's CREATE2 address from it: USDC/WETH 0.05%
+        // at 0x88e6A0c2..., USDC/WETH 0.3% at 0x8ad599c3... and DAI/USDC 0.01% at 0x5777d92f...
+        let vectors = [
+            (usdc, weth, 500, b256!("08374668a423750b443f65d645c5693995d43722b42cd84f7eeba28b008a40a2")),
+            (usdc, weth, 3_000, b256!("e5296716c6b51f594ae537f62d559270eb10202d1f875c450468fc756ecd0ee9")),
+            (dai, usdc, 100, b256!("57e3e781d6dcb1a2575e4e959e576f8bebd87b35fb2feddb9cb41b6c63a235b3")),
+        ];
+        for (token0, token1, fee_tier, pool_id) in vectors {
+            let fee_tier = U256::from(fee_tier);
+            assert_eq!(contract.compute_pool_id(token0, token1, fee_tier).unwrap(), pool_id);
+            assert_eq!(contract.compute_pool_id(token1, token0, fee_tier).unwrap(), pool_id);
+        }
+
+        assert!(matches!(contract.compute_pool_id(usdc, usdc, U256::from(500)), Err(Error::InvalidInput(_))));
+        assert!(matches!(
+            contract.compute_pool_id(Address::ZERO, weth, U256::from(500)),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+
+    #[motsu::test]
+    fn pools_register_under_opaque_ids(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        let token0 = address!("00000000000000000000000000000000000000b0");
+        let token1 = address!("00000000000000000000000000000000000000b1");
+        // An id that is not the tokens' v3 salt, as a v4 `PoolKey` hash would be
+        assert_ne!(contract.compute_pool_id(token0, token1, U256::from(500)).unwrap(), POOL);
+        contract.register_pool(POOL, token0, token1, U256::from(500)).unwrap();
+        assert!(contract.get_pool_metadata(POOL).3);
+    }
+
+    #[motsu::test]
+    fn pools_register_once(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        let token0 = address!("00000000000000000000000000000000000000b0");
+        let token1 = address!("00000000000000000000000000000000000000b1");
+        assert!(matches!(
+            contract.register_pool(POOL, Address::ZERO, token1, U256::from(500)),
+            Err(Error::InvalidInput(_))
+        ));
+
+        contract.register_pool(POOL, token0, token1, U256::from(500)).unwrap();
+        assert!(matches!(
+            contract.register_pool(POOL, token1, token0, U256::from(3_000)),
+            Err(Error::PoolAlreadyRegistered(_))
+        ));
+        // The first registration's metadata stands
+        let (stored0, stored1, fee_tier, registered, _) = contract.get_pool_metadata(POOL);
+        assert_eq!((stored0, stored1, fee_tier, registered), (token0, token1, U256::from(500), true));
+        assert_eq!(contract.pool_count(), U256::from(1));
+    }
+
+    #[motsu::test]
+    fn unregistered_pools_quote_only_until_registration_is_required(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        let (token0, token1, fee_tier, registered, _) = contract.get_pool_metadata(POOL);
+        assert_eq!((token0, token1, fee_tier, registered), (Address::ZERO, Address::ZERO, U256::ZERO, false));
+        let unregistered = |contract: &InsuranceCalculator| {
+            let (liquidity, volume) = (U256::from(WAD) * U256::from(1_000), U256::from(WAD) * U256::from(100));
+            matches!(
+                contract.quote_insurance_fee(POOL, U256::from(WAD), liquidity, volume),
+                Err(Error::PoolNotRegistered(_))
+            )
+        };
+        assert!(!unregistered(&contract));
+
+        contract.set_require_registration(true).unwrap();
+        assert!(unregistered(&contract));
+
+        contract
+            .register_pool(POOL, address!("00000000000000000000000000000000000000b0"), RECIPIENT, U256::from(500))
+            .unwrap();
+        assert!(!unregistered(&contract));
+    }
+
+    #[motsu::test]
+    fn premium_is_earned_epoch_by_epoch(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        let epoch = DEFAULT_ACCRUAL_EPOCH_LENGTH;
+        let first = block_time() / U256::from(epoch);
+        let policy_id = U256::from(1);
+        let premium = U256::from(1_000);
+        // Exactly three epochs: 333 and 333, with the last taking the 334 remainder
+        open_policy(&mut contract, policy_id, premium, U256::from(3 * epoch));
+        assert_eq!(contract.get_premium_accrual(POOL), (U256::ZERO, premium, first));
+        assert_eq!(contract.get_pool_reserves(POOL), U256::ZERO);
+
+        warp(epoch);
+        assert_eq!(contract.close_epoch(POOL).unwrap(), U256::from(333));
+        assert_eq!(contract.get_premium_accrual(POOL), (U256::from(333), U256::from(667), first + U256::from(1)));
+
+        warp(epoch);
+        assert_eq!(contract.close_epoch(POOL).unwrap(), U256::from(333));
+        assert_eq!(contract.get_premium_accrual(POOL), (U256::from(666), U256::from(334), first + U256::from(2)));
+
+        // A third of the term is left, so 333 is refunded from the last epoch's 334 and the
+        // remaining wei is earned
+        let refund = contract.cancel_policy(policy_id).unwrap();
+        assert_eq!(refund, U256::from(333));
+        let (earned, unearned, next_epoch) = contract.get_premium_accrual(POOL);
+        assert_eq!((earned, unearned, next_epoch), (U256::from(667), U256::ZERO, first + U256::from(2)));
+        assert_eq!(earned + unearned + refund, premium);
+        assert_eq!(contract.get_pool_reserves(POOL), earned);
+        assert_eq!(contract.get_refund_balance(msg::sender()), refund);
+        assert_eq!(contract.total_unearned_premium.get(), U256::ZERO);
+        assert_eq!(contract.epoch_premium.get(POOL).get(next_epoch), U256::ZERO);
+        assert_eq!(contract.policy_epoch_premium.get(policy_id).get(next_epoch), U256::ZERO);
+    }
+
+    #[motsu::test]
+    fn keepers_renew_at_the_holders_price(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        // The keeper (the test caller) has earned a 50% loyalty discount; the holder has not
+        contract.set_loyalty_tiers(vec![U256::from(1)], vec![U256::from(5_000)]).unwrap();
+        contract.insured_volume.insert(msg::sender(), U256::from(WAD));
+        let policy_id = U256::from(1);
+        let balance = U256::from(WAD);
+        open_renewing_policy(&mut contract, policy_id, balance);
+
+        let (liquidity, volume) = market(&contract, POOL).unwrap();
+        let price_for = |contract: &InsuranceCalculator, trader| {
+            let (notional, fraction, term) = (U256::from(WAD), U256::from(WAD), U256::from(POLICY_TERM));
+            let (deductible, coinsurance) = (U256::ZERO, U256::ZERO);
+            contract
+                .price_coverage_premium(
+                    POOL, IL_PRODUCT, trader, notional, fraction, term, deductible, coinsurance, liquidity, volume,
+                )
+                .unwrap()
+        };
+        let holder_price = price_for(&contract, HOLDER);
+        assert!(price_for(&contract, msg::sender()) < holder_price);
+
+        warp(POLICY_TERM + 1);
+        let premium = contract.process_renewal_with(policy_id, market).unwrap();
+        assert_eq!(premium, holder_price);
+        assert_eq!(contract.get_renewal_status(policy_id), (true, balance - premium, U256::from(POLICY_TERM)));
+        let policy = contract.policies.get(policy_id);
+        assert_eq!(policy.expiry.get(), block_time() + U256::from(POLICY_TERM));
+        assert_eq!(policy.status.get(), U8::from(POLICY_ACTIVE));
+    }
+
+    #[motsu::test]
+    fn renewals_lapse_when_the_balance_falls_short(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        let policy_id = U256::from(1);
+        open_renewing_policy(&mut contract, policy_id, U256::from(1));
+
+        warp(POLICY_TERM + 1);
+        assert_eq!(contract.process_renewal_with(policy_id, market).unwrap(), U256::ZERO);
+        let policy = contract.policies.get(policy_id);
+        assert_eq!(policy.status.get(), U8::from(POLICY_EXPIRED));
+        // The balance stays withdrawable and nothing renews it again
+        assert_eq!(policy.renewal_balance.get(), U256::from(1));
+        assert!(matches!(
+            contract.process_renewal_with(policy_id, market),
+            Err(Error::PolicyNotActive(_))
+        ));
+    }
+
+    #[motsu::test]
+    fn holders_switch_off_auto_renew(contract: InsuranceCalculator) {
+        contract.initialize().unwrap();
+        let policy_id = U256::from(1);
+        open_renewing_policy(&mut contract, policy_id, U256::from(WAD));
+        // Only the holder may flip it
+        assert!(matches!(contract.set_auto_renew(policy_id, false), Err(Error::Unauthorized(_))));
+        contract.policies.setter(policy_id).holder.set(msg::sender());
+        contract.set_auto_renew(policy_id, false).unwrap();
+
+        warp(POLICY_TERM + 1);
+        assert!(matches!(
+            contract.process_renewal_with(policy_id, market),
+            Err(Error::PolicyNotActive(_))
+        ));
+        assert_eq!(contract.get_renewal_status(policy_id), (false, U256::from(WAD), U256::from(POLICY_TERM)));
     }
 }
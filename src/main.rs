@@ -1,6 +1,21 @@
 #![cfg_attr(not(feature = "export-abi"), no_main)]
 
+/// Where the full interface is written, so Solidity callers can import it instead of
+/// hand-writing error and event definitions
+#[cfg(feature = "export-abi")]
+const INTERFACE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/abi/IInsuranceCalculator.sol");
+
 #[cfg(feature = "export-abi")]
 fn main() {
+    use stylus_insurance_calculator::interface::INTERFACE_SOURCE;
+
     stylus_insurance_calculator::print_abi("MIT-OR-APACHE-2.0", "pragma solidity ^0.8.23;");
-}
\ No newline at end of file
+    println!();
+    print!("{}", INTERFACE_SOURCE);
+
+    let path = std::path::Path::new(INTERFACE_PATH);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).expect("failed to create ABI output directory");
+    }
+    std::fs::write(path, INTERFACE_SOURCE).expect("failed to write interface");
+}
@@ -0,0 +1,153 @@
+//! Fixed-point helpers shared by the fee calculators
+
+use stylus_sdk::alloy_primitives::{Uint, I256, U256};
+
+use crate::{CalculationError, Error, InvalidInput, MAX_DECIMALS, RAY, WAD};
+
+/// Ratio between RAY (1e27) and WAD (1e18)
+const RAY_PER_WAD: u64 = 1_000_000_000;
+
+/// Computes `a * b / denominator` rounded down, with a 512-bit intermediate product
+pub(crate) fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256, Error> {
+    if denominator == U256::ZERO {
+        return Err(Error::CalculationError(CalculationError{}));
+    }
+    let product: Uint<512, 8> = a.widening_mul(b);
+    let quotient = product / Uint::<512, 8>::from(denominator);
+    U256::checked_from_limbs_slice(quotient.as_limbs()).ok_or(Error::CalculationError(CalculationError{}))
+}
+
+/// Computes `a * b / denominator` rounded up, with a 512-bit intermediate product
+pub(crate) fn mul_div_up(a: U256, b: U256, denominator: U256) -> Result<U256, Error> {
+    let quotient = mul_div(a, b, denominator)?;
+    if a.mul_mod(b, denominator) == U256::ZERO {
+        return Ok(quotient);
+    }
+    quotient
+        .checked_add(U256::from(1))
+        .ok_or(Error::CalculationError(CalculationError{}))
+}
+
+/// Computes `a * b / denominator` rounded down, saturating at `U256::MAX` (as does a zero
+/// denominator) and reporting whether it did
+pub(crate) fn saturating_mul_div(a: U256, b: U256, denominator: U256) -> (U256, bool) {
+    match mul_div(a, b, denominator) {
+        Ok(value) => (value, false),
+        Err(_) => (U256::MAX, true),
+    }
+}
+
+/// Computes `a - b` as a signed value, rejecting operands above `I256::MAX`
+pub(crate) fn signed_sub(a: U256, b: U256) -> Result<I256, Error> {
+    let a = I256::try_from(a).map_err(|_| Error::CalculationError(CalculationError{}))?;
+    let b = I256::try_from(b).map_err(|_| Error::CalculationError(CalculationError{}))?;
+    a.checked_sub(b).ok_or(Error::CalculationError(CalculationError{}))
+}
+
+/// Magnitude of a signed value; `I256::MIN` still fits in a `U256`
+pub(crate) fn abs(value: I256) -> U256 {
+    value.unsigned_abs()
+}
+
+/// Converts a signed value to unsigned, rejecting negatives instead of wrapping them
+pub(crate) fn to_unsigned(value: I256) -> Result<U256, Error> {
+    if value.is_negative() {
+        return Err(Error::CalculationError(CalculationError{}));
+    }
+    Ok(value.into_raw())
+}
+
+/// Integer square root, rounded down
+pub(crate) fn sqrt(x: U256) -> U256 {
+    if x < U256::from(2) {
+        return x;
+    }
+    // Newton's method from an initial guess above the root; strictly decreases until it converges
+    let mut guess = U256::from(1) << ((x.bit_len() + 1) / 2);
+    loop {
+        let next = (guess + x / guess) >> 1;
+        if next >= guess {
+            return guess;
+        }
+        guess = next;
+    }
+}
+
+/// Square root of a WAD value, returned in WAD
+pub(crate) fn wad_sqrt(x: U256) -> Result<U256, Error> {
+    let scaled = x
+        .checked_mul(U256::from(WAD))
+        .ok_or(Error::CalculationError(CalculationError{}))?;
+    Ok(sqrt(scaled))
+}
+
+/// Lifts a WAD value to RAY
+pub(crate) fn wad_to_ray(value: U256) -> Result<U256, Error> {
+    value
+        .checked_mul(U256::from(RAY_PER_WAD))
+        .ok_or(Error::CalculationError(CalculationError{}))
+}
+
+/// Builds the RAY multiplier `1 + x * weight` from a WAD input and a WAD weight
+pub(crate) fn ray_multiplier(x: U256, weight: U256) -> Result<U256, Error> {
+    mul_div(wad_to_ray(x)?, weight, U256::from(WAD))?
+        .checked_add(U256::from(RAY))
+        .ok_or(Error::CalculationError(CalculationError{}))
+}
+
+/// Builds the RAY form of the insurance formula's IL multiplier, `1e18 + il * weight` in WAD
+///
+/// The IL × weight product is added to the baseline without rescaling, exactly as the formula
+/// has always priced it; only the representation moves to RAY.
+pub(crate) fn il_ray_multiplier(il: U256, weight: U256) -> Result<U256, Error> {
+    il.checked_mul(weight)
+        .and_then(|scaled| scaled.checked_add(U256::from(WAD)))
+        .ok_or(Error::CalculationError(CalculationError{}))
+        .and_then(wad_to_ray)
+}
+
+/// Returns the median of a non-empty set, averaging the middle pair (rounding down) when even
+pub(crate) fn median(values: &mut [U256]) -> U256 {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        return values[mid];
+    }
+    // Sorted, so the upper middle is never below the lower one
+    values[mid - 1] + (values[mid] - values[mid - 1]) / U256::from(2)
+}
+
+/// Drops a RAY value to WAD, rounding down
+pub(crate) fn ray_to_wad(value: U256) -> U256 {
+    value / U256::from(RAY_PER_WAD)
+}
+
+/// Rescales a value with `decimals` decimals to WAD, rounding down when precision is dropped
+pub(crate) fn to_wad(value: U256, decimals: u8) -> Result<U256, Error> {
+    rescale(value, decimals, 18)
+}
+
+/// Rescales a WAD value to `decimals` decimals, rounding up when precision is dropped
+pub(crate) fn from_wad_up(value: U256, decimals: u8) -> Result<U256, Error> {
+    let scaled = rescale(value, 18, decimals)?;
+    if decimals >= 18 || rescale(scaled, decimals, 18)? == value {
+        return Ok(scaled);
+    }
+    scaled
+        .checked_add(U256::from(1))
+        .ok_or(Error::CalculationError(CalculationError{}))
+}
+
+/// Moves a value between decimal bases, rejecting bases above `MAX_DECIMALS`
+fn rescale(value: U256, from: u8, to: u8) -> Result<U256, Error> {
+    if from > MAX_DECIMALS || to > MAX_DECIMALS {
+        return Err(Error::InvalidInput(InvalidInput{}));
+    }
+    if from <= to {
+        value
+            .checked_mul(U256::from(10).pow(U256::from(to - from)))
+            .ok_or(Error::CalculationError(CalculationError{}))
+    } else {
+        Ok(value / U256::from(10).pow(U256::from(from - to)))
+    }
+}